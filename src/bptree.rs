@@ -1,4 +1,6 @@
-use crate::{LenType, RrError};
+use std::ops::Bound;
+
+use crate::{BPTreeCursor, LenType, RrError};
 
 /// b+ tree(binary plus tree)
 pub trait BPTree<T> {
@@ -21,7 +23,21 @@ pub trait BPTree<T> {
     /// 读取最后一个值（最小值）
     fn get_last(&self, t: &T, key: &[u8]) -> Result<Option<(Vec<u8>, Vec<u8>)>, RrError>;
     fn get(&self, t: &T, key: &[u8], field: &[u8]) -> Result<Option<Vec<u8>>, RrError>;
+    /// 按field从小到大，返回从start_field到end_field的值，limit为0时不限制个数
+    /// 返回值 0: field, 1: field value
+    fn range(&self, t: &T, key: &[u8], start_field: Bound<&[u8]>, end_field: Bound<&[u8]>, limit: usize) -> Result<Vec<(Vec<u8>, Vec<u8>)>, RrError>;
+    /// 按field从大到小，返回从start_field(较大)到end_field(较小)的值，limit为0时不限制个数
+    fn range_rev(&self, t: &T, key: &[u8], start_field: Bound<&[u8]>, end_field: Bound<&[u8]>, limit: usize) -> Result<Vec<(Vec<u8>, Vec<u8>)>, RrError>;
+    /// 返回一个游标，按field的顺序分批读取，rev为true时从大到小
+    fn cursor(&self, key: &[u8], start_field: Bound<&[u8]>, end_field: Bound<&[u8]>, rev: bool) -> BPTreeCursor;
     fn len(&self, t: &T, key: &[u8]) -> Result<Option<LenType>, RrError>;
+    /// field的排名，从0开始，不存在时返回None
+    fn rank(&self, t: &T, key: &[u8], field: &[u8]) -> Result<Option<LenType>, RrError>;
+    /// 排名为n(从0开始)的值
+    /// 返回值 0: field, 1: field value
+    fn select(&self, t: &T, key: &[u8], n: LenType) -> Result<Option<(Vec<u8>, Vec<u8>)>, RrError>;
+    /// field在lo与hi之间的个数
+    fn count(&self, t: &T, key: &[u8], lo: Bound<&[u8]>, hi: Bound<&[u8]>) -> Result<LenType, RrError>;
 
     /// 删除指定的key，及所有字段
     fn del_key(&self, t: &T, key: &[u8]) -> Result<(), RrError>;
@@ -1,11 +1,6 @@
-use std::{
-    marker::PhantomData,
-    mem,
-    mem::{size_of, ManuallyDrop},
-    ptr,
-};
+use std::{cmp::Ordering, marker::PhantomData, mem::size_of};
 
-use crate::{read_int_ptr, write_int_ptr, BytesType, LenType, MetaKey};
+use crate::{read_int, write_int, BytesType, LenType};
 
 #[derive(Debug, Clone)]
 pub struct BytesMeta {
@@ -38,6 +33,8 @@ impl From<usize> for BytesMeta {
 
 /// 前面存放meta信息，所key的offset(相对于第一个key的位置，这样当增加meta时，原meta中的offset不用修改)，key
 /// 这样设计是为了使用二分查找，因为key长度不固定
+/// 布局： [number_keys][bytes_data][metas_capacity][metas...][key: (len, bytes, len)...]
+/// VecBytes必须放在数据的最后，增加key时直接追加在数据的尾部
 #[derive(Clone, Debug)]
 pub struct VecBytes<T: Metas> {
     pub number_keys: LenType,
@@ -59,8 +56,6 @@ impl<T: Metas> VecBytes<T> {
     pub const ONE_EXPAND: isize = 64 * (size_of::<BytesMeta>() as isize);
 
     pub fn new() -> Self {
-        // Vec::new()
-        // core::marker::PhantomData
         Self {
             number_keys: 0,
             bytes_data: 0,
@@ -70,69 +65,78 @@ impl<T: Metas> VecBytes<T> {
         }
     }
 
-    /// keys已排序
+    /// keys已排序, 从offset开始写入，原offset之后的数据会被丢弃
     pub fn add(data: &mut Vec<u8>, offset: isize, keys_: &[&[u8]]) -> Self {
         let mut keys = VecBytes::new();
-        keys.number_keys = keys_.len() as LenType;
         keys.offset = offset;
-        if keys.number_keys as usize * size_of::<BytesMeta>() >= keys.metas_capacity as usize {
-            keys.expand(data);
-        }
+        let metas = keys_.len() * size_of::<BytesMeta>();
+        let expand = Self::ONE_EXPAND as usize;
+        keys.metas_capacity = (metas.div_ceil(expand) * expand) as u64;
+        data.truncate(offset as usize);
+        data.resize(offset as usize + Self::OFFSET_META as usize + keys.metas_capacity as usize, 0);
+        keys.write_metas_capacity(data);
 
-        for index in 0..keys_.len() {
-            keys.insert_with_index(data, keys_[index], Some(index as usize));
+        let mut bytes_data = 0 as BytesType;
+        for (index, key) in keys_.iter().enumerate() {
+            keys.write_meta(data, index, &BytesMeta::new(bytes_data as isize));
+            Self::push_key(data, key);
+            bytes_data += Self::compute_new_bytes(key);
         }
+        keys.set_number_keys(keys_.len() as LenType, data);
+        keys.set_bytes_data(bytes_data, data);
         keys
     }
 
     pub fn read_from(&mut self, data: &[u8], offset: isize) {
         self.offset = offset;
-        unsafe {
-            self.number_keys = read_int_ptr(data.as_ptr().offset(self.offset));
-            self.bytes_data = read_int_ptr(data.as_ptr().offset(self.offset + size_of::<LenType>() as isize));
-            self.metas_capacity = read_int_ptr(data.as_ptr().offset(Self::OFFSET_DATA));
-        }
+        let offset = offset as usize;
+        self.number_keys = read_int(&data[offset..]);
+        self.bytes_data = read_int(&data[offset + size_of::<LenType>()..]);
+        self.metas_capacity = read_int(&data[offset + Self::OFFSET_DATA as usize..]);
+    }
+
+    pub fn len(&self) -> usize {
+        self.number_keys as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.number_keys == 0
+    }
+
+    /// 返回index处的key
+    pub fn get<'a>(&self, data: &'a [u8], index: usize) -> &'a [u8] {
+        let start = self.key_offset(data, index);
+        let len = read_int::<BytesType>(&data[start..]) as usize;
+        &data[start + size_of::<BytesType>()..start + size_of::<BytesType>() + len]
+    }
+
+    /// 按顺序返回所有的key
+    pub fn keys<'a>(&self, data: &'a [u8]) -> Vec<&'a [u8]> {
+        (0..self.len()).map(|i| self.get(data, i)).collect()
     }
 
     /// 在index处插入key,
     pub fn insert_with_index(&mut self, data: &mut Vec<u8>, key: &[u8], index: Option<usize>) {
+        let index = match index {
+            Some(i) => i,
+            None => match self.binary_search(data, key) {
+                Ok(i) => i,
+                Err(i) => i,
+            },
+        };
         if self.is_expand() {
             self.expand(data);
         }
-        let add_bytes = Self::compute_new_bytes(key);
-        data.reserve_exact(add_bytes as usize);
-
-        let start = self.offset + Self::OFFSET_DATA + self.metas_capacity as isize + self.bytes_data as isize;
-        unsafe {
-            write_int_ptr(data.as_mut_ptr().offset(start), key.len() as BytesType);
-            write_int_ptr(
-                data.as_mut_ptr().offset(start + size_of::<BytesType>() as isize + key.len() as isize),
-                key.len() as BytesType,
-            );
-            ptr::copy(key.as_ptr(), data.as_mut_ptr().offset(start + size_of::<BytesType>() as isize), key.len());
-        }
+        let meta = BytesMeta::from(data.len() - self.data_offset());
+        Self::push_key(data, key);
+
+        let start = self.meta_offset(index);
+        let end = self.meta_offset(self.len());
+        data.copy_within(start..end, start + size_of::<BytesMeta>());
+        self.write_meta(data, index, &meta);
 
-        {
-            let mut meta = T::new(
-                data,
-                Self::OFFSET_META,
-                self.number_keys,
-                (self.metas_capacity as usize / size_of::<MetaKey>()) as i64,
-            );
-            let index = match index {
-                Some(i) => i,
-                None => match meta.search(data.as_slice(), self.offset + Self::OFFSET_META + self.metas_capacity as isize, key) {
-                    Ok(i) => i,
-                    Err(i) => i,
-                },
-            };
-            meta.insert(index, BytesMeta::from(start))
-        }
-        unsafe {
-            data.set_len(data.len() + add_bytes as usize);
-        }
         self.set_number_keys(self.number_keys + 1 as LenType, data);
-        self.set_bytes_data(self.bytes_data + add_bytes as BytesType, data);
+        self.set_bytes_data(self.bytes_data + Self::compute_new_bytes(key), data);
     }
     /// offset keys中node中的偏移量，
     pub fn inserts(&mut self, data: &mut Vec<u8>, keys: &[&[u8]]) {
@@ -141,33 +145,98 @@ impl<T: Metas> VecBytes<T> {
         }
     }
 
+    /// 删除index处的key，并返回它
+    pub fn remove(&mut self, data: &mut Vec<u8>, index: usize) -> Vec<u8> {
+        let key = self.get(data, index).to_vec();
+        let removed_meta = self.read_meta(data, index);
+        let removed = Self::compute_new_bytes(&key);
+        let start = self.data_offset() + removed_meta.to_isize() as usize;
+        data.drain(start..start + removed as usize);
+
+        let end = self.meta_offset(self.len());
+        data.copy_within(self.meta_offset(index + 1)..end, self.meta_offset(index));
+        self.set_number_keys(self.number_keys - 1, data);
+        for i in 0..self.len() {
+            let mut meta = self.read_meta(data, i);
+            if meta.to_isize() > removed_meta.to_isize() {
+                meta.set_offset(meta.to_isize() - removed as isize);
+                self.write_meta(data, i, &meta);
+            }
+        }
+        self.set_bytes_data(self.bytes_data - removed, data);
+        if self.metas_capacity as isize - (self.len() * size_of::<BytesMeta>()) as isize >= 2 * Self::ONE_EXPAND {
+            self.reduce(data);
+        }
+        key
+    }
+
+    /// 替换index处的key，替换后需要保证key依然有序
+    pub fn set(&mut self, data: &mut Vec<u8>, index: usize, key: &[u8]) {
+        self.remove(data, index);
+        self.insert_with_index(data, key, Some(index));
+    }
+
     pub fn set_number_keys(&mut self, number_keys: LenType, data: &mut [u8]) {
         self.number_keys = number_keys;
-        unsafe {
-            write_int_ptr(data.as_mut_ptr().offset(self.offset), self.number_keys);
-        }
+        write_int(&mut data[self.offset as usize..], self.number_keys);
     }
     pub fn set_bytes_data(&mut self, bytes_number: BytesType, data: &mut [u8]) {
         self.bytes_data = bytes_number;
-        unsafe {
-            write_int_ptr(data.as_mut_ptr().offset(self.offset + size_of::<LenType>() as isize), self.bytes_data);
-        }
+        write_int(&mut data[self.offset as usize + size_of::<LenType>()..], self.bytes_data);
     }
 
     pub fn binary_search(&self, data: &[u8], key: &[u8]) -> Result<usize, usize> {
-        let meta = T::new(
-            data,
-            Self::OFFSET_META,
-            self.number_keys,
-            (self.metas_capacity as usize / size_of::<MetaKey>()) as i64,
-        );
-        meta.search(data, self.offset + Self::OFFSET_META + self.metas_capacity as isize, key)
+        let mut low = 0;
+        let mut high = self.len();
+        while low < high {
+            let mid = low + (high - low) / 2;
+            match T::compare(self.get(data, mid), key) {
+                Ordering::Less => low = mid + 1,
+                Ordering::Greater => high = mid,
+                Ordering::Equal => return Ok(mid),
+            }
+        }
+        Err(low)
     }
 
     fn compute_new_bytes(key: &[u8]) -> BytesType {
         size_of::<BytesType>() as BytesType * 2 + key.len() as BytesType
     }
 
+    fn push_key(data: &mut Vec<u8>, key: &[u8]) {
+        let mut len = [0u8; size_of::<BytesType>()];
+        write_int(len.as_mut_slice(), key.len() as BytesType);
+        data.extend_from_slice(&len);
+        data.extend_from_slice(key);
+        data.extend_from_slice(&len);
+    }
+
+    fn meta_offset(&self, index: usize) -> usize {
+        self.offset as usize + Self::OFFSET_META as usize + index * size_of::<BytesMeta>()
+    }
+
+    fn data_offset(&self) -> usize {
+        self.offset as usize + Self::OFFSET_META as usize + self.metas_capacity as usize
+    }
+
+    fn key_offset(&self, data: &[u8], index: usize) -> usize {
+        self.data_offset() + self.read_meta(data, index).to_isize() as usize
+    }
+
+    fn read_meta(&self, data: &[u8], index: usize) -> BytesMeta {
+        BytesMeta {
+            offset: read_int(&data[self.meta_offset(index)..]),
+        }
+    }
+
+    fn write_meta(&self, data: &mut [u8], index: usize, meta: &BytesMeta) {
+        write_int(&mut data[self.meta_offset(index)..], meta.offset);
+    }
+
+    fn write_metas_capacity(&self, data: &mut [u8]) {
+        write_int(&mut data[self.offset as usize + Self::OFFSET_DATA as usize..], self.metas_capacity);
+    }
+
     /// 是否需要扩展meta的空间
     fn is_expand(&self) -> bool {
         if self.number_keys as usize * size_of::<BytesMeta>() >= self.metas_capacity as usize {
@@ -176,117 +245,77 @@ impl<T: Metas> VecBytes<T> {
         return false;
     }
     fn expand(&mut self, data: &mut Vec<u8>) {
-        let expand_size = Self::ONE_EXPAND;
-        data.reserve_exact(expand_size as usize);
-        unsafe {
-            data.set_len(data.len() + expand_size as usize);
-        }
-        let old_metas_capacity = self.metas_capacity as isize;
-        unsafe {
-            let p_data = data.as_mut_ptr().offset(Self::OFFSET_META + old_metas_capacity);
-            ptr::copy(
-                p_data,
-                p_data.offset(expand_size as isize),
-                data.len() - expand_size as usize - Self::OFFSET_META as usize - old_metas_capacity as usize,
-            );
-            write_int_ptr(
-                data.as_mut_ptr().offset(size_of::<LenType>() as isize),
-                old_metas_capacity as LenType + expand_size as LenType,
-            );
-        };
-        self.metas_capacity = old_metas_capacity as u64 + expand_size as u64;
+        let start = self.data_offset();
+        data.splice(start..start, std::iter::repeat_n(0, Self::ONE_EXPAND as usize));
+        self.metas_capacity += Self::ONE_EXPAND as u64;
+        self.write_metas_capacity(data);
     }
 
     fn reduce(&mut self, data: &mut Vec<u8>) {
-        let reduce_size = Self::ONE_EXPAND as isize;
-        let mut temp_fields = Vec::<u8>::with_capacity(data.len() - self.metas_capacity as usize - Self::OFFSET_META as usize);
-
-        let mut head_array = unsafe {
-            Vec::from_raw_parts(
-                data.as_mut_ptr().offset(Self::OFFSET_META as isize) as *mut BytesMeta,
-                self.number_keys as usize,
-                self.metas_capacity as usize / mem::size_of::<BytesMeta>(),
-            )
-        };
-        let mut offset = 0;
-        let p_data = unsafe { data.as_ptr().offset(Self::OFFSET_META + self.metas_capacity as isize) };
-        for field_meta in &mut head_array {
-            let start = field_meta.offset as isize;
-            let field_size = unsafe { read_int_ptr::<BytesType>(p_data.offset(start)) };
-            unsafe {
-                ptr::copy_nonoverlapping(
-                    p_data.offset(start),
-                    temp_fields.as_mut_ptr().offset(offset),
-                    field_size as usize + size_of::<BytesType>(),
-                );
-            }
-            field_meta.set_offset(offset);
-            offset += field_size as isize + size_of::<BytesType>() as isize;
-            unsafe {
-                temp_fields.set_len(offset as usize);
-            }
-        }
-        let _ = ManuallyDrop::new(head_array);
-        self.metas_capacity -= reduce_size as u64;
-        unsafe {
-            temp_fields.set_len(offset as usize);
-            write_int_ptr(data.as_mut_ptr().offset(size_of::<LenType>() as isize), self.metas_capacity as LenType);
-            ptr::copy_nonoverlapping(
-                temp_fields.as_ptr(),
-                data.as_mut_ptr().offset(Self::OFFSET_META + self.metas_capacity as isize),
-                temp_fields.len(),
-            );
-            data.set_len((Self::OFFSET_META as usize + self.metas_capacity as usize + temp_fields.len()) as usize);
-        }
+        let end = self.data_offset();
+        data.drain(end - Self::ONE_EXPAND as usize..end);
+        self.metas_capacity -= Self::ONE_EXPAND as u64;
+        self.write_metas_capacity(data);
     }
 }
 
+/// 决定VecBytes中key的排序方式
 pub trait Metas {
-    fn new(data: &[u8], offset: isize, len: LenType, cap: i64) -> Self;
-    fn insert(&mut self, index: usize, key_meta: BytesMeta);
-    fn search(&self, data: &[u8], offset: isize, key: &[u8]) -> Result<usize, usize>;
+    /// 比较存放的key与要查找的key
+    fn compare(key: &[u8], other: &[u8]) -> Ordering;
 }
 
+/// 按字节序比较
 #[derive(Debug, Clone)]
-pub struct KeyMetas {
-    //这个数据是一个引用
-    data: Vec<BytesMeta>,
-}
-
-impl Drop for KeyMetas {
-    fn drop(&mut self) {
-        let data = mem::replace(&mut self.data, vec![]);
-        mem::forget(data);
-    }
-}
+pub struct KeyMetas {}
 
 impl Metas for KeyMetas {
-    fn new(data: &[u8], offset: isize, len: LenType, cap: i64) -> Self {
-        unsafe {
-            Self {
-                data: Vec::from_raw_parts(data.as_ptr().offset(offset) as *mut _, len as usize, cap as usize),
-            }
-        }
-    }
-    fn insert(&mut self, index: usize, key_meta: BytesMeta) {
-        self.data.insert(index, key_meta);
-    }
-
-    fn search(&self, data: &[u8], offset: isize, key: &[u8]) -> Result<usize, usize> {
-        unsafe {
-            self.data.binary_search_by(|a| {
-                let mut start = offset + a.to_isize();
-                let bytes_ = read_int_ptr::<BytesType>(data.as_ptr().offset(start));
-                start += size_of::<BytesType>() as isize;
-                let o_key = &data[start as usize..start as usize + bytes_ as usize];
-                return key.cmp(o_key);
-            })
-        }
+    fn compare(key: &[u8], other: &[u8]) -> Ordering {
+        key.cmp(other)
     }
 }
 
 #[cfg(test)]
 mod test {
+    use crate::datas::{KeyMetas, VecBytes};
+
     #[test]
-    fn test_vec() {}
+    fn test_vec() {
+        let offset = 3;
+        let mut data = vec![9u8; offset];
+        let mut keys = VecBytes::<KeyMetas>::add(&mut data, offset as isize, &[b"b".as_slice(), b"d"]);
+        assert_eq!(vec![b"b".as_slice(), b"d"], keys.keys(&data));
+
+        keys.insert_with_index(&mut data, b"c", None);
+        keys.insert_with_index(&mut data, b"a", None);
+        keys.insert_with_index(&mut data, b"e", None);
+        assert_eq!(vec![b"a".as_slice(), b"b", b"c", b"d", b"e"], keys.keys(&data));
+        assert_eq!(Ok(2), keys.binary_search(&data, b"c"));
+        assert_eq!(Err(3), keys.binary_search(&data, b"cc"));
+
+        assert_eq!(b"b".to_vec(), keys.remove(&mut data, 1));
+        keys.set(&mut data, 0, b"aa");
+        assert_eq!(vec![b"aa".as_slice(), b"c", b"d", b"e"], keys.keys(&data));
+
+        let mut read = VecBytes::<KeyMetas>::new();
+        read.read_from(&data, offset as isize);
+        assert_eq!(vec![b"aa".as_slice(), b"c", b"d", b"e"], read.keys(&data));
+        assert_eq!(&[9u8; 3], &data[..offset]);
+    }
+
+    #[test]
+    fn test_vec_expand() {
+        let mut data = vec![];
+        let mut keys = VecBytes::<KeyMetas>::add(&mut data, 0, &[]);
+        let all: Vec<Vec<u8>> = (0..300u32).map(|i| i.to_be_bytes().to_vec()).collect();
+        for k in all.iter().rev() {
+            keys.insert_with_index(&mut data, k, None);
+        }
+        assert_eq!(all.iter().map(|k| k.as_slice()).collect::<Vec<_>>(), keys.keys(&data));
+        for _ in 0..290 {
+            keys.remove(&mut data, 0);
+        }
+        assert_eq!(all[290..].iter().map(|k| k.as_slice()).collect::<Vec<_>>(), keys.keys(&data));
+        assert!(keys.metas_capacity <= 2 * VecBytes::<KeyMetas>::ONE_EXPAND as u64);
+    }
 }
@@ -1,3 +1,7 @@
+use std::{cmp::Ordering, sync::Arc};
+
+use compare::Compare;
+
 use crate::{LenType, RrError};
 
 /// 二叉堆（binary heap）
@@ -5,14 +9,149 @@ pub trait Heap<T> {
     /// 取出binary heap的第一个字段,并不删除, 参数及返回值参见[Heap::pop]
     fn peek(&self, t: &T, key: &[u8]) -> Result<Option<(Vec<u8>, Vec<u8>)>, RrError>;
     /// 取出binary heap的第一个字段,并删除,(如果是min binary heap就是最小值，如果是max binary heap就是最大值)
-    /// 注： 最大小最小值是以 field来比较的，并不是value的
+    /// 注： 最大小最小值是以 field来比较的，并不是value的，比较的方式参见[HeapOrder]
     /// 返回值 0: field, 1: field value
     fn pop(&self, t: &T, key: &[u8]) -> Result<Option<(Vec<u8>, Vec<u8>)>, RrError>;
-    ///
+    /// field已存在时只替换它的value，不会重复加入，field就是优先级，所以在heap中的位置不变；不想替换时使用[Heap::push_nx]
+    /// field不符合heap的[HeapOrder]时(如Int的长度不对)返回错误
     fn push(&self, t: &T, key: &[u8], field: &[u8], value: &[u8]) -> Result<(), RrError>;
+    /// 最多保存capacity个字段(如按分数排名的前100个)，加入后超过capacity时删除最后的字段(最大堆中的最小值，最小堆中的最大值)及其value
+    /// 返回被删除的 (field, value)，从最后一个开始；如果加入的field本身就是最后的，它也在返回值中，并且不会保存
+    /// field已存在时只替换它的value，参见[Heap::push]；capacity为0时返回错误
+    fn push_bounded(&self, t: &T, key: &[u8], field: &[u8], value: &[u8], capacity: LenType) -> Result<Vec<(Vec<u8>, Vec<u8>)>, RrError>;
+    /// field已存在时不做修改，返回false，参见[Heap::push]
+    fn push_nx(&self, t: &T, key: &[u8], field: &[u8], value: &[u8]) -> Result<bool, RrError>;
+    /// 删除field，返回它的value，field不存在时返回None
+    fn remove(&self, t: &T, key: &[u8], field: &[u8]) -> Result<Option<Vec<u8>>, RrError>;
+    /// 把field改为new_field(即修改优先级)，value不变，在heap中移动到新的位置
+    /// field不存在时返回false，new_field已存在时返回错误
+    fn update_priority(&self, t: &T, key: &[u8], field: &[u8], new_field: &[u8]) -> Result<bool, RrError>;
+
+    /// 按顺序返回前n个 (field, value)，并不删除，n大于字段个数时返回所有的字段
+    /// 只读取一次heap，value使用一次multi get读取
+    fn peek_n(&self, t: &T, key: &[u8], n: LenType) -> Result<Vec<(Vec<u8>, Vec<u8>)>, RrError>;
+    /// 按顺序取出前n个 (field, value)并删除，只读写一次heap，参见[Heap::peek_n]
+    fn pop_n(&self, t: &T, key: &[u8], n: LenType) -> Result<Vec<(Vec<u8>, Vec<u8>)>, RrError>;
+    /// 按顺序遍历所有的 (field, value)，并不删除，参见[Heap::peek_n]
+    fn iter(&self, t: &T, key: &[u8]) -> Result<HeapIter, RrError>;
 
     fn len(&self, t: &T, key: &[u8]) -> Result<Option<LenType>, RrError>;
 
     /// 删除指定的key，及所有字段
     fn remove_key(&self, t: &T, key: &[u8]) -> Result<(), RrError>;
 }
+
+/// 按顺序遍历heap中的 (field, value)，参见[Heap::iter]
+pub struct HeapIter {
+    items: std::vec::IntoIter<(Vec<u8>, Vec<u8>)>,
+}
+
+impl HeapIter {
+    pub(crate) fn new(items: Vec<(Vec<u8>, Vec<u8>)>) -> Self {
+        HeapIter { items: items.into_iter() }
+    }
+}
+
+impl Iterator for HeapIter {
+    type Item = (Vec<u8>, Vec<u8>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.items.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.items.size_hint()
+    }
+}
+
+/// heap中field的比较方式，创建heap时保存在heap的header中，之后使用不同的比较方式打开这个heap时返回错误
+/// 整数与浮点数都是小端的(与[crate::write_int]一致)，可以直接用作按优先级或到期时间排序的队列
+#[derive(Clone, Default)]
+pub enum HeapOrder {
+    /// 按字节比较
+    #[default]
+    Bytes,
+    /// 有符号整数，field的长度为1、2、4或8
+    Int,
+    /// 无符号整数，field的长度为1、2、4或8
+    UInt,
+    /// f32或f64，field的长度为4或8，使用total_cmp比较
+    Float,
+    /// 自定义的比较，header中只保存id，所以不同的比较方式需要使用不同的id
+    Custom { id: u32, compare: Arc<dyn Compare<[u8]> + Send + Sync> },
+}
+
+impl HeapOrder {
+    /// 保存在header中的值，高32位为种类，低32位为Custom的id
+    pub(crate) fn header(&self) -> LenType {
+        let (kind, id) = match self {
+            HeapOrder::Bytes => (0, 0),
+            HeapOrder::Int => (1, 0),
+            HeapOrder::UInt => (2, 0),
+            HeapOrder::Float => (3, 0),
+            HeapOrder::Custom { id, .. } => (4, *id),
+        };
+        ((kind as LenType) << 32) | id as LenType
+    }
+
+    /// 检查field能否按这个方式比较
+    pub(crate) fn check_field(&self, field: &[u8]) -> Result<(), RrError> {
+        let ok = match self {
+            HeapOrder::Bytes | HeapOrder::Custom { .. } => true,
+            HeapOrder::Int | HeapOrder::UInt => [1, 2, 4, 8].contains(&field.len()),
+            HeapOrder::Float => [4, 8].contains(&field.len()),
+        };
+        if ok {
+            Ok(())
+        } else {
+            Err(RrError::message(format!("the len of field({}) is invalid for the heap order", field.len())))
+        }
+    }
+
+    pub(crate) fn compare(&self, l: &[u8], r: &[u8]) -> Ordering {
+        match self {
+            HeapOrder::Bytes => l.cmp(r),
+            HeapOrder::Int => match (HeapOrder::read_i64(l), HeapOrder::read_i64(r)) {
+                (Some(l), Some(r)) => l.cmp(&r),
+                _ => l.cmp(r),
+            },
+            HeapOrder::UInt => match (HeapOrder::read_u64(l), HeapOrder::read_u64(r)) {
+                (Some(l), Some(r)) => l.cmp(&r),
+                _ => l.cmp(r),
+            },
+            HeapOrder::Float => match (HeapOrder::read_f64(l), HeapOrder::read_f64(r)) {
+                (Some(l), Some(r)) => l.total_cmp(&r),
+                _ => l.cmp(r),
+            },
+            HeapOrder::Custom { compare, .. } => compare.compare(l, r),
+        }
+    }
+
+    fn read_i64(field: &[u8]) -> Option<i64> {
+        Some(match field.len() {
+            1 => field[0] as i8 as i64,
+            2 => i16::from_le_bytes(field.try_into().ok()?) as i64,
+            4 => i32::from_le_bytes(field.try_into().ok()?) as i64,
+            8 => i64::from_le_bytes(field.try_into().ok()?),
+            _ => return None,
+        })
+    }
+
+    fn read_u64(field: &[u8]) -> Option<u64> {
+        Some(match field.len() {
+            1 => field[0] as u64,
+            2 => u16::from_le_bytes(field.try_into().ok()?) as u64,
+            4 => u32::from_le_bytes(field.try_into().ok()?) as u64,
+            8 => u64::from_le_bytes(field.try_into().ok()?),
+            _ => return None,
+        })
+    }
+
+    fn read_f64(field: &[u8]) -> Option<f64> {
+        Some(match field.len() {
+            4 => f32::from_le_bytes(field.try_into().ok()?) as f64,
+            8 => f64::from_le_bytes(field.try_into().ok()?),
+            _ => return None,
+        })
+    }
+}
@@ -7,6 +7,7 @@ pub use key_value::*;
 pub use list::*;
 pub use object::*;
 pub use rocksdb_impl::*;
+pub use set::*;
 pub use sorted_set::*;
 pub use stack::*;
 pub use types::*;
@@ -20,6 +21,7 @@ mod key_value;
 mod list;
 mod object;
 mod rocksdb_impl;
+mod set;
 mod sorted_set;
 mod stack;
 mod types;
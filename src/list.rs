@@ -1,66 +1,168 @@
-use crate::{Bytes, LenType, RrError};
+use rocksdb::TransactionDB;
 
+use crate::{Bytes, LenType, ListIter, RrError};
+
+/// list的两端，对应redis lmove中的 LEFT 与 RIGHT
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ListEnd {
+    Front,
+    Back,
+}
+
+/// quick list中node的容量，node中的元素个数超过max_len或字节数超过max_bytes时，push会新建node
+/// 创建list时保存在list的header中，之后修改配置不影响已有的list；0表示使用默认值
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ListConfig {
+    pub max_len: LenType,
+    pub max_bytes: LenType,
+    /// 对应redis的list-compress-depth，两端各compress_depth个node不压缩，中间的node使用lz4压缩
+    /// 0表示不压缩(默认)
+    pub compress_depth: LenType,
+}
+
+impl ListConfig {
+    pub const DEFAULT_MAX_LEN: LenType = 124;
+    pub const DEFAULT_MAX_BYTES: LenType = ListConfig::DEFAULT_MAX_LEN * 4;
+}
+
+impl Default for ListConfig {
+    fn default() -> Self {
+        ListConfig {
+            max_len: ListConfig::DEFAULT_MAX_LEN,
+            max_bytes: ListConfig::DEFAULT_MAX_BYTES,
+            compress_depth: 0,
+        }
+    }
+}
+
+/// RedisRocksdb中list的写操作是串行的，可以在多个线程中共用一个RedisRocksdb(如Arc<RedisRocksdb>)
 pub trait RedisList {
-    fn list_blpop<K: Bytes, V: Bytes>(&mut self, key: &K, timeout: i64) -> Result<V, RrError>;
-    fn list_brpop<K: Bytes, V: Bytes>(&mut self, key: &K, timeout: i64) -> Result<V, RrError>;
-    fn list_brpoplpush<K: Bytes, V: Bytes>(&mut self, srckey: &K, dstkey: &K, timeout: i64) -> Result<V, RrError>;
+    /// 对应redis的blpop，按keys的顺序找到第一个不为空的list，弹出其第一个元素，返回 (key, value)
+    /// 所有的list都为空时阻塞，直到有元素加入或超时(返回None)。timeout的单位为毫秒，0表示一直等待
+    /// 多个线程阻塞在同一个key上时，按阻塞的先后顺序取得数据
+    fn list_blpop<K: Bytes>(&self, keys: &[&K], timeout: i64) -> Result<Option<(Vec<u8>, Vec<u8>)>, RrError>;
+    /// 对应redis的brpop，参数参见[RedisList::list_blpop]
+    fn list_brpop<K: Bytes>(&self, keys: &[&K], timeout: i64) -> Result<Option<(Vec<u8>, Vec<u8>)>, RrError>;
+    /// 对应redis的brpoplpush，弹出srckey的最后一个元素并加入到dstkey的头部，在一个事务中完成
+    /// 超时返回None，参数参见[RedisList::list_blpop]
+    fn list_brpoplpush<K: Bytes>(&self, srckey: &K, dstkey: &K, timeout: i64) -> Result<Option<Vec<u8>>, RrError>;
+    /// 对应redis的blmove，srckey为空时阻塞，参数参见[RedisList::list_move]与[RedisList::list_blpop]
+    fn list_blmove<K: Bytes>(&self, srckey: &K, dstkey: &K, from: ListEnd, to: ListEnd, timeout: i64) -> Result<Option<Vec<u8>>, RrError>;
+    /// 对应redis的lindex，负数表示从尾部开始计算(-1为最后一个元素)，index不在范围内时返回错误
     fn list_index<K: Bytes>(&self, key: &K, index: i32) -> Result<Vec<u8>, RrError>;
+    /// 对应redis的lpos，返回与value相等的元素的下标(从头部开始计算)
+    /// rank: 不能为0，为正数时从头部开始查找，跳过前 rank - 1 个匹配的元素；为负数时从尾部开始查找
+    /// count: 最多返回的个数，0表示返回所有匹配的元素
+    /// max_len: 最多比较的元素个数，0表示不限制
+    fn list_pos<K: Bytes, V: Bytes>(&self, key: &K, value: &V, rank: i64, count: LenType, max_len: LenType) -> Result<Vec<LenType>, RrError>;
 
     /// 如果命令执行成功，返回插入操作完成之后，列表的长度。
     /// 如果没有找到指定元素 ，返回 -1 。
     /// 如果 key 不存在或为空列表，返回 0
-    fn list_insert_before<K: Bytes, V: Bytes>(&mut self, key: &K, pivot: &V, value: &V) -> Result<i32, RrError>;
+    fn list_insert_before<K: Bytes, V: Bytes>(&self, key: &K, pivot: &V, value: &V) -> Result<i32, RrError>;
     /// 如果命令执行成功，返回插入操作完成之后，列表的长度。
     /// 如果没有找到指定元素 ，返回 -1 。
     /// 如果 key 不存在或为空列表，返回 0
-    fn list_insert_after<K: Bytes, V: Bytes>(&mut self, key: &K, pivot: &V, value: &V) -> Result<i32, RrError>;
+    fn list_insert_after<K: Bytes, V: Bytes>(&self, key: &K, pivot: &V, value: &V) -> Result<i32, RrError>;
 
     // 返回值为-1表示还没有这个list
     fn list_len<K: Bytes>(&self, key: &K) -> Result<i32, RrError>;
 
     /// 对应redis的 lpop，由于lpop命名，不是很明确，所以改名
-    fn list_pop_front<K: Bytes>(&mut self, key: &K) -> Result<Option<Vec<u8>>, RrError>;
+    fn list_pop_front<K: Bytes>(&self, key: &K) -> Result<Option<Vec<u8>>, RrError>;
     /// 返回len of list
     /// 对应redis的lpush，由于lpush命名，不是很明确，所以改名
-    fn list_push_front<K: Bytes, V: Bytes>(&mut self, key: &K, value: &V) -> Result<i32, RrError>;
+    fn list_push_front<K: Bytes, V: Bytes>(&self, key: &K, value: &V) -> Result<i32, RrError>;
 
-    // /// 返回len of list
-    // fn lpushs<K: Bytes, V: Bytes>(&mut self, key: &K, values: &[&V]) -> Result<i32, RrError>;
+    /// 返回len of list
+    /// 对应redis中有多个值的lpush，依次加入到头部(最后一个值成为第一个元素)，在一个事务中完成
+    fn list_push_front_many<K: Bytes, V: Bytes>(&self, key: &K, values: &[&V]) -> Result<i32, RrError>;
 
     /// 返回len of list，如果list不存在返回值为 0
     /// 对应redis的lpushx
-    fn list_push_front_exists<K: Bytes, V: Bytes>(&mut self, key: &K, value: &V) -> Result<i32, RrError>;
+    fn list_push_front_exists<K: Bytes, V: Bytes>(&self, key: &K, value: &V) -> Result<i32, RrError>;
     /// 返回在range范围内的元素，所以start与stop可能会在list的下标之外。range是包含stop的
     /// 如果一个都没有找到，返回为len为0的Vec
     /// 0表示第一个元素
     /// -1表示倒数第一个元素
     /// -100 100表示从到数100个元素到第101个元素。如果这时list中只有3个元素，返回所有的值，因为这3个都在 range的范围之内
     fn list_range<K: Bytes>(&self, key: &K, start: i32, stop: i32) -> Result<Vec<Vec<u8>>, RrError>;
+    /// 在当前的snapshot上遍历list，from为Front时从头部开始，为Back时从尾部开始
+    /// 与[RedisList::list_range]不同，按node依次读取，不会一次把所有的值读入内存；list不存在时没有值
+    fn list_iter<K: Bytes>(&self, key: &K, from: ListEnd) -> Result<ListIter<'_, TransactionDB>, RrError>;
     /// 返回值为删除的数量
     /// COUNT 的值可以是以下几种：
     /// count > 0 : 从表头开始向表尾搜索，移除与 VALUE 相等的元素，数量为 COUNT。
     /// count < 0 : 从表尾开始向表头搜索，移除与 VALUE 相等的元素，数量为 COUNT 的绝对值。
     /// count = 0 : 移除表中所有与 VALUE 相等的值
-    fn list_rem<K: Bytes, V: Bytes>(&mut self, key: &K, count: i32, value: &V) -> Result<LenType, RrError>;
+    fn list_rem<K: Bytes, V: Bytes>(&self, key: &K, count: i32, value: &V) -> Result<LenType, RrError>;
     /// 保留指定区间内的元素，不在指定区间之内的元素都将被删除, 反回删除的元素数量
-    fn list_trim<K: Bytes>(&mut self, key: K, start: i32, stop: i32) -> Result<i32, RrError>;
+    fn list_trim<K: Bytes>(&self, key: K, start: i32, stop: i32) -> Result<i32, RrError>;
 
-    /// index无效或list为空时，返回错误。其余返回原来的值
-    fn list_set<K: Bytes, V: Bytes>(&mut self, key: &K, index: i32, value: &V) -> Result<Vec<u8>, RrError>;
+    /// index无效或list为空时，返回错误。其余返回原来的值，index可以为负数，参见[RedisList::list_index]
+    fn list_set<K: Bytes, V: Bytes>(&self, key: &K, index: i32, value: &V) -> Result<Vec<u8>, RrError>;
     /// 移除列表的最后一个元素
-    fn list_pop_back<K: Bytes>(&mut self, key: &K) -> Result<Option<Vec<u8>>, RrError>;
+    fn list_pop_back<K: Bytes>(&self, key: &K) -> Result<Option<Vec<u8>>, RrError>;
     /// 移除列表的最后一个元素，并将该元素添加到另一个列表并返回
-    /// 对应redis的rpoplpush
-    fn list_replace_back<K: Bytes, V: Bytes>(&mut self, key: &K, dstkey: &K) -> Result<V, RrError>;
+    /// 对应redis的rpoplpush，key为空时返回None
+    fn list_replace_back<K: Bytes>(&self, key: &K, dstkey: &K) -> Result<Option<Vec<u8>>, RrError>;
+    /// 对应redis的lmove，从srckey的from端弹出一个元素，加入到dstkey的to端并返回，srckey为空时返回None
+    /// 弹出与加入在一个事务中完成，srckey与dstkey可以相同(即旋转list)
+    fn list_move<K: Bytes>(&self, srckey: &K, dstkey: &K, from: ListEnd, to: ListEnd) -> Result<Option<Vec<u8>>, RrError>;
     /// 返回len of list
     /// 对应redis的rpush
-    fn list_push_back<K: Bytes, V: Bytes>(&mut self, key: &K, value: &V) -> Result<i32, RrError>;
-    // /// 返回len of list
-    // fn rpushs<K: Bytes, V: Bytes>(&mut self, key: &K, value: &[&V]) -> Result<i32, RrError>;
+    fn list_push_back<K: Bytes, V: Bytes>(&self, key: &K, value: &V) -> Result<i32, RrError>;
+    /// 返回len of list
+    /// 对应redis中有多个值的rpush，依次加入到尾部，在一个事务中完成
+    fn list_push_back_many<K: Bytes, V: Bytes>(&self, key: &K, values: &[&V]) -> Result<i32, RrError>;
     /// 为已经存在的列表添加值， 添加到尾部
     /// 对应redis的rpushx
-    fn list_push_back_exists<K: Bytes, V: Bytes>(&mut self, key: &K, value: &V) -> Result<i32, RrError>;
+    fn list_push_back_exists<K: Bytes, V: Bytes>(&self, key: &K, value: &V) -> Result<i32, RrError>;
 
     /// 返回len of list
-    fn list_clear<K: Bytes>(&mut self, key: &K) -> Result<i32, RrError>;
+    fn list_clear<K: Bytes>(&self, key: &K) -> Result<i32, RrError>;
+}
+
+/// 与[RedisList]的功能相同，但不自己创建事务，可以与其它的数据类型共用一个[crate::WrapTransaction]
+/// 返回值参见[RedisList]中对应的方法
+pub trait List<T> {
+    /// 对应redis的lindex
+    fn index(&self, t: &T, key: &[u8], index: i32) -> Result<Vec<u8>, RrError>;
+    /// 对应redis的linsert before
+    fn insert_before(&self, t: &T, key: &[u8], pivot: &[u8], value: &[u8]) -> Result<i32, RrError>;
+    /// 对应redis的linsert after
+    fn insert_after(&self, t: &T, key: &[u8], pivot: &[u8], value: &[u8]) -> Result<i32, RrError>;
+    /// 返回值为-1表示还没有这个list
+    fn len(&self, t: &T, key: &[u8]) -> Result<i32, RrError>;
+    /// 对应redis的lpop
+    fn pop_front(&self, t: &T, key: &[u8]) -> Result<Option<Vec<u8>>, RrError>;
+    /// 对应redis的lpush
+    fn push_front(&self, t: &T, key: &[u8], value: &[u8]) -> Result<i32, RrError>;
+    /// 对应redis中有多个值的lpush，每个node只读写一次
+    fn push_front_many(&self, t: &T, key: &[u8], values: &[&[u8]]) -> Result<i32, RrError>;
+    /// 对应redis的lpushx
+    fn push_front_exists(&self, t: &T, key: &[u8], value: &[u8]) -> Result<i32, RrError>;
+    /// 对应redis的lrange
+    fn range(&self, t: &T, key: &[u8], start: i32, stop: i32) -> Result<Vec<Vec<u8>>, RrError>;
+    /// 对应redis的lrem
+    fn rem(&self, t: &T, key: &[u8], count: i32, value: &[u8]) -> Result<LenType, RrError>;
+    /// 对应redis的ltrim，返回删除的元素数量，全部删除时同时删除list
+    /// 只读取与删除范围之外的node，并修改两端的node，中间保留的node不会被读写
+    fn trim(&self, t: &T, key: &[u8], start: i32, stop: i32) -> Result<i32, RrError>;
+    /// 对应redis的lpos，参数参见[RedisList::list_pos]
+    fn pos(&self, t: &T, key: &[u8], value: &[u8], rank: i64, count: LenType, max_len: LenType) -> Result<Vec<LenType>, RrError>;
+    /// 对应redis的lset
+    fn set(&self, t: &T, key: &[u8], index: i32, value: &[u8]) -> Result<Vec<u8>, RrError>;
+    /// 对应redis的rpop
+    fn pop_back(&self, t: &T, key: &[u8]) -> Result<Option<Vec<u8>>, RrError>;
+    /// 对应redis的rpush
+    fn push_back(&self, t: &T, key: &[u8], value: &[u8]) -> Result<i32, RrError>;
+    /// 对应redis中有多个值的rpush，每个node只读写一次
+    fn push_back_many(&self, t: &T, key: &[u8], values: &[&[u8]]) -> Result<i32, RrError>;
+    /// 对应redis的rpushx
+    fn push_back_exists(&self, t: &T, key: &[u8], value: &[u8]) -> Result<i32, RrError>;
+    /// 对应redis的lmove，在WrapTransaction中执行时是原子的
+    fn move_to(&self, t: &T, src: &[u8], dst: &[u8], from: ListEnd, to: ListEnd) -> Result<Option<Vec<u8>>, RrError>;
+    /// 删除list，返回node的个数
+    fn clear(&self, t: &T, key: &[u8]) -> Result<i32, RrError>;
 }
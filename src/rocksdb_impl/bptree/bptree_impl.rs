@@ -1,67 +1,125 @@
-use crate::{rocksdb_impl::shared::make_field_key, BPTree, LenType, RrError, WrapDb};
+use std::ops::Bound;
 
+use super::btree::BTree;
+use crate::{rocksdb_impl::shared::make_field_key, BPTree, BPTreeCursor, LenType, RrError, WrapDb};
+
+/// field按字节序存放在b+ tree中，field的值存放在 make_field_key(key, field) 中
 /// see
 /// [Writing a storage engine in Rust: Writing a persistent BTree (Part 1)] (https://nimrodshn.medium.com/writing-a-storage-engine-in-rust-writing-a-persistent-btree-part-1-916b6f3e2934)
 /// [A persistent copy-on-write B+Tree implementation, designed as an index for a key-value store, inspired by SQLite](https://github.com/nimrodshn/btree)
 pub struct BPTreeImpl {}
 
+impl BPTreeImpl {
+    fn get_field<T: WrapDb>(t: &T, key: &[u8], field: Option<Vec<u8>>) -> Result<Option<(Vec<u8>, Vec<u8>)>, RrError> {
+        match field {
+            None => Ok(None),
+            Some(field) => {
+                let v = t.get(&make_field_key(key, &field))?.unwrap_or_default();
+                Ok(Some((field, v)))
+            }
+        }
+    }
+
+    fn del_field<T: WrapDb>(t: &T, key: &[u8], field: Option<Vec<u8>>) -> Result<Option<(Vec<u8>, Vec<u8>)>, RrError> {
+        let re = BPTreeImpl::get_field(t, key, field)?;
+        if let Some((field, _)) = &re {
+            BTree::new(key, t).delete(field)?;
+            t.delete(&make_field_key(key, field))?;
+        }
+        Ok(re)
+    }
+}
+
 impl<T: WrapDb> BPTree<T> for BPTreeImpl {
-    fn set_exist(&self, t: &T, key: &[u8], field: &[u8], _value: &[u8]) -> Result<(), RrError> {
+    fn set_exist(&self, t: &T, key: &[u8], field: &[u8], value: &[u8]) -> Result<(), RrError> {
         let field_key = make_field_key(key, field);
-        if t.exist(&field_key)? {
-            // let head_key = make_head_key(key);
-            // let mut heap = {
-            //     match t.get(&head_key)? {
-            //         None => FieldHeap::new(vec![]),
-            //         Some(v) => FieldHeap::new(v)
-            //     }
-            // };
-            // let p = &mut heap as *mut _;
-            // heap.init(MaxHeapCompare { heap: p });
-            // heap.push(field);
-            // t.put(&head_key, &heap.data)?;
+        if t.get(&field_key)?.is_some() {
+            t.put(&field_key, value)?;
+        }
+        Ok(())
+    }
+
+    fn set_not_exist(&self, t: &T, key: &[u8], field: &[u8], value: &[u8]) -> Result<(), RrError> {
+        if BTree::new(key, t).insert(field)? {
+            t.put(&make_field_key(key, field), value)?;
         }
-        // t.put(&field_key, value)?;
         Ok(())
     }
 
-    fn set_not_exist(&self, _t: &T, _key: &[u8], _field: &[u8], _value: &[u8]) -> Result<(), RrError> {
-        todo!()
+    fn set(&self, t: &T, key: &[u8], field: &[u8], value: &[u8]) -> Result<(), RrError> {
+        BTree::new(key, t).insert(field)?;
+        t.put(&make_field_key(key, field), value)?;
+        Ok(())
+    }
+
+    fn del_first(&self, t: &T, key: &[u8]) -> Result<Option<(Vec<u8>, Vec<u8>)>, RrError> {
+        let field = BTree::new(key, t).first()?;
+        BPTreeImpl::del_field(t, key, field)
     }
 
-    fn set(&self, _t: &T, _key: &[u8], _field: &[u8], _value: &[u8]) -> Result<(), RrError> {
-        todo!()
+    fn del_last(&self, t: &T, key: &[u8]) -> Result<Option<(Vec<u8>, Vec<u8>)>, RrError> {
+        let field = BTree::new(key, t).last()?;
+        BPTreeImpl::del_field(t, key, field)
     }
 
-    fn del_first(&self, _t: &T, _key: &[u8]) -> Result<Option<(Vec<u8>, Vec<u8>)>, RrError> {
-        todo!()
+    fn del(&self, t: &T, key: &[u8], field: &[u8]) -> Result<Option<Vec<u8>>, RrError> {
+        if !BTree::new(key, t).delete(field)? {
+            return Ok(None);
+        }
+        let field_key = make_field_key(key, field);
+        let v = t.get(&field_key)?.unwrap_or_default();
+        t.delete(&field_key)?;
+        Ok(Some(v))
     }
 
-    fn del_last(&self, _t: &T, _key: &[u8]) -> Result<Option<(Vec<u8>, Vec<u8>)>, RrError> {
-        todo!()
+    fn get_first(&self, t: &T, key: &[u8]) -> Result<Option<(Vec<u8>, Vec<u8>)>, RrError> {
+        let field = BTree::new(key, t).first()?;
+        BPTreeImpl::get_field(t, key, field)
     }
 
-    fn del(&self, _t: &T, _key: &[u8], _field: &[u8]) -> Result<Option<Vec<u8>>, RrError> {
-        todo!()
+    fn get_last(&self, t: &T, key: &[u8]) -> Result<Option<(Vec<u8>, Vec<u8>)>, RrError> {
+        let field = BTree::new(key, t).last()?;
+        BPTreeImpl::get_field(t, key, field)
     }
 
-    fn get_first(&self, _t: &T, _key: &[u8]) -> Result<Option<(Vec<u8>, Vec<u8>)>, RrError> {
-        todo!()
+    fn get(&self, t: &T, key: &[u8], field: &[u8]) -> Result<Option<Vec<u8>>, RrError> {
+        t.get(&make_field_key(key, field))
     }
 
-    fn get_last(&self, _t: &T, _key: &[u8]) -> Result<Option<(Vec<u8>, Vec<u8>)>, RrError> {
-        todo!()
+    fn range(&self, t: &T, key: &[u8], start_field: Bound<&[u8]>, end_field: Bound<&[u8]>, limit: usize) -> Result<Vec<(Vec<u8>, Vec<u8>)>, RrError> {
+        BPTreeCursor::new(key, start_field, end_field, false).next_n(t, if limit == 0 { usize::MAX } else { limit })
     }
 
-    fn get(&self, _t: &T, _key: &[u8], _field: &[u8]) -> Result<Option<Vec<u8>>, RrError> {
-        todo!()
+    fn range_rev(&self, t: &T, key: &[u8], start_field: Bound<&[u8]>, end_field: Bound<&[u8]>, limit: usize) -> Result<Vec<(Vec<u8>, Vec<u8>)>, RrError> {
+        BPTreeCursor::new(key, start_field, end_field, true).next_n(t, if limit == 0 { usize::MAX } else { limit })
     }
 
-    fn len(&self, _t: &T, _key: &[u8]) -> Result<Option<LenType>, RrError> {
-        todo!()
+    fn cursor(&self, key: &[u8], start_field: Bound<&[u8]>, end_field: Bound<&[u8]>, rev: bool) -> BPTreeCursor {
+        BPTreeCursor::new(key, start_field, end_field, rev)
     }
 
-    fn del_key(&self, _t: &T, _key: &[u8]) -> Result<(), RrError> {
-        todo!()
+    fn len(&self, t: &T, key: &[u8]) -> Result<Option<LenType>, RrError> {
+        Ok(BTree::new(key, t).len()?)
+    }
+
+    fn rank(&self, t: &T, key: &[u8], field: &[u8]) -> Result<Option<LenType>, RrError> {
+        Ok(BTree::new(key, t).rank(field)?)
+    }
+
+    fn select(&self, t: &T, key: &[u8], n: LenType) -> Result<Option<(Vec<u8>, Vec<u8>)>, RrError> {
+        let field = BTree::new(key, t).select(n)?;
+        BPTreeImpl::get_field(t, key, field)
+    }
+
+    fn count(&self, t: &T, key: &[u8], lo: Bound<&[u8]>, hi: Bound<&[u8]>) -> Result<LenType, RrError> {
+        Ok(BTree::new(key, t).count(lo, hi)?)
+    }
+
+    fn del_key(&self, t: &T, key: &[u8]) -> Result<(), RrError> {
+        BTree::new(key, t).clear(|field| {
+            t.delete(&make_field_key(key, field))?;
+            Ok(())
+        })?;
+        Ok(())
     }
 }
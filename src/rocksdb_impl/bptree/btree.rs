@@ -1,5 +1,13 @@
-use super::{error::Error, node::Node, node_type::NodeType};
-use crate::WrapDb;
+use std::{convert::TryFrom, ops::Bound};
+
+use super::{error::Error, node::Node};
+use crate::{
+    rocksdb_impl::{
+        bptree::{db_key::DbKey, tree_head::TreeHead},
+        shared::make_head_key,
+    },
+    LenType, WrapDb,
+};
 
 /// [see](https://github.com/nimrodshn/btree)
 /// B+Tree properties.
@@ -7,7 +15,8 @@ pub const MAX_BRANCHING_FACTOR: usize = 200;
 pub const NODE_KEYS_LIMIT: usize = MAX_BRANCHING_FACTOR - 1;
 
 /// BTree struct represents an on-disk B+tree.
-/// Each node is persisted in the table file, the leaf nodes contain the values.
+/// 每个节点使用自己的db key存入数据库，树的头信息(len, root)存放在 make_head_key(key) 中
+/// 叶子节点只存放field，field的值由调用者存放
 pub struct BTree<'a, T: WrapDb> {
     b: u64,
     t: &'a T,
@@ -16,357 +25,503 @@ pub struct BTree<'a, T: WrapDb> {
 
 impl<'a, T: WrapDb> BTree<'a, T> {
     pub fn new(key: &[u8], t: &'a T) -> Self {
-        BTree { b: 3, t, key: key.to_vec() }
+        BTree {
+            b: (MAX_BRANCHING_FACTOR / 2) as u64,
+            t,
+            key: key.to_vec(),
+        }
+    }
+
+    /// 节点中key的个数超过 2b - 1 时需要分裂
+    fn is_node_overflow(&self, node: &Node) -> bool {
+        node.len() > NODE_KEYS_LIMIT
+    }
+
+    fn is_node_underflow(&self, node: &Node) -> bool {
+        // A root cannot really be "underflowing" as it can contain less than b-1 keys / pointers.
+        (node.len() as u64) < self.b - 1 && !node.is_root()
+    }
+
+    /// 兄弟节点是否可以借出一个key
+    fn can_lend(&self, node: &Node) -> bool {
+        node.len() as u64 > self.b - 1
+    }
+
+    pub fn head(&self) -> Result<Option<TreeHead>, Error> {
+        match self.t.get(&make_head_key(&self.key))? {
+            None => Ok(None),
+            Some(v) => Ok(Some(TreeHead::try_from(v)?)),
+        }
+    }
+
+    fn save_head(&self, head: &TreeHead) -> Result<(), Error> {
+        self.t.put(&make_head_key(&self.key), head.as_ref())?;
+        Ok(())
+    }
+
+    fn load(&self, db_key: &DbKey) -> Result<Node, Error> {
+        let data = self.t.get(db_key.key())?.ok_or(Error::KeyNotFound)?;
+        Node::try_from(data)
+    }
+
+    fn save(&self, node: &Node) -> Result<(), Error> {
+        self.t.put(node.db_key().key(), &node.data)?;
+        Ok(())
+    }
+
+    fn remove(&self, node: &Node) -> Result<(), Error> {
+        self.t.delete(node.db_key().key())?;
+        Ok(())
+    }
+
+    fn set_parent(&self, child: &DbKey, parent: &DbKey) -> Result<(), Error> {
+        let mut data = self.t.get(child.key())?.ok_or(Error::KeyNotFound)?;
+        Node::set_parent_db_key_data(data.as_mut_slice(), parent.key());
+        self.t.put(child.key(), &data)?;
+        Ok(())
+    }
+
+    /// 修改叶子节点的左兄弟
+    fn set_left(&self, leaf: &DbKey, left: &DbKey) -> Result<(), Error> {
+        let mut data = self.t.get(leaf.key())?.ok_or(Error::KeyNotFound)?;
+        Node::set_link_data(data.as_mut_slice(), Node::OFFSET_LEFT, Some(left));
+        self.t.put(leaf.key(), &data)?;
+        Ok(())
+    }
+
+    /// node的所有中的child的每一个 parent_key已变化，所以需要更新
+    fn set_parent_of_children(&self, node: &Node) -> Result<(), Error> {
+        let parent = node.db_key();
+        for (child, _) in node.children() {
+            self.set_parent(&child, &parent)?;
+        }
+        Ok(())
+    }
+
+    /// 从root开始查找field所在的叶子节点，返回经过的内部节点(及子节点的位置)与叶子节点
+    fn find_leaf(&self, root: &DbKey, field: &[u8]) -> Result<(Vec<(Node, usize)>, Node), Error> {
+        let mut path = Vec::new();
+        let mut node = self.load(root)?;
+        while !node.is_leaf() {
+            let index = node.child_index(field);
+            let child = node.child(index)?;
+            path.push((node, index));
+            node = self.load(&child)?;
+        }
+        Ok((path, node))
+    }
+
+    pub fn len(&self) -> Result<Option<LenType>, Error> {
+        Ok(self.head()?.map(|h| h.len()))
+    }
+
+    /// insert a field possibly splitting nodes along the way.
+    /// 返回 true: 新插入, false: 已存在
+    pub fn insert(&self, field: &[u8]) -> Result<bool, Error> {
+        let (mut head, mut path, mut node) = match self.head()? {
+            None => {
+                let leaf = Node::new_leaf(&[]);
+                (TreeHead::new(&leaf.db_key()), Vec::new(), leaf)
+            }
+            Some(head) => {
+                let (path, leaf) = self.find_leaf(&head.root(), field)?;
+                (head, path, leaf)
+            }
+        };
+        let index = match node.search(field) {
+            Ok(_) => return Ok(false),
+            Err(i) => i,
+        };
+        node.insert_field(index, field)?;
+        head.set_len(head.len() + 1);
+        for (parent, index) in path.iter_mut() {
+            parent.set_count(*index, parent.count(*index) + 1);
+        }
+
+        while self.is_node_overflow(&node) {
+            let (median, mut sibling) = node.split(node.len() as u64 / 2)?;
+            if let Some(right) = sibling.right() {
+                self.set_left(&right, &sibling.db_key())?;
+            }
+            match path.pop() {
+                Some((mut parent, index)) => {
+                    parent.set_count(index, node.subtree_len());
+                    parent.insert_key_child(index, &median, index + 1, &sibling.db_key(), sibling.subtree_len())?;
+                    self.save(&node)?;
+                    self.save(&sibling)?;
+                    self.set_parent_of_children(&sibling)?;
+                    node = parent;
+                }
+                None => {
+                    // split the root creating a new root
+                    let children = [(node.db_key(), node.subtree_len()), (sibling.db_key(), sibling.subtree_len())];
+                    let root = Node::new_internal(&children, &[median.as_slice()]);
+                    node.set_parent_db_key(root.db_key().key());
+                    sibling.set_parent_db_key(root.db_key().key());
+                    self.save(&node)?;
+                    self.save(&sibling)?;
+                    self.set_parent_of_children(&sibling)?;
+                    head.set_root(&root.db_key());
+                    node = root;
+                }
+            }
+        }
+        self.save(&node)?;
+        // 路径上节点的count已变化
+        for (parent, _) in path {
+            self.save(&parent)?;
+        }
+        self.save_head(&head)?;
+        Ok(true)
+    }
+
+    /// delete deletes a given field from the tree.
+    /// 返回 true: 删除成功, false: 不存在
+    pub fn delete(&self, field: &[u8]) -> Result<bool, Error> {
+        let mut head = match self.head()? {
+            None => return Ok(false),
+            Some(h) => h,
+        };
+        let (mut path, mut node) = self.find_leaf(&head.root(), field)?;
+        let index = match node.search(field) {
+            Ok(i) => i,
+            Err(_) => return Ok(false),
+        };
+        node.remove_field(index)?;
+        head.set_len(head.len() - 1);
+        if head.len() == 0 {
+            self.remove(&node)?;
+            self.t.delete(&make_head_key(&self.key))?;
+            return Ok(true);
+        }
+        for (parent, index) in path.iter_mut() {
+            parent.set_count(*index, parent.count(*index) - 1);
+        }
+
+        loop {
+            let (mut parent, index) = match path.pop() {
+                Some(p) => p,
+                None => {
+                    if !node.is_leaf() && node.len() == 0 {
+                        // the root has only one child, so the child becomes the new root
+                        let mut child = self.load(&node.child(0)?)?;
+                        self.remove(&node)?;
+                        child.set_parent_none();
+                        self.save(&child)?;
+                        head.set_root(&child.db_key());
+                    } else {
+                        self.save(&node)?;
+                    }
+                    break;
+                }
+            };
+            if !self.is_node_underflow(&node) {
+                self.save(&node)?;
+                self.save(&parent)?;
+                break;
+            }
+
+            let mut left = None;
+            if index > 0 {
+                let mut l = self.load(&parent.child(index - 1)?)?;
+                if self.can_lend(&l) {
+                    self.borrow_from_left(&mut parent, index, &mut l, &mut node)?;
+                    self.save(&l)?;
+                    self.save(&node)?;
+                    self.save(&parent)?;
+                    break;
+                }
+                left = Some(l);
+            }
+            let mut right = None;
+            if index + 1 < parent.number_children() {
+                let mut r = self.load(&parent.child(index + 1)?)?;
+                if self.can_lend(&r) {
+                    self.borrow_from_right(&mut parent, index, &mut node, &mut r)?;
+                    self.save(&r)?;
+                    self.save(&node)?;
+                    self.save(&parent)?;
+                    break;
+                }
+                right = Some(r);
+            }
+
+            match (left, right) {
+                (Some(mut l), _) => self.merge(&mut parent, index - 1, &mut l, &node)?,
+                (None, Some(r)) => self.merge(&mut parent, index, &mut node, &r)?,
+                (None, None) => return Err(Error::UnexpectedError),
+            }
+            node = parent;
+        }
+        for (parent, _) in path {
+            self.save(&parent)?;
+        }
+        self.save_head(&head)?;
+        Ok(true)
+    }
+
+    /// 把左边兄弟节点的最后一个key移到node中
+    fn borrow_from_left(&self, parent: &mut Node, index: usize, left: &mut Node, node: &mut Node) -> Result<(), Error> {
+        if node.is_leaf() {
+            let field = left.remove_field(left.len() - 1)?;
+            node.insert_field(0, &field)?;
+            parent.set_key(index - 1, &field)?;
+        } else {
+            let (key, (child, count)) = left.remove_key_child(left.len() - 1, left.number_children() - 1)?;
+            let separator = parent.key(index - 1).to_vec();
+            node.insert_key_child(0, &separator, 0, &child, count)?;
+            parent.set_key(index - 1, &key)?;
+            self.set_parent(&child, &node.db_key())?;
+        }
+        parent.set_count(index - 1, left.subtree_len());
+        parent.set_count(index, node.subtree_len());
+        Ok(())
+    }
+
+    /// 把右边兄弟节点的第一个key移到node中
+    fn borrow_from_right(&self, parent: &mut Node, index: usize, node: &mut Node, right: &mut Node) -> Result<(), Error> {
+        if node.is_leaf() {
+            let field = right.remove_field(0)?;
+            node.insert_field(node.len(), &field)?;
+            parent.set_key(index, right.key(0))?;
+        } else {
+            let (key, (child, count)) = right.remove_key_child(0, 0)?;
+            let separator = parent.key(index).to_vec();
+            node.insert_key_child(node.len(), &separator, node.number_children(), &child, count)?;
+            parent.set_key(index, &key)?;
+            self.set_parent(&child, &node.db_key())?;
+        }
+        parent.set_count(index, node.subtree_len());
+        parent.set_count(index + 1, right.subtree_len());
+        Ok(())
+    }
+
+    /// 把right合并到left中，并从parent中删除key_index处的key及right
+    fn merge(&self, parent: &mut Node, key_index: usize, left: &mut Node, right: &Node) -> Result<(), Error> {
+        let median = parent.key(key_index).to_vec();
+        left.merge(&median, right)?;
+        parent.remove_key_child(key_index, key_index + 1)?;
+        parent.set_count(key_index, left.subtree_len());
+        let left_key = left.db_key();
+        for (child, _) in right.children() {
+            self.set_parent(&child, &left_key)?;
+        }
+        if let Some(next) = right.right() {
+            self.set_left(&next, &left_key)?;
+        }
+        self.remove(right)?;
+        self.save(left)?;
+        Ok(())
+    }
+
+    /// 最小的field
+    pub fn first(&self) -> Result<Option<Vec<u8>>, Error> {
+        self.edge(true)
+    }
+
+    /// 最大的field
+    pub fn last(&self) -> Result<Option<Vec<u8>>, Error> {
+        self.edge(false)
     }
-    fn is_node_full(&self, node: &Node) -> Result<bool, Error> {
-        match &node.node_type {
-            NodeType::Leaf(pairs) => Ok(pairs.number_keys == (2 * self.b - 1)),
-            NodeType::Internal(_, keys) => Ok(keys.number_keys == (2 * self.b - 1)),
-            NodeType::None => Err(Error::UnexpectedError),
+
+    fn edge(&self, first: bool) -> Result<Option<Vec<u8>>, Error> {
+        let head = match self.head()? {
+            None => return Ok(None),
+            Some(h) => h,
+        };
+        let node = self.edge_leaf(&head, first)?;
+        if node.len() == 0 {
+            return Ok(None);
+        }
+        let index = if first { 0 } else { node.len() - 1 };
+        Ok(Some(node.key(index).to_vec()))
+    }
+
+    /// 最左或最右的叶子节点
+    fn edge_leaf(&self, head: &TreeHead, first: bool) -> Result<Node, Error> {
+        let mut node = self.load(&head.root())?;
+        while !node.is_leaf() {
+            let index = if first { 0 } else { node.number_children() - 1 };
+            node = self.load(&node.child(index)?)?;
+        }
+        Ok(node)
+    }
+
+    /// 查找第一个满足start的field所在的叶子节点及位置，rev为true时从大到小
+    fn seek(&self, start: Bound<&[u8]>, rev: bool) -> Result<Option<(Node, usize)>, Error> {
+        let head = match self.head()? {
+            None => return Ok(None),
+            Some(h) => h,
+        };
+        let (leaf, index) = match start {
+            Bound::Unbounded => {
+                let leaf = self.edge_leaf(&head, !rev)?;
+                let index = if rev { leaf.len() as isize - 1 } else { 0 };
+                (leaf, index)
+            }
+            Bound::Included(field) | Bound::Excluded(field) => {
+                let (_, leaf) = self.find_leaf(&head.root(), field)?;
+                let included = matches!(start, Bound::Included(_));
+                let index = match (leaf.search(field), rev) {
+                    (Ok(i), false) if !included => i as isize + 1,
+                    (Ok(i), true) if !included => i as isize - 1,
+                    (Ok(i), _) => i as isize,
+                    (Err(i), false) => i as isize,
+                    (Err(i), true) => i as isize - 1,
+                };
+                (leaf, index)
+            }
+        };
+        self.step(leaf, index, rev)
+    }
+
+    /// index超出叶子节点的范围时，移到兄弟节点
+    fn step(&self, mut leaf: Node, mut index: isize, rev: bool) -> Result<Option<(Node, usize)>, Error> {
+        loop {
+            if index >= 0 && (index as usize) < leaf.len() {
+                return Ok(Some((leaf, index as usize)));
+            }
+            let next = if rev { leaf.left() } else { leaf.right() };
+            match next {
+                None => return Ok(None),
+                Some(next) => {
+                    leaf = self.load(&next)?;
+                    index = if rev { leaf.len() as isize - 1 } else { 0 };
+                }
+            }
+        }
+    }
+
+    /// 按顺序(rev为true时逆序)返回从start到end的field，limit为0时不限制个数
+    pub fn scan(&self, start: Bound<&[u8]>, end: Bound<&[u8]>, rev: bool, limit: usize) -> Result<Vec<Vec<u8>>, Error> {
+        let mut re = Vec::new();
+        let (mut leaf, mut index) = match self.seek(start, rev)? {
+            None => return Ok(re),
+            Some(p) => p,
+        };
+        loop {
+            let field = leaf.key(index);
+            let in_range = match (end, rev) {
+                (Bound::Unbounded, _) => true,
+                (Bound::Included(e), false) => field <= e,
+                (Bound::Excluded(e), false) => field < e,
+                (Bound::Included(e), true) => field >= e,
+                (Bound::Excluded(e), true) => field > e,
+            };
+            if !in_range {
+                break;
+            }
+            re.push(field.to_vec());
+            if limit > 0 && re.len() >= limit {
+                break;
+            }
+            let next = if rev { index as isize - 1 } else { index as isize + 1 };
+            match self.step(leaf, next, rev)? {
+                None => break,
+                Some((l, i)) => {
+                    leaf = l;
+                    index = i;
+                }
+            }
+        }
+        Ok(re)
+    }
+
+    /// field的排名(从0开始)，不存在时返回None
+    pub fn rank(&self, field: &[u8]) -> Result<Option<LenType>, Error> {
+        let head = match self.head()? {
+            None => return Ok(None),
+            Some(h) => h,
+        };
+        let (less, leaf) = self.count_before(&head, field)?;
+        Ok(leaf.search(field).ok().map(|i| less + i as LenType))
+    }
+
+    /// 第n个(从0开始)field
+    pub fn select(&self, mut n: LenType) -> Result<Option<Vec<u8>>, Error> {
+        let head = match self.head()? {
+            None => return Ok(None),
+            Some(h) => h,
+        };
+        if n >= head.len() {
+            return Ok(None);
+        }
+        let mut node = self.load(&head.root())?;
+        while !node.is_leaf() {
+            let mut index = 0;
+            while index + 1 < node.number_children() && n >= node.count(index) {
+                n -= node.count(index);
+                index += 1;
+            }
+            node = self.load(&node.child(index)?)?;
+        }
+        if n as usize >= node.len() {
+            return Err(Error::UnexpectedError);
+        }
+        Ok(Some(node.key(n as usize).to_vec()))
+    }
+
+    /// 在lo与hi之间的field的个数
+    pub fn count(&self, lo: Bound<&[u8]>, hi: Bound<&[u8]>) -> Result<LenType, Error> {
+        let head = match self.head()? {
+            None => return Ok(0),
+            Some(h) => h,
+        };
+        let start = match lo {
+            Bound::Unbounded => 0,
+            Bound::Included(f) => self.count_less(&head, f, false)?,
+            Bound::Excluded(f) => self.count_less(&head, f, true)?,
+        };
+        let end = match hi {
+            Bound::Unbounded => head.len(),
+            Bound::Included(f) => self.count_less(&head, f, true)?,
+            Bound::Excluded(f) => self.count_less(&head, f, false)?,
+        };
+        Ok(end.saturating_sub(start))
+    }
+
+    /// 小于field(equal为true时，小于等于)的field的个数
+    fn count_less(&self, head: &TreeHead, field: &[u8], equal: bool) -> Result<LenType, Error> {
+        let (less, leaf) = self.count_before(head, field)?;
+        let index = match leaf.search(field) {
+            Ok(i) if equal => i + 1,
+            Ok(i) => i,
+            Err(i) => i,
+        };
+        Ok(less + index as LenType)
+    }
+
+    /// 查找field所在的叶子节点，并返回此叶子节点之前的所有field的个数
+    fn count_before(&self, head: &TreeHead, field: &[u8]) -> Result<(LenType, Node), Error> {
+        let mut less = 0;
+        let mut node = self.load(&head.root())?;
+        while !node.is_leaf() {
+            let index = node.child_index(field);
+            less += (0..index).map(|i| node.count(i)).sum::<LenType>();
+            node = self.load(&node.child(index)?)?;
         }
+        Ok((less, node))
     }
 
-    fn is_node_underflow(&self, node: &Node) -> Result<bool, Error> {
-        match &node.node_type {
-            // A root cannot really be "underflowing" as it can contain less than b-1 keys / pointers.
-            NodeType::Leaf(pairs) => Ok(pairs.number_keys < self.b - 1 && !node.is_root()),
-            NodeType::Internal(_, keys) => Ok(keys.number_keys < self.b - 1 && !node.is_root()),
-            NodeType::None => Err(Error::UnexpectedError),
+    /// 删除所有的节点及头信息，每个field都会调用一次 on_field
+    pub fn clear<F: FnMut(&[u8]) -> Result<(), Error>>(&self, mut on_field: F) -> Result<(), Error> {
+        let head = match self.head()? {
+            None => return Ok(()),
+            Some(h) => h,
+        };
+        let mut stack = vec![head.root()];
+        while let Some(db_key) = stack.pop() {
+            let node = self.load(&db_key)?;
+            if node.is_leaf() {
+                for field in node.keys() {
+                    on_field(field)?;
+                }
+            } else {
+                stack.extend(node.children().into_iter().map(|(child, _)| child));
+            }
+            self.remove(&node)?;
         }
+        self.t.delete(&make_head_key(&self.key))?;
+        Ok(())
     }
-    //
-    //     fn set_parent_of_children(&self, node: &mut Node) -> Result<(), Error> {
-    //         match &node.node_type {
-    //             NodeType::Internal(children, _) => {
-    //                 let mut offset = children.offset + Children::OFFSET_DATA;
-    //                 let mut child_db_key = DbKey::ZERO_KEY.clone();
-    //                 let parent_db_key = node.parent_db_key().key();
-    //
-    //                 for _ in 0..children.number_children as isize {
-    //                     unsafe {
-    //                         std::ptr::copy_nonoverlapping(node.data.as_ptr().offset(offset), child_db_key.as_mut_ptr(), DbKey::LEN_DB_KEY);
-    //                     }
-    //
-    //                     let mut data = self.t.get(&child_db_key)?.ok_or(Error::KeyNotFound)?;
-    //
-    //                     Node::set_parent_db_key_data(data.as_mut_slice(), parent_db_key);
-    //                     self.t.put(&child_db_key, data.as_slice())?;
-    //
-    //                     offset += DbKey::LEN_DB_KEY as isize;
-    //                 }
-    //             }
-    //             NodeType::Leaf(leaf) => {
-    //                 //todo
-    //             }
-    //             NodeType::None => {}
-    //         }
-    //         Ok(())
-    //     }
-    //
-    //     /// insert a key value pair possibly splitting nodes along the way.
-    //     pub fn insert(&mut self, k: &[u8], v: &[u8]) -> Result<(), Error> {
-    //         // let new_root_offset: Offset;
-    //         let mut new_root: Node;
-    //         let head_key = make_head_key(self.key);
-    //         let mut root = {
-    //             match self.t.get(&head_key)? {
-    //                 Some(data) => Node::try_from(data)?,
-    //                 None => Node::new(NodeType::Internal(Children::new(), VecBytes::new()))
-    //             }
-    //         };
-    //
-    //         if self.is_node_full(&root)? {
-    //             // split the root creating a new root and child nodes along the way.
-    //             let (median, mut sibling) = root.split(self.b)?;
-    //             new_root = Node::new(NodeType::Internal(Children::new(), VecBytes::new()));
-    //             new_root.set_parent_none();
-    //
-    //             root.set_parent_db_key(new_root.db_key().key());
-    //             sibling.set_parent_db_key(new_root.db_key().key());
-    //
-    //             let children = Children::add(&mut new_root, &vec![root.db_key().key(), sibling.db_key().key()]);
-    //
-    //             VecBytes::<KeyMetas>::add(&mut new_root.data, children.offset_keys(), &vec![median.as_slice()]);
-    //
-    //             self.t.put(sibling.db_key().key(), &sibling.data)?;
-    //             self.t.put(root.db_key().key(), &root.data)?;
-    //             self.t.put(new_root.db_key().key(), &new_root.data)?;
-    //             //sibling的所有中的child的每一个 parent_key已变化，所以需要更新
-    //             self.set_parent_of_children(&mut sibling)?;
-    //
-    //             // continue recursively.
-    //             self.insert_non_full(&mut new_root, k, v)?;
-    //             self.t.put(&head_key, &new_root.data)?;
-    //         } else {
-    //             // continue recursively.
-    //             self.insert_non_full(&mut root, k, v)?;
-    //             // finish by setting the root to its new copy.
-    //             self.t.put(&head_key, &root.data)?;
-    //         }
-    //
-    //         Ok(())
-    //     }
-    //
-    //     /// insert_non_full (recursively) finds a node rooted at a given non-full node.
-    //     /// to insert a given key-value pair. Here we assume the node is
-    //     /// already a copy of an existing node in a copy-on-write root to node traversal.
-    //     fn insert_non_full(
-    //         &mut self,
-    //         node: &mut Node,
-    //         k: &[u8], v: &[u8],
-    //     ) -> Result<(), Error> {
-    //         match &mut node.node_type {
-    //             NodeType::Leaf(ref mut pairs) => {
-    //                 let kv = KeyValue::to_vec(k, v);
-    //                 pairs.insert_with_index(&mut node.data, kv.as_slice(), None);
-    //                 self.t.put(node.db_key().key(), &node.data)?;
-    //                 Ok(())
-    //             }
-    //             NodeType::Internal(ref mut children, ref mut keys) => {
-    //                 let idx = keys.binary_search(&mut node.data, k).unwrap_or_else(|x| x);
-    //                 let child_db_key = children.get(&node.data, idx);
-    //                 let bytes = self.t.get(child_db_key.key())?.ok_or(Error::KeyNotFound)?;
-    //                 let mut child = Node::try_from(bytes)?;
-    //                 if self.is_node_full(&child)? {
-    //                     let (median, mut sibling) = child.split(self.b)?;
-    //                     sibling.set_parent_db_key(node.db_key().key());
-    //                     children.insert(idx + 1, sibling.db_key().key());
-    //                     keys.insert(idx, median.clone());
-    //
-    //                     self.t.put(sibling.db_key().key(), &sibling.data)?;
-    //                     self.t.put(child.db_key().key(), &child.data)?;
-    //                     //sibling的所有中的child的每一个 parent_key已变化，所以需要更新
-    //                     self.set_parent_of_children(&mut sibling)?;
-    //
-    //
-    //                     if k <= median.0 {
-    //                         self.insert_non_full(&mut child, k, v)
-    //                     } else {
-    //                         self.insert_non_full(&mut sibling, k, v)
-    //                     }
-    //                 } else {
-    //                     // self.pager.write_page_at_offset(Page::try_from(&*node)?, &node_offset)?;
-    //                     self.insert_non_full(&mut child, k, v)
-    //                 }
-    //
-    //                 self.t.put(node.db_key().key(), &node.data)?;
-    //                 Ok(())
-    //             }
-    //             NodeType::None => Err(Error::UnexpectedError),
-    //         }
-    //     }
-    //
-    //     /// search searches for a specific key in the BTree.
-    //     pub fn search(&mut self, key: &[u8]) -> Result<KeyValue, Error> {
-    //         let root_offset = self.wal.get_root()?;
-    //         let root_page = self.pager.get_page(&root_offset)?;
-    //         let root = Node::try_from(root_page)?;
-    //         self.search_node(root, &key)
-    //     }
-    //
-    //     /// search_node recursively searches a sub tree rooted at node for a key.
-    //     fn search_node(&mut self, node: Node, search: &[u8]) -> Result<KeyValue, Error> {
-    //         match node.node_type {
-    //             NodeType::Internal(children, keys) => {
-    //                 let idx = keys.binary_search(&node.data, search)
-    //                     .unwrap_or_else(|x| x);
-    //                 // Retrieve child page from disk and deserialize.
-    //                 let child_offset = children.get(idx).ok_or(Error::UnexpectedError)?;
-    //                 let page = self.pager.get_page(child_offset)?;
-    //                 let child_node = Node::try_from(page)?;
-    //                 self.search_node(child_node, search)
-    //             }
-    //             NodeType::Leaf(pairs) => {
-    //                 if let Ok(idx) =
-    //                     pairs.binary_search_by_key(&search.to_string(), |pair| pair.key.clone())
-    //                 {
-    //                     return Ok(pairs[idx].clone());
-    //                 }
-    //                 Err(Error::KeyNotFound)
-    //             }
-    //             NodeType::None => Err(Error::UnexpectedError),
-    //         }
-    //     }
-    //
-    //     /// delete deletes a given key from the tree.
-    //     pub fn delete(&mut self, key: &[u8]) -> Result<(), Error> {
-    //         let root_offset = self.wal.get_root()?;
-    //         let root_page = self.pager.get_page(&root_offset)?;
-    //         // Shadow the new root and rewrite it.
-    //         let mut new_root = Node::try_from(root_page)?;
-    //         let new_root_page = Page::try_from(&new_root)?;
-    //         let new_root_offset = self.pager.write_page(new_root_page)?;
-    //         self.delete_key_from_subtree(key, &mut new_root)?;
-    //         self.wal.set_root(new_root_offset)
-    //     }
-    //
-    //     /// delete key from subtree recursively traverses a tree rooted at a node in certain offset
-    //     /// until it finds the given key and delete the key-value pair. Here we assume the node is
-    //     /// already a copy of an existing node in a copy-on-write root to node traversal.
-    //     fn delete_key_from_subtree(
-    //         &mut self,
-    //         key: &[u8],
-    //         node: &mut Node,
-    //     ) -> Result<(), Error> {
-    //         match &mut node.node_type {
-    //             NodeType::Leaf(ref mut pairs) => {
-    //                 let key_idx = pairs
-    //                     .binary_search_by_key(&key, |kv| Key(kv.key.clone()))
-    //                     .map_err(|_| Error::KeyNotFound)?;
-    //                 pairs.remove(key_idx);
-    //                 // self.pager
-    //                 //     .write_page_at_offset(Page::try_from(&*node)?, node_offset)?;
-    //                 // Check for underflow - if it occures,
-    //                 // we need to merge with a sibling.
-    //                 // this can only occur if node is not the root (as it cannot "underflow").
-    //                 // continue recoursively up the tree.
-    //                 self.borrow_if_needed(node.to_owned(), &key)?;
-    //             }
-    //             NodeType::Internal(children, keys) => {
-    //                 let node_idx = keys.binary_search(&key).unwrap_or_else(|x| x);
-    //                 // Retrieve child page from disk and deserialize,
-    //                 // copy over the child page and continue recursively.
-    //                 let child_offset = children.get(node_idx).ok_or(Error::UnexpectedError)?;
-    //                 let child_page = self.pager.get_page(child_offset)?;
-    //                 let mut child_node = Node::try_from(child_page)?;
-    //                 // Fix the parent_offset as the child node is a child of a copied parent
-    //                 // in a copy-on-write root to leaf traversal.
-    //                 // This is important for the case of a node underflow which might require a leaf to root traversal.
-    //                 // child_node.parent_key = Some(node_offset.to_owned());
-    //                 let new_child_page = Page::try_from(&child_node)?;
-    //                 let new_child_offset = self.pager.write_page(new_child_page)?;
-    //                 // Assign the new pointer in the parent and continue reccoursively.
-    //                 children[node_idx] = new_child_offset.to_owned();
-    //                 // self.pager
-    //                 //     .write_page_at_offset(Page::try_from(&*node)?, node_offset)?;
-    //                 return self.delete_key_from_subtree(key, &mut child_node, &new_child_offset);
-    //             }
-    //             NodeType::None => return Err(Error::UnexpectedError),
-    //         }
-    //         Ok(())
-    //     }
-    //
-    //     /// borrow_if_needed checks the node for underflow (following a removal of a key),
-    //     /// if it underflows it is merged with a sibling node, and than called recoursively
-    //     /// up the tree. Since the downward root-to-leaf traversal was done using the copy-on-write
-    //     /// technique we are ensured that any merges will only be reflected in the copied parent in the path.
-    //     fn borrow_if_needed(&mut self, node: Node, key: &[u8]) -> Result<(), Error> {
-    //         if self.is_node_underflow(&node)? {
-    //             // Fetch the sibling from the parent -
-    //             // This could be quicker if we implement sibling pointers.
-    //             let parent_offset = node.parent_key.clone().ok_or(Error::UnexpectedError)?;
-    //             let parent_page = self.pager.get_page(&parent_offset)?;
-    //             let mut parent_node = Node::try_from(parent_page)?;
-    //             // The parent has to be an "internal" node.
-    //             match parent_node.node_type {
-    //                 NodeType::Internal(ref mut children, ref mut keys) => {
-    //                     let idx = keys.binary_search(key).unwrap_or_else(|x| x);
-    //                     // The sibling is in idx +- 1 as the above index led
-    //                     // the downward search to node.
-    //                     let sibling_idx;
-    //                     match idx > 0 {
-    //                         false => sibling_idx = idx + 1,
-    //                         true => sibling_idx = idx - 1,
-    //                     }
-    //
-    //                     let sibling_offset = children.get(sibling_idx).ok_or(Error::UnexpectedError)?;
-    //                     let sibling_page = self.pager.get_page(sibling_offset)?;
-    //                     let sibling = Node::try_from(sibling_page)?;
-    //                     let merged_node = self.merge(node, sibling)?;
-    //                     let merged_node_offset =
-    //                         self.pager.write_page(Page::try_from(&merged_node)?)?;
-    //                     let merged_node_idx = cmp::min(idx, sibling_idx);
-    //                     // remove the old nodes.
-    //                     children.remove(merged_node_idx);
-    //                     // remove shifts nodes to the left.
-    //                     children.remove(merged_node_idx);
-    //                     // if the parent is the root, and there is a single child - the merged node -
-    //                     // we can safely replace the root with the child.
-    //                     if parent_node.is_root && children.is_empty() {
-    //                         self.wal.set_root(merged_node_offset)?;
-    //                         return Ok(());
-    //                     }
-    //                     // remove the keys that separated the two nodes from each other:
-    //                     keys.remove(idx);
-    //                     // write the new node in place.
-    //                     children.insert(merged_node_idx, merged_node_offset);
-    //                     // write the updated parent back to disk and continue up the tree.
-    //                     self.pager
-    //                         .write_page_at_offset(Page::try_from(&parent_node)?, &parent_offset)?;
-    //                     return self.borrow_if_needed(parent_node, key);
-    //                 }
-    //                 _ => return Err(Error::UnexpectedError),
-    //             }
-    //         }
-    //         Ok(())
-    //     }
-    //
-    //     // merges two *sibling* nodes, it assumes the following:
-    //     // 1. the two nodes are of the same type.
-    //     // 2. the two nodes do not accumulate to an overflow,
-    //     // i.e. |first.keys| + |second.keys| <= [2*(b-1) for keys or 2*b for offsets].
-    //     fn merge(&self, first: Node, second: Node) -> Result<Node, Error> {
-    //         match first.node_type {
-    //             NodeType::Leaf(first_pairs) => {
-    //                 if let NodeType::Leaf(second_pairs) = second.node_type {
-    //                     let merged_pairs: LeafData = first_pairs
-    //                         .into_iter()
-    //                         .chain(second_pairs.into_iter())
-    //                         .collect();
-    //                     let node_type = NodeType::Leaf(merged_pairs);
-    //                     Ok(Node::new(node_type))
-    //                 } else {
-    //                     Err(Error::UnexpectedError)
-    //                 }
-    //             }
-    //             NodeType::Internal(first_offsets, first_keys) => {
-    //                 if let NodeType::Internal(second_offsets, second_keys) = second.node_type {
-    //                     let merged_keys = first_keys
-    //                         .into_iter()
-    //                         .chain(second_keys.into_iter())
-    //                         .collect();
-    //                     let merged_offsets = first_offsets
-    //                         .into_iter()
-    //                         .chain(second_offsets.into_iter())
-    //                         .collect();
-    //                     let node_type = NodeType::Internal(merged_offsets, merged_keys);
-    //                     Ok(Node::new(node_type))
-    //                 } else {
-    //                     Err(Error::UnexpectedError)
-    //                 }
-    //             }
-    //             NodeType::None => Err(Error::UnexpectedError),
-    //         }
-    //     }
-    //
-    //     /// print_sub_tree is a helper function for recursively printing the nodes rooted at a node given by its offset.
-    //     fn print_sub_tree(&mut self, prefix: String) -> Result<(), Error> {
-    //         // println!("{}Node at offset: {}", prefix, offset.0);
-    //         let curr_prefix = format!("{}|->", prefix);
-    //         // let page = self.pager.get_page(&offset)?;
-    //         let node = Node::try_from(vec![])?;
-    //         match node.node_type {
-    //             NodeType::Internal(children, keys) => {
-    //                 println!("{}Keys: {:?}", curr_prefix, keys);
-    //                 println!("{}Children: {:?}", curr_prefix, children);
-    //                 let child_prefix = format!("{}   |  ", prefix);
-    //                 for child_offset in children {
-    //                     self.print_sub_tree(child_prefix.clone())?;
-    //                 }
-    //                 Ok(())
-    //             }
-    //             NodeType::Leaf(pairs) => {
-    //                 println!("{}Key value pairs: {:?}", curr_prefix, pairs);
-    //                 Ok(())
-    //             }
-    //             NodeType::None => Err(Error::UnexpectedError),
-    //         }
-    //     }
-    //
-    //     /// print is a helper for recursively printing the tree.
-    //     pub fn print(&mut self) -> Result<(), Error> {
-    //         println!();
-    //         let root_offset = self.wal.get_root()?;
-    //         self.print_sub_tree("".to_string())
-    //     }
 }
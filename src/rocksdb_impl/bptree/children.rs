@@ -1,11 +1,12 @@
 use std::mem::size_of;
 
 use crate::{
-    read_int_ptr,
-    rocksdb_impl::bptree::{db_key::DbKey, node::Node, node_type::NodeType},
-    write_int_ptr, BytesType, LenType,
+    read_int,
+    rocksdb_impl::bptree::{db_key::DbKey, node::Node},
+    write_int, BytesType, LenType,
 };
 
+/// 内部节点中子节点的db key及子树中field的个数, 布局： [number_children][bytes_number][(db_key, count)...]
 #[derive(Clone, Debug)]
 pub struct Children {
     pub number_children: LenType,
@@ -16,19 +17,16 @@ pub struct Children {
 
 impl From<&[u8]> for Children {
     fn from(data: &[u8]) -> Self {
-        let offset = Node::OFFSET_NODE_DATA as isize;
-        let number_children = read_int_ptr(unsafe { data.as_ptr().offset(offset) });
-        let bytes_number = read_int_ptr(unsafe { data.as_ptr().offset(offset + size_of::<LenType>() as isize) });
-        Self {
-            offset,
-            number_children,
-            bytes_number,
-        }
+        let mut children = Children::new();
+        children.read_from(data);
+        children
     }
 }
 
 impl Children {
     pub const OFFSET_DATA: isize = (size_of::<LenType>() + size_of::<BytesType>()) as isize;
+    /// 一个子节点占用的字节数，db key + 子树中field的个数
+    pub const LEN_CHILD: usize = DbKey::LEN_DB_KEY + size_of::<LenType>();
     pub fn new() -> Self {
         Children {
             number_children: 0,
@@ -38,56 +36,77 @@ impl Children {
     }
     pub fn read_from(&mut self, data: &[u8]) {
         self.offset = Node::OFFSET_NODE_DATA as isize;
-        unsafe {
-            self.number_children = read_int_ptr(data.as_ptr().offset(self.offset));
-            self.bytes_number = read_int_ptr(data.as_ptr().offset(self.offset + size_of::<LenType>() as isize));
-        }
+        self.number_children = read_int(&data[self.offset as usize..]);
+        self.bytes_number = read_int(&data[self.offset as usize + size_of::<LenType>()..]);
+    }
+
+    pub fn len(&self) -> usize {
+        self.number_children as usize
     }
 
     pub fn get(&self, data: &[u8], index: usize) -> DbKey {
-        let start = self.offset as usize + Children::OFFSET_DATA as usize + index * DbKey::LEN_DB_KEY;
-        DbKey::from(&data[start..])
+        DbKey::from(&data[self.child_offset(index)..])
+    }
+
+    /// index处子树中field的个数
+    pub fn count(&self, data: &[u8], index: usize) -> LenType {
+        read_int(&data[self.child_offset(index) + DbKey::LEN_DB_KEY..])
+    }
+
+    pub fn set_count(&self, data: &mut [u8], index: usize, count: LenType) {
+        write_int(&mut data[self.child_offset(index) + DbKey::LEN_DB_KEY..], count);
+    }
+
+    /// 按顺序返回所有子节点
+    pub fn all(&self, data: &[u8]) -> Vec<(DbKey, LenType)> {
+        (0..self.len()).map(|i| (self.get(data, i), self.count(data, i))).collect()
+    }
+
+    /// 在index处插入子节点，后面的keys数据会整体后移 LEN_CHILD
+    pub fn insert(&mut self, data: &mut Vec<u8>, index: usize, child: &DbKey, count: LenType) {
+        let start = self.child_offset(index);
+        data.splice(start..start, std::iter::repeat_n(0, Children::LEN_CHILD));
+        data[start..start + DbKey::LEN_DB_KEY].copy_from_slice(child.key());
+        self.set_number_children(self.number_children + 1, data);
+        self.set_count(data, index, count);
+    }
+
+    /// 删除index处的子节点，后面的keys数据会整体前移 LEN_CHILD
+    pub fn remove(&mut self, data: &mut Vec<u8>, index: usize) -> (DbKey, LenType) {
+        let start = self.child_offset(index);
+        let child = (DbKey::from(&data[start..]), self.count(data, index));
+        data.drain(start..start + Children::LEN_CHILD);
+        self.set_number_children(self.number_children - 1, data);
+        child
     }
 
     pub fn set_number_children(&mut self, number_children: LenType, data: &mut [u8]) {
         self.number_children = number_children;
-        self.bytes_number = self.number_children as BytesType * DbKey::LEN_DB_KEY as BytesType;
-        unsafe {
-            write_int_ptr(data.as_mut_ptr().offset(self.offset), self.number_children);
-            write_int_ptr(data.as_mut_ptr().offset(self.offset + size_of::<LenType>() as isize), self.bytes_number);
-        }
+        self.bytes_number = self.number_children as BytesType * Children::LEN_CHILD as BytesType;
+        write_int(&mut data[self.offset as usize..], self.number_children);
+        write_int(&mut data[self.offset as usize + size_of::<LenType>()..], self.bytes_number);
     }
 
     pub fn offset_keys(&self) -> isize {
         self.offset + self.bytes_number as isize + Children::OFFSET_DATA as isize
     }
 
-    pub fn add(node: &mut Node, children: &[&[u8]]) -> Children {
-        if let NodeType::Internal(old_children, _) = &mut node.node_type {
-            let new_bytes = DbKey::LEN_DB_KEY * children.len();
-            node.data.reserve_exact(new_bytes);
-            unsafe {
-                //移动keys的数据
-                let _old = old_children.number_children;
-                let offset_keys = old_children.offset + Children::OFFSET_DATA + old_children.bytes_number as isize;
-                std::ptr::copy(
-                    node.data.as_mut_ptr().offset(offset_keys),
-                    node.data.as_mut_ptr().offset(offset_keys + new_bytes as isize),
-                    node.data.len() - offset_keys as usize - new_bytes,
-                );
-                node.data.set_len(node.data.len() + new_bytes);
-            }
-            let mut offset_keys = old_children.offset + Children::OFFSET_DATA + old_children.bytes_number as isize;
-            for k in children {
-                unsafe {
-                    std::ptr::copy_nonoverlapping(k.as_ptr(), node.data.as_mut_ptr().offset(offset_keys), k.len());
-                }
-                offset_keys += k.len() as isize;
-            }
-            old_children.set_number_children(old_children.number_children + children.len() as LenType, &mut node.data);
-            old_children.clone()
-        } else {
-            panic!("the node type is not Internal");
+    /// 从offset开始写入children，原offset之后的数据会被丢弃
+    pub fn add(data: &mut Vec<u8>, children: &[(DbKey, LenType)]) -> Children {
+        let mut re = Children::new();
+        data.truncate(re.offset as usize);
+        data.resize(re.offset as usize + Children::OFFSET_DATA as usize, 0);
+        for (child, count) in children {
+            data.extend_from_slice(child.key());
+            let start = data.len();
+            data.resize(start + size_of::<LenType>(), 0);
+            write_int(&mut data[start..], *count);
         }
+        re.set_number_children(children.len() as LenType, data);
+        re
+    }
+
+    fn child_offset(&self, index: usize) -> usize {
+        self.offset as usize + Children::OFFSET_DATA as usize + index * Children::LEN_CHILD
     }
 }
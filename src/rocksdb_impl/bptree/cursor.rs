@@ -0,0 +1,62 @@
+use std::ops::Bound;
+
+use super::btree::BTree;
+use crate::{rocksdb_impl::shared::make_field_key, RrError, WrapDb};
+
+/// 按field的顺序遍历b+ tree的游标
+/// 游标只记录最后返回的field，每次读取时从root重新定位，然后沿着叶子节点的兄弟节点读取，
+/// 所以两次读取之间修改了数据，游标依然有效，也可以保存 last_field 之后再重新创建游标
+pub struct BPTreeCursor {
+    key: Vec<u8>,
+    position: Bound<Vec<u8>>,
+    end: Bound<Vec<u8>>,
+    rev: bool,
+}
+
+impl BPTreeCursor {
+    /// rev为false时，从小到大(start <= end)，为true时从大到小(start >= end)
+    pub fn new(key: &[u8], start_field: Bound<&[u8]>, end_field: Bound<&[u8]>, rev: bool) -> Self {
+        BPTreeCursor {
+            key: key.to_vec(),
+            position: start_field.map(|f| f.to_vec()),
+            end: end_field.map(|f| f.to_vec()),
+            rev,
+        }
+    }
+
+    /// 读取下一个，没有时返回None
+    /// 返回值 0: field, 1: field value
+    pub fn next<T: WrapDb>(&mut self, t: &T) -> Result<Option<(Vec<u8>, Vec<u8>)>, RrError> {
+        Ok(self.next_n(t, 1)?.pop())
+    }
+
+    /// 读取最多limit个，返回的个数小于limit时，说明已经读取完成
+    pub fn next_n<T: WrapDb>(&mut self, t: &T, limit: usize) -> Result<Vec<(Vec<u8>, Vec<u8>)>, RrError> {
+        if limit == 0 {
+            return Ok(Vec::new());
+        }
+        let fields = BTree::new(&self.key, t).scan(
+            self.position.as_ref().map(|f| f.as_slice()),
+            self.end.as_ref().map(|f| f.as_slice()),
+            self.rev,
+            limit,
+        )?;
+        if let Some(last) = fields.last() {
+            self.position = Bound::Excluded(last.clone());
+        }
+        let mut re = Vec::with_capacity(fields.len());
+        for field in fields {
+            let v = t.get(&make_field_key(&self.key, &field))?.unwrap_or_default();
+            re.push((field, v));
+        }
+        Ok(re)
+    }
+
+    /// 最后返回的field
+    pub fn last_field(&self) -> Option<&[u8]> {
+        match &self.position {
+            Bound::Excluded(f) => Some(f),
+            _ => None,
+        }
+    }
+}
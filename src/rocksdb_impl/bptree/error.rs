@@ -1,3 +1,5 @@
+use std::fmt::{Display, Formatter};
+
 use crate::RrError;
 
 #[derive(Debug)]
@@ -12,6 +14,17 @@ pub enum Error {
     RrError(RrError),
 }
 
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::RrError(e) => write!(f, "{}", e),
+            _ => write!(f, "bptree: {:?}", self),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
 impl std::convert::From<std::io::Error> for Error {
     fn from(_e: std::io::Error) -> Error {
         Error::UnexpectedError
@@ -23,3 +36,12 @@ impl From<RrError> for Error {
         Error::RrError(e)
     }
 }
+
+impl From<Error> for RrError {
+    fn from(e: Error) -> RrError {
+        match e {
+            Error::RrError(e) => e,
+            _ => RrError::Other(anyhow::Error::from(e)),
+        }
+    }
+}
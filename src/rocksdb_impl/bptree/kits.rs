@@ -1,3 +1,6 @@
-pub fn new_db_key() -> Vec<u8> {
-    xid::new().to_string().as_bytes().to_vec()
+use crate::rocksdb_impl::bptree::db_key::DbKey;
+
+/// 使用xid的原始12个字节，与 DbKey::LEN_DB_KEY 一致
+pub fn new_db_key() -> DbKey {
+    DbKey::from(xid::new().as_bytes().as_slice())
 }
@@ -1,16 +1,15 @@
-// pub use node::*;
-// pub use node_type::*;
-// pub use error::Error;
-// pub use page::*;
-// pub use page_layout::*;
-// pub use wal::*;
+pub use bptree_impl::BPTreeImpl;
+pub(crate) use btree::BTree;
+pub use cursor::BPTreeCursor;
 
 mod bptree_impl;
 mod btree;
 mod children;
+mod cursor;
 mod db_key;
 mod error;
 mod kits;
 mod leaf_data;
 mod node;
 mod node_type;
+mod tree_head;
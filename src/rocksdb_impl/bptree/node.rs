@@ -2,10 +2,8 @@ use std::{convert::TryFrom, mem::size_of};
 
 use super::{error::Error, node_type::NodeType};
 use crate::{
-    datas::VecBytes,
-    read_int_ptr,
     rocksdb_impl::bptree::{children::Children, db_key::DbKey, kits::new_db_key, leaf_data::LeafData, node_type::Keys},
-    BytesType, LenType,
+    LenType,
 };
 
 /// Node represents a node in the BTree occupied by a single page in memory.
@@ -17,63 +15,48 @@ pub struct Node {
 
 // Node represents a node in the B-Tree.
 impl Node {
-    /// Common Node header layout (Ten bytes in total)
+    /// Common Node header layout (25 bytes in total)
     pub const OFFSET_NODE_TYPE: isize = 0;
     pub const OFFSET_DB_KEY: isize = Node::OFFSET_NODE_TYPE + size_of::<u8>() as isize;
     pub const OFFSET_PARENT_DB_KEY: isize = Node::OFFSET_DB_KEY + DbKey::LEN_DB_KEY as isize;
     pub const OFFSET_NODE_DATA: isize = Node::OFFSET_PARENT_DB_KEY + DbKey::LEN_DB_KEY as isize;
+    /// leaf node layout: [header][left db key][right db key][leaf data]
+    pub const OFFSET_LEFT: isize = Node::OFFSET_NODE_DATA;
+    pub const OFFSET_RIGHT: isize = Node::OFFSET_LEFT + DbKey::LEN_DB_KEY as isize;
+    pub const OFFSET_LEAF_DATA: isize = Node::OFFSET_RIGHT + DbKey::LEN_DB_KEY as isize;
 
+    /// 创建一个空的节点，并分配新的db key
     pub fn new(node_type: NodeType) -> Node {
-        let mut data = Vec::with_capacity(Node::OFFSET_NODE_DATA as usize);
-        data.resize(data.capacity(), 0);
-        data[0] = u8::from(&node_type);
-        let mut node = Node { node_type, data };
-        node.set_node_type(&[]);
+        let mut node = Node {
+            node_type,
+            data: vec![0; Node::OFFSET_NODE_DATA as usize],
+        };
+        node.data[Node::OFFSET_NODE_TYPE as usize] = u8::from(&node.node_type);
         node.make_db_key();
-        node
-    }
-
-    pub fn set_node_type(&mut self, data: &[u8]) {
-        let node_type = &self.node_type;
-        match node_type {
-            NodeType::None => {
-                unsafe {
-                    self.data.set_len(Node::OFFSET_NODE_DATA as usize);
-                }
-                let t = [0 as u8; Node::OFFSET_NODE_DATA as usize];
-                self.data.extend_from_slice(&t);
-            }
-            NodeType::Internal(_children, _keys) => {
-                if data.is_empty() {
-                    unsafe {
-                        self.data.set_len(Node::OFFSET_NODE_DATA as usize);
-                    }
-                    let t = [0 as u8; Node::OFFSET_NODE_DATA as usize];
-                    self.data.extend_from_slice(&t);
-                } else {
-                    self.data.reserve(data.len() - self.data.len());
-                    unsafe {
-                        self.data.set_len(data.len());
-                    }
-                    self.data.copy_from_slice(data);
-                }
+        match &mut node.node_type {
+            NodeType::Internal(children, keys) => {
+                *children = Children::add(&mut node.data, &[]);
+                *keys = Keys::add(&mut node.data, children.offset_keys(), &[]);
             }
-            NodeType::Leaf(_leaf) => {
-                if data.is_empty() {
-                    unsafe {
-                        self.data.set_len(Node::OFFSET_NODE_DATA as usize);
-                    }
-                    let t = [0 as u8; Node::OFFSET_NODE_DATA as usize];
-                    self.data.extend_from_slice(&t);
-                } else {
-                    self.data.reserve(data.len() - self.data.len());
-                    unsafe {
-                        self.data.set_len(data.len());
-                    }
-                    self.data.copy_from_slice(data);
-                }
+            NodeType::Leaf(leaf) => {
+                node.data.resize(Node::OFFSET_LEAF_DATA as usize, 0);
+                *leaf = LeafData::add(&mut node.data, Node::OFFSET_LEAF_DATA, &[]);
             }
+            NodeType::None => {}
         }
+        node
+    }
+
+    pub fn new_internal(children: &[(DbKey, LenType)], keys: &[&[u8]]) -> Node {
+        let mut node = Node::new(NodeType::Internal(Children::new(), Keys::new()));
+        node.set_internal(children, keys);
+        node
+    }
+
+    pub fn new_leaf(fields: &[&[u8]]) -> Node {
+        let mut node = Node::new(NodeType::Leaf(LeafData::new()));
+        node.set_leaf(fields);
+        node
     }
 
     pub fn is_root(&self) -> bool {
@@ -81,9 +64,13 @@ impl Node {
         parent.key().eq(&DbKey::ZERO_KEY)
     }
 
+    pub fn is_leaf(&self) -> bool {
+        matches!(self.node_type, NodeType::Leaf(_))
+    }
+
     pub fn make_db_key(&mut self) {
         let n = new_db_key();
-        unsafe { std::ptr::copy(n.as_ptr(), self.data.as_mut_ptr().offset(Node::OFFSET_DB_KEY), n.len()) }
+        self.set_db_key(n.key());
     }
 
     pub fn db_key(&self) -> DbKey {
@@ -91,9 +78,8 @@ impl Node {
     }
 
     pub fn set_db_key(&mut self, key: &[u8]) {
-        unsafe {
-            std::ptr::copy(key.as_ptr(), self.data.as_mut_ptr().offset(Node::OFFSET_DB_KEY), key.len());
-        }
+        let start = Node::OFFSET_DB_KEY as usize;
+        self.data[start..start + DbKey::LEN_DB_KEY].copy_from_slice(key);
     }
 
     pub fn parent_db_key(&self) -> DbKey {
@@ -108,101 +94,261 @@ impl Node {
     }
 
     pub fn set_parent_db_key_data(data: &mut [u8], key: &[u8]) {
-        unsafe {
-            std::ptr::copy(key.as_ptr(), data.as_mut_ptr().offset(Node::OFFSET_PARENT_DB_KEY), key.len());
+        let start = Node::OFFSET_PARENT_DB_KEY as usize;
+        data[start..start + DbKey::LEN_DB_KEY].copy_from_slice(key);
+    }
+
+    /// 左边的兄弟叶子节点
+    pub fn left(&self) -> Option<DbKey> {
+        self.link(Node::OFFSET_LEFT)
+    }
+
+    pub fn set_left(&mut self, left: Option<&DbKey>) {
+        Node::set_link_data(&mut self.data, Node::OFFSET_LEFT, left);
+    }
+
+    /// 右边的兄弟叶子节点
+    pub fn right(&self) -> Option<DbKey> {
+        self.link(Node::OFFSET_RIGHT)
+    }
+
+    pub fn set_right(&mut self, right: Option<&DbKey>) {
+        Node::set_link_data(&mut self.data, Node::OFFSET_RIGHT, right);
+    }
+
+    /// 修改叶子节点数据中的兄弟节点，offset为 OFFSET_LEFT 或 OFFSET_RIGHT
+    pub fn set_link_data(data: &mut [u8], offset: isize, key: Option<&DbKey>) {
+        let start = offset as usize;
+        let key = key.map(|k| k.key()).unwrap_or(&DbKey::ZERO_KEY);
+        data[start..start + DbKey::LEN_DB_KEY].copy_from_slice(key);
+    }
+
+    fn link(&self, offset: isize) -> Option<DbKey> {
+        if !self.is_leaf() {
+            return None;
+        }
+        let key = &self.data[offset as usize..offset as usize + DbKey::LEN_DB_KEY];
+        if key.eq(&DbKey::ZERO_KEY) {
+            None
+        } else {
+            Some(DbKey::from(key))
+        }
+    }
+
+    /// 内部节点为key的个数，叶子节点为field的个数
+    pub fn len(&self) -> usize {
+        match &self.node_type {
+            NodeType::Internal(_, keys) => keys.len(),
+            NodeType::Leaf(leaf) => leaf.len(),
+            NodeType::None => 0,
+        }
+    }
+
+    /// 内部节点的key，或叶子节点的field
+    pub fn keys(&self) -> Vec<&[u8]> {
+        match &self.node_type {
+            NodeType::Internal(_, keys) => keys.keys(&self.data),
+            NodeType::Leaf(leaf) => leaf.keys(&self.data),
+            NodeType::None => vec![],
+        }
+    }
+
+    pub fn key(&self, index: usize) -> &[u8] {
+        match &self.node_type {
+            NodeType::Internal(_, keys) => keys.get(&self.data, index),
+            NodeType::Leaf(leaf) => leaf.get(&self.data, index),
+            NodeType::None => &[],
+        }
+    }
+
+    /// 所有的子节点及子树中field的个数
+    pub fn children(&self) -> Vec<(DbKey, LenType)> {
+        match &self.node_type {
+            NodeType::Internal(children, _) => children.all(&self.data),
+            _ => vec![],
+        }
+    }
+
+    /// 子树中field的个数
+    pub fn count(&self, index: usize) -> LenType {
+        match &self.node_type {
+            NodeType::Internal(children, _) => children.count(&self.data, index),
+            _ => 0,
+        }
+    }
+
+    pub fn set_count(&mut self, index: usize, count: LenType) {
+        if let NodeType::Internal(children, _) = &self.node_type {
+            children.set_count(&mut self.data, index, count);
+        }
+    }
+
+    /// 以当前节点为根的子树中field的个数
+    pub fn subtree_len(&self) -> LenType {
+        match &self.node_type {
+            NodeType::Internal(children, _) => (0..children.len()).map(|i| children.count(&self.data, i)).sum(),
+            NodeType::Leaf(leaf) => leaf.len() as LenType,
+            NodeType::None => 0,
+        }
+    }
+
+    pub fn child(&self, index: usize) -> Result<DbKey, Error> {
+        match &self.node_type {
+            NodeType::Internal(children, _) if index < children.len() => Ok(children.get(&self.data, index)),
+            _ => Err(Error::UnexpectedError),
+        }
+    }
+
+    pub fn number_children(&self) -> usize {
+        match &self.node_type {
+            NodeType::Internal(children, _) => children.len(),
+            _ => 0,
+        }
+    }
+
+    /// 查找field所在子节点的位置, 子节点i中的field都小于key i，大于等于key i-1
+    pub fn child_index(&self, field: &[u8]) -> usize {
+        match &self.node_type {
+            NodeType::Internal(_, keys) => match keys.binary_search(&self.data, field) {
+                Ok(i) => i + 1,
+                Err(i) => i,
+            },
+            _ => 0,
+        }
+    }
+
+    /// 在叶子节点中查找field
+    pub fn search(&self, field: &[u8]) -> Result<usize, usize> {
+        match &self.node_type {
+            NodeType::Leaf(leaf) => leaf.binary_search(&self.data, field),
+            _ => Err(0),
+        }
+    }
+
+    /// 在叶子节点的index处插入field
+    pub fn insert_field(&mut self, index: usize, field: &[u8]) -> Result<(), Error> {
+        match &mut self.node_type {
+            NodeType::Leaf(leaf) => {
+                leaf.insert_with_index(&mut self.data, field, Some(index));
+                Ok(())
+            }
+            _ => Err(Error::UnexpectedError),
+        }
+    }
+
+    /// 删除叶子节点index处的field
+    pub fn remove_field(&mut self, index: usize) -> Result<Vec<u8>, Error> {
+        match &mut self.node_type {
+            NodeType::Leaf(leaf) => Ok(leaf.remove(&mut self.data, index)),
+            _ => Err(Error::UnexpectedError),
+        }
+    }
+
+    /// 在内部节点中插入key与子节点
+    pub fn insert_key_child(&mut self, key_index: usize, key: &[u8], child_index: usize, child: &DbKey, count: LenType) -> Result<(), Error> {
+        match &mut self.node_type {
+            NodeType::Internal(children, keys) => {
+                children.insert(&mut self.data, child_index, child, count);
+                keys.offset = children.offset_keys();
+                keys.insert_with_index(&mut self.data, key, Some(key_index));
+                Ok(())
+            }
+            _ => Err(Error::UnexpectedError),
+        }
+    }
+
+    /// 删除内部节点的key与子节点
+    pub fn remove_key_child(&mut self, key_index: usize, child_index: usize) -> Result<(Vec<u8>, (DbKey, LenType)), Error> {
+        match &mut self.node_type {
+            NodeType::Internal(children, keys) => {
+                let key = keys.remove(&mut self.data, key_index);
+                let child = children.remove(&mut self.data, child_index);
+                keys.offset = children.offset_keys();
+                Ok((key, child))
+            }
+            _ => Err(Error::UnexpectedError),
+        }
+    }
+
+    /// 替换内部节点的key
+    pub fn set_key(&mut self, index: usize, key: &[u8]) -> Result<(), Error> {
+        match &mut self.node_type {
+            NodeType::Internal(_, keys) => {
+                keys.set(&mut self.data, index, key);
+                Ok(())
+            }
+            _ => Err(Error::UnexpectedError),
         }
     }
 
     /// split creates a sibling node from a given node by splitting the node in two around a median.
-    /// split will split the child at b leaving the [0, b-1] keys
-    /// while moving the set of [b, 2b-1] keys to the sibling.
+    /// internal: 保留[0, at)的key，key at上移到父节点，[at + 1, len)移到sibling
+    /// leaf: 保留[0, at)的field，[at, len)移到sibling，sibling的第一个field复制到父节点，sibling在当前节点的右边
     pub fn split(&mut self, at: u64) -> Result<(Vec<u8>, Node), Error> {
-        let node = self;
         let at = at as usize;
-        match &mut node.node_type {
-            NodeType::Internal(children, keys) => {
-                let mut new_children = Children::new();
-                let mut new_keys = VecBytes::new();
-                let mut mid_key = vec![];
-
-                let mut new_data = Vec::with_capacity(Node::OFFSET_NODE_DATA as usize + node.data.len() / 2);
-                // let mut re = Vec::with_capacity(keys.bytes_number as usize);
-                let new_offset = Node::OFFSET_NODE_DATA as isize;
-                unsafe {
-                    new_children.set_number_children(children.number_children - at as LenType, &mut new_data);
-                    children.set_number_children(at as LenType, &mut node.data);
-
-                    let start = new_offset + Children::OFFSET_DATA + children.number_children as isize * DbKey::LEN_DB_KEY as isize;
-                    let count = new_children.number_children as usize * DbKey::LEN_DB_KEY;
-                    std::ptr::copy_nonoverlapping(
-                        node.data.as_ptr().offset(start),
-                        new_data.as_mut_ptr().offset(Node::OFFSET_NODE_DATA as isize + Children::OFFSET_DATA),
-                        count,
-                    );
-
-                    std::ptr::copy(
-                        node.data.as_ptr().offset(start + count as isize),
-                        node.data.as_mut_ptr().offset(start),
-                        node.data.len() - start as usize - count,
-                    );
-                    node.data.set_len(node.data.len() - count);
-                }
-                new_keys.offset = new_children.offset_keys();
-                new_keys.set_number_keys(at as LenType - 1, &mut new_data);
-
-                let mut offset_original = keys.offset + Keys::OFFSET_DATA;
-                unsafe {
-                    for i in 0..keys.number_keys {
-                        if i as usize == at {
-                            let b = read_int_ptr::<BytesType>(node.data.as_ptr().offset(offset_original));
-                            mid_key.reserve_exact(b as usize);
-                            mid_key.set_len(b as usize);
-                            std::ptr::copy_nonoverlapping(
-                                node.data.as_ptr().offset(offset_original + size_of::<BytesType>() as isize),
-                                new_data.as_mut_ptr(),
-                                mid_key.len(),
-                            );
-                            let temp_offset = offset_original + b as isize + size_of::<BytesType>() as isize;
-
-                            let new_offset = new_keys.offset + Keys::OFFSET_DATA;
-                            new_keys.set_bytes_data(node.data.len() as BytesType - temp_offset as BytesType, &mut new_data);
-                            std::ptr::copy_nonoverlapping(
-                                node.data.as_ptr().offset(temp_offset),
-                                new_data.as_mut_ptr().offset(new_offset),
-                                new_keys.bytes_data as usize,
-                            );
-                            break;
-                        }
-                        let b = read_int_ptr::<BytesType>(node.data.as_ptr().offset(offset_original));
-                        offset_original += b as isize + size_of::<BytesType>() as isize;
-                    }
-                    keys.set_number_keys(at as LenType - 1, &mut node.data);
-                    keys.set_bytes_data((offset_original - keys.offset - Keys::OFFSET_DATA) as BytesType, &mut node.data);
-                    node.data.set_len(offset_original as usize);
-                }
-                Ok((
-                    mid_key,
-                    Node {
-                        node_type: NodeType::Internal(new_children, new_keys),
-                        data: new_data,
-                    },
-                ))
+        let (mid_key, mut sibling) = match &self.node_type {
+            NodeType::Internal(_, _) => {
+                let keys: Vec<Vec<u8>> = self.keys().iter().map(|k| k.to_vec()).collect();
+                let children = self.children();
+                let sibling_keys: Vec<&[u8]> = keys[at + 1..].iter().map(|k| k.as_slice()).collect();
+                let sibling = Node::new_internal(&children[at + 1..], &sibling_keys);
+                let left_keys: Vec<&[u8]> = keys[..at].iter().map(|k| k.as_slice()).collect();
+                self.set_internal(&children[..=at], &left_keys);
+                (keys[at].clone(), sibling)
             }
-            NodeType::Leaf(_leaf) => {
-                //todo
-                let new_leaf = LeafData::new();
-                let new_data = Vec::with_capacity(Node::OFFSET_NODE_DATA as usize);
-                Ok((
-                    vec![],
-                    Node {
-                        data: new_data,
-                        node_type: NodeType::Leaf(new_leaf),
-                    },
-                ))
+            NodeType::Leaf(_) => {
+                let fields: Vec<Vec<u8>> = self.keys().iter().map(|k| k.to_vec()).collect();
+                let sibling_fields: Vec<&[u8]> = fields[at..].iter().map(|k| k.as_slice()).collect();
+                let mut sibling = Node::new_leaf(&sibling_fields);
+                sibling.set_left(Some(&self.db_key()));
+                sibling.set_right(self.right().as_ref());
+                self.set_right(Some(&sibling.db_key()));
+                let left_fields: Vec<&[u8]> = fields[..at].iter().map(|k| k.as_slice()).collect();
+                self.set_leaf(&left_fields);
+                (fields[at].clone(), sibling)
             }
-            NodeType::None => Err(Error::UnexpectedError),
+            NodeType::None => return Err(Error::UnexpectedError),
+        };
+        sibling.set_parent_db_key(self.parent_db_key().key());
+        Ok((mid_key, sibling))
+    }
+
+    /// 把右边的兄弟节点合并到当前节点，median为父节点中两者之间的key(叶子节点不使用)
+    /// 叶子节点合并后，当前节点的右兄弟为right的右兄弟
+    pub fn merge(&mut self, median: &[u8], right: &Node) -> Result<(), Error> {
+        match (&self.node_type, &right.node_type) {
+            (NodeType::Internal(_, _), NodeType::Internal(_, _)) => {
+                let mut keys: Vec<Vec<u8>> = self.keys().iter().map(|k| k.to_vec()).collect();
+                keys.push(median.to_vec());
+                keys.extend(right.keys().iter().map(|k| k.to_vec()));
+                let mut children = self.children();
+                children.extend(right.children());
+                let keys: Vec<&[u8]> = keys.iter().map(|k| k.as_slice()).collect();
+                self.set_internal(&children, &keys);
+                Ok(())
+            }
+            (NodeType::Leaf(_), NodeType::Leaf(_)) => {
+                let mut fields: Vec<Vec<u8>> = self.keys().iter().map(|k| k.to_vec()).collect();
+                fields.extend(right.keys().iter().map(|k| k.to_vec()));
+                let fields: Vec<&[u8]> = fields.iter().map(|k| k.as_slice()).collect();
+                self.set_leaf(&fields);
+                self.set_right(right.right().as_ref());
+                Ok(())
+            }
+            _ => Err(Error::UnexpectedError),
         }
     }
+
+    fn set_internal(&mut self, children: &[(DbKey, LenType)], keys: &[&[u8]]) {
+        let c = Children::add(&mut self.data, children);
+        let k = Keys::add(&mut self.data, c.offset_keys(), keys);
+        self.node_type = NodeType::Internal(c, k);
+    }
+
+    fn set_leaf(&mut self, fields: &[&[u8]]) {
+        let leaf = LeafData::add(&mut self.data, Node::OFFSET_LEAF_DATA, fields);
+        self.node_type = NodeType::Leaf(leaf);
+    }
 }
 
 /// Implement TryFrom<Page> for Node allowing for easier
@@ -210,6 +356,9 @@ impl Node {
 impl TryFrom<Vec<u8>> for Node {
     type Error = Error;
     fn try_from(data: Vec<u8>) -> Result<Node, Error> {
+        if data.len() < Node::OFFSET_NODE_DATA as usize {
+            return Err(Error::UnexpectedError);
+        }
         let raw = data.as_slice();
         let node_type = NodeType::from(raw[Node::OFFSET_NODE_TYPE as usize]);
 
@@ -224,7 +373,7 @@ impl TryFrom<Vec<u8>> for Node {
             }
 
             NodeType::Leaf(mut leaf) => {
-                leaf.read_from(raw, Node::OFFSET_NODE_DATA);
+                leaf.read_from(raw, Node::OFFSET_LEAF_DATA);
                 Ok(Node {
                     node_type: NodeType::Leaf(leaf),
                     data,
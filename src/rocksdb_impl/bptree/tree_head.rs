@@ -0,0 +1,50 @@
+use std::mem::size_of;
+
+use super::error::Error;
+use crate::{read_int, rocksdb_impl::bptree::db_key::DbKey, write_int, LenType};
+
+/// 存放在 make_head_key(key) 中， 布局： [len][root db key]
+pub struct TreeHead([u8; TreeHead::LEN]);
+
+impl TreeHead {
+    const OFFSET_LEN: usize = 0;
+    const OFFSET_ROOT: usize = TreeHead::OFFSET_LEN + size_of::<LenType>();
+    pub const LEN: usize = TreeHead::OFFSET_ROOT + DbKey::LEN_DB_KEY;
+
+    pub fn new(root: &DbKey) -> Self {
+        let mut head = TreeHead([0; TreeHead::LEN]);
+        head.set_root(root);
+        head
+    }
+
+    /// field的个数
+    pub fn len(&self) -> LenType {
+        read_int(&self.0[TreeHead::OFFSET_LEN..])
+    }
+
+    pub fn set_len(&mut self, len: LenType) {
+        write_int(&mut self.0[TreeHead::OFFSET_LEN..], len)
+    }
+
+    pub fn root(&self) -> DbKey {
+        DbKey::from(&self.0[TreeHead::OFFSET_ROOT..])
+    }
+
+    pub fn set_root(&mut self, root: &DbKey) {
+        self.0[TreeHead::OFFSET_ROOT..].copy_from_slice(root.key());
+    }
+}
+
+impl TryFrom<Vec<u8>> for TreeHead {
+    type Error = Error;
+    fn try_from(value: Vec<u8>) -> Result<Self, Self::Error> {
+        let data = value.as_slice().try_into().map_err(|_| Error::TryFromSliceError("tree head"))?;
+        Ok(TreeHead(data))
+    }
+}
+
+impl AsRef<[u8]> for TreeHead {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
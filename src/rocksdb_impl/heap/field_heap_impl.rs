@@ -0,0 +1,341 @@
+use std::marker::PhantomData;
+
+use crate::{
+    rocksdb_impl::{
+        heap::heap::{field_values, FieldHeap, HeapCompare},
+        shared::{make_field_key, make_head_key},
+    },
+    Heap, HeapIter, HeapOrder, LenType, RrError, WrapDb,
+};
+
+/// [crate::MaxHeap]与[crate::MinHeap]的实现，它们只有比较的方式(C)不同
+pub(crate) struct FieldHeapImpl<C> {
+    /// 新建heap时保存在header中，打开已有的heap时检查是否一致
+    order: HeapOrder,
+    _mark: PhantomData<C>,
+}
+
+impl<C> FieldHeapImpl<C> {
+    pub(crate) fn new(order: HeapOrder) -> Self {
+        FieldHeapImpl { order, _mark: PhantomData }
+    }
+}
+
+impl<C> Default for FieldHeapImpl<C> {
+    fn default() -> Self {
+        FieldHeapImpl::new(HeapOrder::default())
+    }
+}
+
+impl<T: WrapDb, C: HeapCompare> Heap<T> for FieldHeapImpl<C> {
+    fn peek(&self, t: &T, key: &[u8]) -> Result<Option<(Vec<u8>, Vec<u8>)>, RrError> {
+        let head_key = make_head_key(key);
+        let mut heap = {
+            match t.get(&head_key)? {
+                None => return Ok(None),
+                Some(v) => FieldHeap::new(v, &self.order)?,
+            }
+        };
+        let p = &mut heap as *mut _;
+        heap.init(C::from_heap(p));
+        let field = match heap.peek() {
+            None => return Ok(None),
+            Some(f) => f,
+        };
+        let field_key = make_field_key(key, &field);
+        let v = {
+            match t.get(&field_key)? {
+                None => vec![],
+                Some(v) => v,
+            }
+        };
+        Ok(Some((field, v)))
+    }
+
+    fn pop(&self, t: &T, key: &[u8]) -> Result<Option<(Vec<u8>, Vec<u8>)>, RrError> {
+        let head_key = make_head_key(key);
+        let mut heap = {
+            match t.get(&head_key)? {
+                None => return Ok(None),
+                Some(v) => FieldHeap::new(v, &self.order)?,
+            }
+        };
+        let p = &mut heap as *mut _;
+        heap.init(C::from_heap(p));
+        let field = match heap.pop() {
+            None => return Ok(None),
+            Some(f) => f,
+        };
+        let field_key = make_field_key(key, &field);
+        let v = {
+            match t.get(&field_key)? {
+                None => vec![],
+                Some(v) => v,
+            }
+        };
+        t.put(&head_key, &heap.data)?;
+        t.delete(&field_key)?;
+        Ok(Some((field, v)))
+    }
+
+    fn push(&self, t: &T, key: &[u8], field: &[u8], value: &[u8]) -> Result<(), RrError> {
+        self.order.check_field(field)?;
+        let field_key = make_field_key(key, field);
+        if !t.exist(&field_key)? {
+            let head_key = make_head_key(key);
+            let mut heap = {
+                match t.get(&head_key)? {
+                    None => FieldHeap::new(vec![], &self.order)?,
+                    Some(v) => FieldHeap::new(v, &self.order)?,
+                }
+            };
+            let p = &mut heap as *mut _;
+            heap.init(C::from_heap(p));
+            heap.push(field);
+            t.put(&head_key, &heap.data)?;
+        }
+        t.put(&field_key, value)?;
+        Ok(())
+    }
+
+    fn push_bounded(&self, t: &T, key: &[u8], field: &[u8], value: &[u8], capacity: LenType) -> Result<Vec<(Vec<u8>, Vec<u8>)>, RrError> {
+        if capacity == 0 {
+            return Err(RrError::message("the capacity of heap is 0".to_owned()));
+        }
+        self.order.check_field(field)?;
+        let field_key = make_field_key(key, field);
+        if t.exist(&field_key)? {
+            t.put(&field_key, value)?;
+            return Ok(vec![]);
+        }
+        let head_key = make_head_key(key);
+        let mut heap = {
+            match t.get(&head_key)? {
+                None => FieldHeap::new(vec![], &self.order)?,
+                Some(v) => FieldHeap::new(v, &self.order)?,
+            }
+        };
+        let p = &mut heap as *mut _;
+        heap.init(C::from_heap(p));
+        heap.push(field);
+        let mut evicted = Vec::new();
+        while heap.len() > capacity as usize {
+            match heap.pop_worst() {
+                None => break,
+                Some(f) => evicted.push(f),
+            }
+        }
+        let mut items = field_values(t, key, evicted)?;
+        t.put(&head_key, &heap.data)?;
+        let mut stored = true;
+        for (f, v) in items.iter_mut() {
+            if f.as_slice() == field {
+                *v = value.to_vec();
+                stored = false;
+            } else {
+                t.delete(&make_field_key(key, f))?;
+            }
+        }
+        if stored {
+            t.put(&field_key, value)?;
+        }
+        Ok(items)
+    }
+
+    fn push_nx(&self, t: &T, key: &[u8], field: &[u8], value: &[u8]) -> Result<bool, RrError> {
+        if t.exist(&make_field_key(key, field))? {
+            return Ok(false);
+        }
+        self.push(t, key, field, value)?;
+        Ok(true)
+    }
+
+    fn remove(&self, t: &T, key: &[u8], field: &[u8]) -> Result<Option<Vec<u8>>, RrError> {
+        let field_key = make_field_key(key, field);
+        let v = match t.get(&field_key)? {
+            None => return Ok(None),
+            Some(v) => v,
+        };
+        let head_key = make_head_key(key);
+        let mut heap = {
+            match t.get(&head_key)? {
+                None => return Err(RrError::none_error("heap")),
+                Some(v) => FieldHeap::new(v, &self.order)?,
+            }
+        };
+        let p = &mut heap as *mut _;
+        heap.init(C::from_heap(p));
+        if !heap.remove(field) {
+            return Err(RrError::data_error("field of heap"));
+        }
+        t.put(&head_key, &heap.data)?;
+        t.delete(&field_key)?;
+        Ok(Some(v))
+    }
+
+    fn update_priority(&self, t: &T, key: &[u8], field: &[u8], new_field: &[u8]) -> Result<bool, RrError> {
+        self.order.check_field(new_field)?;
+        let field_key = make_field_key(key, field);
+        let v = match t.get(&field_key)? {
+            None => return Ok(false),
+            Some(v) => v,
+        };
+        if field == new_field {
+            return Ok(true);
+        }
+        let new_field_key = make_field_key(key, new_field);
+        if t.exist(&new_field_key)? {
+            return Err(RrError::message("the new field is already in the heap".to_owned()));
+        }
+        let head_key = make_head_key(key);
+        let mut heap = {
+            match t.get(&head_key)? {
+                None => return Err(RrError::none_error("heap")),
+                Some(v) => FieldHeap::new(v, &self.order)?,
+            }
+        };
+        let p = &mut heap as *mut _;
+        heap.init(C::from_heap(p));
+        if !heap.replace(field, new_field) {
+            return Err(RrError::data_error("field of heap"));
+        }
+        t.put(&head_key, &heap.data)?;
+        t.delete(&field_key)?;
+        t.put(&new_field_key, &v)?;
+        Ok(true)
+    }
+
+    fn peek_n(&self, t: &T, key: &[u8], n: LenType) -> Result<Vec<(Vec<u8>, Vec<u8>)>, RrError> {
+        let head_key = make_head_key(key);
+        let mut heap = {
+            match t.get(&head_key)? {
+                None => return Ok(vec![]),
+                Some(v) => FieldHeap::new(v, &self.order)?,
+            }
+        };
+        let p = &mut heap as *mut _;
+        heap.init(C::from_heap(p));
+        field_values(t, key, heap.peek_n(n as usize))
+    }
+
+    fn pop_n(&self, t: &T, key: &[u8], n: LenType) -> Result<Vec<(Vec<u8>, Vec<u8>)>, RrError> {
+        let head_key = make_head_key(key);
+        let mut heap = {
+            match t.get(&head_key)? {
+                None => return Ok(vec![]),
+                Some(v) => FieldHeap::new(v, &self.order)?,
+            }
+        };
+        let p = &mut heap as *mut _;
+        heap.init(C::from_heap(p));
+        let fields = heap.pop_n(n as usize);
+        if fields.is_empty() {
+            return Ok(vec![]);
+        }
+        let items = field_values(t, key, fields)?;
+        t.put(&head_key, &heap.data)?;
+        for (field, _) in &items {
+            t.delete(&make_field_key(key, field))?;
+        }
+        Ok(items)
+    }
+
+    fn iter(&self, t: &T, key: &[u8]) -> Result<HeapIter, RrError> {
+        Ok(HeapIter::new(self.peek_n(t, key, LenType::MAX)?))
+    }
+
+    fn len(&self, t: &T, key: &[u8]) -> Result<Option<LenType>, RrError> {
+        let head_key = make_head_key(key);
+        let heap = match t.get(&head_key)? {
+            None => return Ok(None),
+            Some(v) => FieldHeap::<C>::new(v, &self.order)?,
+        };
+        Ok(Some(heap.len() as LenType))
+    }
+
+    fn remove_key(&self, t: &T, key: &[u8]) -> Result<(), RrError> {
+        let head_key = make_head_key(key);
+        let mut heap = {
+            match t.get(&head_key)? {
+                None => return Ok(()),
+                Some(v) => FieldHeap::new(v, &self.order)?,
+            }
+        };
+        let p = &mut heap as *mut _;
+        heap.init(C::from_heap(p));
+        loop {
+            let field = match heap.pop() {
+                None => break,
+                Some(f) => f,
+            };
+            let field_key = make_field_key(key, &field);
+            t.delete(&field_key)?;
+        }
+        t.delete(&head_key)?;
+        Ok(())
+    }
+}
+
+/// 为$name实现[Heap]，所有的方法都委托给它的heap字段([FieldHeapImpl])
+macro_rules! delegate_heap {
+    ($name:ident) => {
+        impl<T: $crate::WrapDb> $crate::Heap<T> for $name {
+            fn peek(&self, t: &T, key: &[u8]) -> Result<Option<(Vec<u8>, Vec<u8>)>, $crate::RrError> {
+                $crate::Heap::<T>::peek(&self.heap, t, key)
+            }
+
+            fn pop(&self, t: &T, key: &[u8]) -> Result<Option<(Vec<u8>, Vec<u8>)>, $crate::RrError> {
+                $crate::Heap::<T>::pop(&self.heap, t, key)
+            }
+
+            fn push(&self, t: &T, key: &[u8], field: &[u8], value: &[u8]) -> Result<(), $crate::RrError> {
+                $crate::Heap::<T>::push(&self.heap, t, key, field, value)
+            }
+
+            fn push_bounded(
+                &self,
+                t: &T,
+                key: &[u8],
+                field: &[u8],
+                value: &[u8],
+                capacity: $crate::LenType,
+            ) -> Result<Vec<(Vec<u8>, Vec<u8>)>, $crate::RrError> {
+                $crate::Heap::<T>::push_bounded(&self.heap, t, key, field, value, capacity)
+            }
+
+            fn push_nx(&self, t: &T, key: &[u8], field: &[u8], value: &[u8]) -> Result<bool, $crate::RrError> {
+                $crate::Heap::<T>::push_nx(&self.heap, t, key, field, value)
+            }
+
+            fn remove(&self, t: &T, key: &[u8], field: &[u8]) -> Result<Option<Vec<u8>>, $crate::RrError> {
+                $crate::Heap::<T>::remove(&self.heap, t, key, field)
+            }
+
+            fn update_priority(&self, t: &T, key: &[u8], field: &[u8], new_field: &[u8]) -> Result<bool, $crate::RrError> {
+                $crate::Heap::<T>::update_priority(&self.heap, t, key, field, new_field)
+            }
+
+            fn peek_n(&self, t: &T, key: &[u8], n: $crate::LenType) -> Result<Vec<(Vec<u8>, Vec<u8>)>, $crate::RrError> {
+                $crate::Heap::<T>::peek_n(&self.heap, t, key, n)
+            }
+
+            fn pop_n(&self, t: &T, key: &[u8], n: $crate::LenType) -> Result<Vec<(Vec<u8>, Vec<u8>)>, $crate::RrError> {
+                $crate::Heap::<T>::pop_n(&self.heap, t, key, n)
+            }
+
+            fn iter(&self, t: &T, key: &[u8]) -> Result<$crate::HeapIter, $crate::RrError> {
+                $crate::Heap::<T>::iter(&self.heap, t, key)
+            }
+
+            fn len(&self, t: &T, key: &[u8]) -> Result<Option<$crate::LenType>, $crate::RrError> {
+                $crate::Heap::<T>::len(&self.heap, t, key)
+            }
+
+            fn remove_key(&self, t: &T, key: &[u8]) -> Result<(), $crate::RrError> {
+                $crate::Heap::<T>::remove_key(&self.heap, t, key)
+            }
+        }
+    };
+}
+
+pub(crate) use delegate_heap;
@@ -2,7 +2,13 @@ use std::{cmp::Ordering, mem, mem::ManuallyDrop, ptr, slice};
 
 use compare::Compare;
 
-use crate::{read_int, read_int_ptr, write_int, write_int_ptr, LenType};
+use crate::{read_int, read_int_ptr, rocksdb_impl::shared::make_field_key, write_int, write_int_ptr, HeapOrder, LenType, RrError, WrapDb};
+
+/// 比较heap中的字段，决定是最大堆还是最小堆
+pub(crate) trait HeapCompare: Compare<FieldMeta> + Clone + Sized {
+    /// heap调用[FieldHeap::init]之后，不能再移动
+    fn from_heap(heap: *mut FieldHeap<Self>) -> Self;
+}
 
 #[derive(Clone)]
 pub(crate) struct MaxHeapCompare {
@@ -11,15 +17,14 @@ pub(crate) struct MaxHeapCompare {
 
 impl Compare<FieldMeta> for MaxHeapCompare {
     fn compare(&self, l: &FieldMeta, r: &FieldMeta) -> Ordering {
-        unsafe {
-            let field_heap = &(*self.heap);
-            let p = field_heap.data.as_ptr().offset(field_heap.bst_capt + FieldHeap::<MaxHeapCompare>::BST_OFFSET);
-            let l_len = read_int_ptr::<SizeField>(p.offset(l.offset)) as usize;
-            let l_v = slice::from_raw_parts(p.offset(l_len as isize + l.offset), l_len);
-            let r_len = read_int_ptr::<SizeField>(p.offset(r.offset)) as usize;
-            let r_v = slice::from_raw_parts(p.offset(r_len as isize + r.offset), r_len);
-            l_v.cmp(r_v)
-        }
+        let field_heap = unsafe { &(*self.heap) };
+        field_heap.order.compare(field_heap.field(l), field_heap.field(r))
+    }
+}
+
+impl HeapCompare for MaxHeapCompare {
+    fn from_heap(heap: *mut FieldHeap<Self>) -> Self {
+        MaxHeapCompare { heap }
     }
 }
 
@@ -30,20 +35,20 @@ pub(crate) struct MinHeapCompare {
 
 impl Compare<FieldMeta> for MinHeapCompare {
     fn compare(&self, l: &FieldMeta, r: &FieldMeta) -> Ordering {
-        unsafe {
-            let field_heap = &(*self.heap);
-            let p = field_heap.data.as_ptr().offset(field_heap.bst_capt + FieldHeap::<MaxHeapCompare>::BST_OFFSET);
-            let l_len = read_int_ptr::<SizeField>(p.offset(l.offset)) as usize;
-            let l_v = slice::from_raw_parts(p.offset(l_len as isize + l.offset), l_len);
-            let r_len = read_int_ptr::<SizeField>(p.offset(r.offset)) as usize;
-            let r_v = slice::from_raw_parts(p.offset(r_len as isize + r.offset), r_len);
-            //由于是最小堆，所以反过比较
-            r_v.cmp(l_v)
-        }
+        let field_heap = unsafe { &(*self.heap) };
+        //由于是最小堆，所以反过比较
+        field_heap.order.compare(field_heap.field(r), field_heap.field(l))
+    }
+}
+
+impl HeapCompare for MinHeapCompare {
+    fn from_heap(heap: *mut FieldHeap<Self>) -> Self {
+        MinHeapCompare { heap }
     }
 }
 
 ///所有的field连续存入一遍连续的内存区中
+/// header: 字段个数(LenType)，bst的空间大小(LenType，最高位为1表示有order)，[HeapOrder::header](LenType)
 /// [C++ Binary Search Tree array implementation](https://www.daniweb.com/programming/software-development/threads/466340/c-binary-search-tree-array-implementation)
 /// [ArrayBinarySearchTree.java](http://faculty.washington.edu/moishe/javademos/jss2/ArrayBinarySearchTree.java)
 /// [binary-search-tree(not array)](https://www.geeksforgeeks.org/binary-search-tree-set-1-search-and-insertion/?ref=lbp)
@@ -52,7 +57,10 @@ pub(crate) struct FieldHeap<T: Compare<FieldMeta> + Clone> {
     pub data: Vec<u8>,
     /// 为bst分配置的空间大小， 默认为256，增加方式 每次增加256个
     bst_capt: isize,
+    order: HeapOrder,
     comparer: Option<T>,
+    /// 旧版本的heap是按错误的offset比较建立的，并不一定满足堆的顺序，在[FieldHeap::init]中重建
+    rebuild: bool,
 }
 
 //存放字段名的数据大小
@@ -64,29 +72,69 @@ pub(crate) struct FieldMeta {
 
 impl<T: Compare<FieldMeta> + Clone> FieldHeap<T> {
     pub const SIZE: usize = mem::size_of::<SizeField>();
-    pub const BST_OFFSET: isize = 2 * (mem::size_of::<LenType>() as isize);
+    pub const BST_OFFSET: isize = 3 * (mem::size_of::<LenType>() as isize);
     pub const BST_EXPAND: isize = 64 * (mem::size_of::<FieldMeta>() as isize);
+    const OFFSET_CAPT: usize = mem::size_of::<LenType>();
+    const OFFSET_ORDER: usize = 2 * mem::size_of::<LenType>();
+    /// 旧版本的header中没有order，bst_capt的最高位为0
+    const HAS_ORDER: LenType = 1 << (LenType::BITS - 1);
 
-    pub fn new(data: Vec<u8>) -> Self {
+    /// data为空时新建heap；已有的heap与order不一致时返回错误，旧版本的heap按[HeapOrder::Bytes]比较
+    pub fn new(data: Vec<u8>, order: &HeapOrder) -> Result<Self, RrError> {
         let mut data = data;
-        let mut bst_capt = Self::BST_EXPAND;
-        if data.is_empty() {
-            data.resize(Self::BST_OFFSET as usize + bst_capt as usize, 0);
-            unsafe { write_int_ptr(data.as_mut_ptr().offset(mem::size_of::<LenType>() as isize), bst_capt as LenType) }
+        let mut rebuild = false;
+        let bst_capt = if data.is_empty() {
+            data.resize(Self::BST_OFFSET as usize + Self::BST_EXPAND as usize, 0);
+            write_int(&mut data[Self::OFFSET_ORDER..], order.header());
+            Self::BST_EXPAND
         } else {
-            unsafe {
-                bst_capt = read_int_ptr::<LenType>(data.as_ptr().offset(mem::size_of::<LenType>() as isize)) as isize;
+            if data.len() < Self::OFFSET_ORDER {
+                return Err(RrError::data_error("heap"));
             }
+            let capt = read_int::<LenType>(&data[Self::OFFSET_CAPT..]);
+            rebuild = capt & Self::HAS_ORDER == 0;
+            if rebuild {
+                data.splice(Self::OFFSET_ORDER..Self::OFFSET_ORDER, HeapOrder::Bytes.header().to_le_bytes());
+            }
+            if data.len() < Self::BST_OFFSET as usize {
+                return Err(RrError::data_error("heap"));
+            }
+            if read_int::<LenType>(&data[Self::OFFSET_ORDER..]) != order.header() {
+                return Err(RrError::message("the order of heap is not eq".to_owned()));
+            }
+            (capt & !Self::HAS_ORDER) as isize
         };
-        FieldHeap {
+        let mut heap = FieldHeap {
             data,
             bst_capt,
+            order: order.clone(),
             comparer: None,
-        }
+            rebuild,
+        };
+        heap.set_bst_capt(bst_capt);
+        Ok(heap)
+    }
+
+    fn set_bst_capt(&mut self, bst_capt: isize) {
+        self.bst_capt = bst_capt;
+        write_int(&mut self.data[Self::OFFSET_CAPT..], bst_capt as LenType | Self::HAS_ORDER);
+    }
+
+    /// 字段的值，meta.offset是相对于字段区的
+    fn field(&self, meta: &FieldMeta) -> &[u8] {
+        let start = (self.field_offset() + meta.offset) as usize;
+        let field_size = read_int::<SizeField>(&self.data[start..]) as usize;
+        &self.data[start + Self::SIZE..start + Self::SIZE + field_size]
     }
 
     pub fn init(&mut self, comparer: T) {
         self.comparer = Some(comparer);
+        if self.rebuild {
+            self.rebuild = false;
+            let heap = self.make_heap();
+            let heap = binary_heap_plus::BinaryHeap::from_vec_cmp(heap.into_vec(), self.comparer.as_ref().expect("").clone());
+            self.drop_heap(heap);
+        }
     }
 
     fn make_heap(&mut self) -> binary_heap_plus::BinaryHeap<FieldMeta, T> {
@@ -112,16 +160,7 @@ impl<T: Compare<FieldMeta> + Clone> FieldHeap<T> {
     }
     pub fn peek(&mut self) -> Option<Vec<u8>> {
         let heap = self.make_heap();
-        let v = heap.peek();
-        let pop_v = if let Some(v) = v {
-            let start = v.offset + Self::BST_OFFSET + self.bst_capt;
-            let field_size = unsafe { read_int_ptr::<SizeField>(self.data.as_ptr().offset(start)) };
-            let end = start + Self::SIZE as isize + field_size as isize;
-            let re = self.data[start as usize + Self::SIZE as usize..end as usize].to_vec();
-            Some(re)
-        } else {
-            None
-        };
+        let pop_v = heap.peek().map(|v| self.field(v).to_vec());
         self.drop_heap(heap);
         pop_v
     }
@@ -133,13 +172,10 @@ impl<T: Compare<FieldMeta> + Clone> FieldHeap<T> {
         if let Some(v) = v {
             let len_field = self.len() - 1;
             self.set_len(len_field);
-            let start = v.offset + Self::BST_OFFSET + self.bst_capt;
-            let field_size = unsafe { read_int_ptr::<SizeField>(self.data.as_ptr().offset(start)) };
-            let end = start + Self::SIZE as isize + field_size as isize;
-            let re = self.data[start as usize + Self::SIZE as usize..end as usize].to_vec();
+            let re = self.field(&v).to_vec();
             //如果删除的数据，等于或超过一次扩展的数据，那么进行清理，把没有使用的空间删除（压缩数据）
             if self.bst_capt as usize - len_field * mem::size_of::<FieldMeta>() > Self::BST_EXPAND as usize {
-                self.reduce();
+                self.compact(self.bst_capt - Self::BST_EXPAND);
             }
             Some(re)
         } else {
@@ -148,13 +184,156 @@ impl<T: Compare<FieldMeta> + Clone> FieldHeap<T> {
     }
     /// 由于head结构查找很慢，所以不能插入相同的key
     pub fn push(&mut self, field: &[u8]) {
-        //把字段加入最后
         //检查是否有heap的空间是否够大
         let len = self.len();
         if len * mem::size_of::<FieldMeta>() >= self.bst_capt as usize {
             self.expand();
         }
 
+        let offset = self.append_field(field);
+        let mut heap = self.make_heap();
+        heap.push(FieldMeta { offset });
+        self.drop_heap(heap);
+        let len = self.len() + 1;
+        //写入总的字段个数
+        write_int_ptr(self.data.as_mut_ptr(), len as LenType);
+    }
+
+    /// 按顺序返回前n个字段，并不删除
+    pub fn peek_n(&self, n: usize) -> Vec<Vec<u8>> {
+        let comparer = self.comparer.as_ref().expect("").clone();
+        let len = self.len();
+        //从堆顶开始，候选的为已经取出的字段的子节点，每次取出候选中最大的
+        let mut candidates = binary_heap_plus::BinaryHeap::from_vec_cmp(Vec::new(), |l: &usize, r: &usize| comparer.compare(&self.meta(*l), &self.meta(*r)));
+        if len > 0 {
+            candidates.push(0);
+        }
+        let mut fields = Vec::with_capacity(n.min(len));
+        while fields.len() < n {
+            let i = match candidates.pop() {
+                None => break,
+                Some(i) => i,
+            };
+            fields.push(self.field(&self.meta(i)).to_vec());
+            for child in [2 * i + 1, 2 * i + 2] {
+                if child < len {
+                    candidates.push(child);
+                }
+            }
+        }
+        fields
+    }
+
+    /// 按顺序取出前n个字段并删除
+    pub fn pop_n(&mut self, n: usize) -> Vec<Vec<u8>> {
+        (0..n).map_while(|_| self.pop()).collect()
+    }
+
+    /// 删除field，不存在时返回false
+    pub fn remove(&mut self, field: &[u8]) -> bool {
+        match self.position(field) {
+            None => false,
+            Some(i) => {
+                self.remove_at(i);
+                true
+            }
+        }
+    }
+
+    /// 取出并删除最后一个字段(最大堆中的最小值，最小堆中的最大值)
+    pub fn pop_worst(&mut self) -> Option<Vec<u8>> {
+        let len = self.len();
+        if len == 0 {
+            return None;
+        }
+        let comparer = self.comparer.as_ref().expect("").clone();
+        //最后的字段一定是叶子节点，叶子节点都在后一半
+        let index = (len / 2..len).min_by(|l, r| comparer.compare(&self.meta(*l), &self.meta(*r))).expect("");
+        let field = self.field(&self.meta(index)).to_vec();
+        self.remove_at(index);
+        Some(field)
+    }
+
+    fn remove_at(&mut self, index: usize) {
+        let last = self.len() - 1;
+        if index != last {
+            let meta = self.meta(last);
+            self.set_meta(index, meta);
+        }
+        self.set_len(last);
+        if index != last {
+            self.sift(index);
+        }
+        self.compact_if_sparse();
+    }
+
+    /// 把field改为new_field，并移动到新的位置，field不存在时返回false
+    pub fn replace(&mut self, field: &[u8], new_field: &[u8]) -> bool {
+        let index = match self.position(field) {
+            None => return false,
+            Some(i) => i,
+        };
+        let offset = self.append_field(new_field);
+        self.set_meta(index, FieldMeta { offset });
+        self.sift(index);
+        self.compact_if_sparse();
+        true
+    }
+
+    /// 顺序查找field在bst中的下标
+    fn position(&self, field: &[u8]) -> Option<usize> {
+        (0..self.len()).find(|i| self.field(&self.meta(*i)) == field)
+    }
+
+    /// data只保证1字节对齐，所以使用read_unaligned
+    fn meta(&self, index: usize) -> FieldMeta {
+        unsafe { ptr::read_unaligned((self.data.as_ptr().offset(Self::BST_OFFSET) as *const FieldMeta).add(index)) }
+    }
+
+    fn set_meta(&mut self, index: usize, meta: FieldMeta) {
+        unsafe { ptr::write_unaligned((self.data.as_mut_ptr().offset(Self::BST_OFFSET) as *mut FieldMeta).add(index), meta) }
+    }
+
+    /// index的值改变后，向上或向下移动到合适的位置
+    fn sift(&mut self, index: usize) {
+        let comparer = self.comparer.as_ref().expect("").clone();
+        let len = self.len();
+        let mut i = index;
+        while i > 0 {
+            let parent = (i - 1) / 2;
+            if comparer.compare(&self.meta(i), &self.meta(parent)) != Ordering::Greater {
+                break;
+            }
+            self.swap_meta(i, parent);
+            i = parent;
+        }
+        if i != index {
+            return;
+        }
+        loop {
+            let mut child = 2 * i + 1;
+            if child >= len {
+                break;
+            }
+            if child + 1 < len && comparer.compare(&self.meta(child + 1), &self.meta(child)) == Ordering::Greater {
+                child += 1;
+            }
+            if comparer.compare(&self.meta(child), &self.meta(i)) != Ordering::Greater {
+                break;
+            }
+            self.swap_meta(i, child);
+            i = child;
+        }
+    }
+
+    fn swap_meta(&mut self, a: usize, b: usize) {
+        let (meta_a, meta_b) = (self.meta(a), self.meta(b));
+        self.set_meta(a, meta_b);
+        self.set_meta(b, meta_a);
+    }
+
+    /// 把字段加入最后，返回相对于字段区的偏移
+    fn append_field(&mut self, field: &[u8]) -> isize {
         let add = Self::SIZE + field.len();
         self.data.reserve(add);
         let len_data = self.data.len();
@@ -166,15 +345,15 @@ impl<T: Compare<FieldMeta> + Clone> FieldHeap<T> {
             ptr::copy_nonoverlapping(field.as_ptr(), p.offset(Self::SIZE as isize), field.len());
             self.data.set_len(self.data.len() + add)
         }
+        len_data as isize - self.field_offset()
+    }
 
-        let mut heap = self.make_heap();
-        heap.push(FieldMeta {
-            offset: len_data as isize - Self::BST_OFFSET - self.bst_capt,
-        });
-        self.drop_heap(heap);
-        let len = self.len() + 1;
-        //写入总的字段个数
-        write_int_ptr(self.data.as_mut_ptr(), len as LenType);
+    /// remove与replace后旧的字段仍然占用空间，没有使用的空间超过一半时清理
+    fn compact_if_sparse(&mut self) {
+        let used: usize = (0..self.len()).map(|i| Self::SIZE + self.field(&self.meta(i)).len()).sum();
+        if (self.data.len() - self.field_offset() as usize) > used * 2 {
+            self.compact(self.bst_capt);
+        }
     }
 
     pub fn len(&self) -> usize {
@@ -202,16 +381,12 @@ impl<T: Compare<FieldMeta> + Clone> FieldHeap<T> {
                 p_data.offset(expand_size as isize),
                 self.data.len() - expand_size as usize - Self::BST_OFFSET as usize - old_capt as usize,
             );
-            write_int_ptr(
-                self.data.as_mut_ptr().offset(mem::size_of::<LenType>() as isize),
-                old_capt as LenType + expand_size as LenType,
-            );
         };
-        self.bst_capt = old_capt + expand_size;
+        self.set_bst_capt(old_capt + expand_size);
     }
 
-    fn reduce(&mut self) {
-        let reduce_size = Self::BST_EXPAND;
+    /// 把使用中的字段连续存放，并把bst的空间大小改为bst_capt
+    fn compact(&mut self, bst_capt: isize) {
         let mut temp_fields = Vec::<u8>::with_capacity(self.data.len() - self.bst_capt as usize - Self::BST_OFFSET as usize);
 
         let mut head_array = unsafe {
@@ -236,10 +411,9 @@ impl<T: Compare<FieldMeta> + Clone> FieldHeap<T> {
             }
         }
         let _ = ManuallyDrop::new(head_array);
-        self.bst_capt -= reduce_size;
+        self.set_bst_capt(bst_capt);
         unsafe {
             temp_fields.set_len(offset as usize);
-            write_int_ptr(self.data.as_mut_ptr().offset(mem::size_of::<LenType>() as isize), self.bst_capt as LenType);
             ptr::copy_nonoverlapping(
                 temp_fields.as_ptr(),
                 self.data.as_mut_ptr().offset(Self::BST_OFFSET + self.bst_capt),
@@ -250,6 +424,13 @@ impl<T: Compare<FieldMeta> + Clone> FieldHeap<T> {
     }
 }
 
+/// 使用一次multi get读取fields的value，不存在的value为空
+pub(crate) fn field_values<T: WrapDb>(t: &T, key: &[u8], fields: Vec<Vec<u8>>) -> Result<Vec<(Vec<u8>, Vec<u8>)>, RrError> {
+    let keys: Vec<Vec<u8>> = fields.iter().map(|f| make_field_key(key, f)).collect();
+    let values = t.multi_get(&keys)?;
+    Ok(fields.into_iter().zip(values).map(|(f, v)| (f, v.unwrap_or_default())).collect())
+}
+
 pub(crate) struct FieldIt<'a, T: Compare<FieldMeta> + Clone> {
     data: &'a FieldHeap<T>,
     len: isize,
@@ -300,7 +481,78 @@ impl<'a, T: Compare<FieldMeta> + Clone> Iterator for FieldIt<'a, T> {
 mod test {
     use std::mem;
 
-    use crate::write_int;
+    use crate::{
+        rocksdb_impl::heap::heap::{FieldHeap, MaxHeapCompare, MinHeapCompare, SizeField},
+        write_int, HeapOrder, LenType,
+    };
+
+    /// 旧版本的heap，metas按fields的顺序保存，并不一定是有效的堆
+    fn legacy_heap(fields: &[&[u8]]) -> Vec<u8> {
+        let capt = FieldHeap::<MaxHeapCompare>::BST_EXPAND as usize;
+        let mut legacy = vec![0u8; 2 * mem::size_of::<LenType>() + capt];
+        write_int(&mut legacy[..], fields.len() as LenType);
+        write_int(&mut legacy[mem::size_of::<LenType>()..], capt as LenType);
+        let mut offset = 0isize;
+        for (i, field) in fields.iter().enumerate() {
+            let start = 2 * mem::size_of::<LenType>() + i * mem::size_of::<isize>();
+            legacy[start..start + mem::size_of::<isize>()].copy_from_slice(&offset.to_ne_bytes());
+            legacy.extend_from_slice(&(field.len() as SizeField).to_le_bytes());
+            legacy.extend_from_slice(field);
+            offset += (mem::size_of::<SizeField>() + field.len()) as isize;
+        }
+        legacy
+    }
+
+    #[test]
+    fn test_field_heap_legacy_header() {
+        // 旧版本的header只有字段个数与bst的空间大小
+        let capt = FieldHeap::<MaxHeapCompare>::BST_EXPAND as usize;
+        let mut legacy = vec![0u8; 2 * mem::size_of::<LenType>() + capt];
+        write_int(&mut legacy[mem::size_of::<LenType>()..], capt as LenType);
+        assert!(FieldHeap::<MaxHeapCompare>::new(legacy.clone(), &HeapOrder::Int).is_err());
+
+        let mut heap = FieldHeap::<MaxHeapCompare>::new(legacy, &HeapOrder::Bytes).expect("");
+        let p = &mut heap as *mut _;
+        heap.init(MaxHeapCompare { heap: p });
+        heap.push(b"b");
+        heap.push(b"abc");
+        let data = heap.data.clone();
+        assert!(FieldHeap::<MaxHeapCompare>::new(data.clone(), &HeapOrder::UInt).is_err());
+        let mut heap = FieldHeap::<MaxHeapCompare>::new(data, &HeapOrder::Bytes).expect("");
+        let p = &mut heap as *mut _;
+        heap.init(MaxHeapCompare { heap: p });
+        assert_eq!(2, heap.len());
+        assert_eq!(Some(b"b".to_vec()), heap.pop());
+        assert_eq!(Some(b"abc".to_vec()), heap.pop());
+        assert_eq!(None, heap.pop());
+
+        // 根不是最大值，打开时需要重建
+        let mut heap = FieldHeap::<MaxHeapCompare>::new(legacy_heap(&[b"a", b"ccc", b"bb", b"d"]), &HeapOrder::Bytes).expect("");
+        let p = &mut heap as *mut _;
+        heap.init(MaxHeapCompare { heap: p });
+        assert_eq!(Some(b"d".to_vec()), heap.peek());
+        heap.push(b"b");
+        for v in [&b"d"[..], b"ccc", b"bb", b"b", b"a"] {
+            assert_eq!(Some(v.to_vec()), heap.pop());
+        }
+        assert_eq!(None, heap.pop());
+
+        // 根不是最小值
+        let mut heap = FieldHeap::<MinHeapCompare>::new(legacy_heap(&[b"ccc", b"d", b"a", b"bb"]), &HeapOrder::Bytes).expect("");
+        let p = &mut heap as *mut _;
+        heap.init(MinHeapCompare { heap: p });
+        let data = heap.data.clone();
+        for v in [&b"a"[..], b"bb", b"ccc", b"d"] {
+            assert_eq!(Some(v.to_vec()), heap.pop());
+        }
+        assert_eq!(None, heap.pop());
+
+        // 重建后的header有order，再次打开时不用重建
+        let mut heap = FieldHeap::<MinHeapCompare>::new(data, &HeapOrder::Bytes).expect("");
+        let p = &mut heap as *mut _;
+        heap.init(MinHeapCompare { heap: p });
+        assert_eq!(Some(b"a".to_vec()), heap.peek());
+    }
 
     #[test]
     fn test_binary_heap() {
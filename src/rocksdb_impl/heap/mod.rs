@@ -1,6 +1,8 @@
+pub(crate) use field_heap_impl::FieldHeapImpl;
 pub use max_heap::*;
 pub use min_heap::*;
 
+mod field_heap_impl;
 mod heap;
 mod max_heap;
 mod min_heap;
@@ -1,161 +1,221 @@
 use anyhow::Context;
+use rocksdb::TransactionDB;
 
 use crate::{
-    rocksdb_impl::{quick_list::QuickList, quick_list_node::QuickListNode, zip_list::ZipList},
-    Bytes, LenType, RedisList, RedisRocksdb, RrError,
+    rocksdb_impl::{quick_list::QuickList, quick_list_index::QuickListIndex, quick_list_node::QuickListNode, zip_list::ZipList},
+    Bytes, LenType, List, ListConfig, ListEnd, ListIter, MetaKey, RedisList, RedisRocksdb, RrError, WrapDb, WrapTransaction,
 };
 
 /// [see] (https://xindoo.blog.csdn.net/article/details/109150975)
 /// ssdb没有实现list，只实现了queue
 ///
 /// redis中的list使用quicklist与ziplist实现
-impl RedisList for RedisRocksdb {
-    fn list_blpop<K: Bytes, V: Bytes>(&mut self, _key: &K, _timeout: i64) -> Result<V, RrError> {
-        todo!()
-    }
-
-    fn list_brpop<K: Bytes, V: Bytes>(&mut self, _key: &K, _timeout: i64) -> Result<V, RrError> {
-        todo!()
-    }
+#[derive(Default)]
+pub struct ListImpl {
+    /// 新建list时使用的配置
+    pub(crate) config: ListConfig,
+}
 
-    fn list_brpoplpush<K: Bytes, V: Bytes>(&mut self, _srckey: &K, _dstkey: &K, _timeout: i64) -> Result<V, RrError> {
-        todo!()
+impl ListImpl {
+    fn get_or_new<T: WrapDb>(&self, t: &T, key: &[u8]) -> Result<QuickList, RrError> {
+        match QuickList::get(t, key)? {
+            None => {
+                let mut q = QuickList::new();
+                q.init_meta_key(t)?;
+                q.set_config(&self.config);
+                Ok(q)
+            }
+            Some(q) => Ok(q),
+        }
     }
 
-    fn list_index<K: Bytes>(&self, key: &K, index: i32) -> Result<Vec<u8>, RrError> {
-        let t = QuickList::get(&self.db, key.as_ref())?.ok_or(RrError::not_find("key of list"))?;
-        if index >= t.len_list() as i32 {
+    /// 把负数的index转换为从头开始的下标，不在list范围内时返回错误
+    fn real_index(quick: &QuickList, index: i32) -> Result<i32, RrError> {
+        let len = quick.len_list() as i64;
+        let real = if index < 0 { len + index as i64 } else { index as i64 };
+        if real < 0 || real >= len {
             return Err(RrError::not_find(&format!("the index {}", index)));
         }
-        //todo read only
-        let tr = self.db.transaction();
-        let node_key = t.left().context("left of quick list")?;
-        let mut node = QuickListNode::get(&tr, node_key.as_ref())?.context("left node")?;
-        let mut it_index = 0i32;
-        it_index += node.len_list() as i32;
-        while index >= it_index {
-            let next_key = node.right().context("right node")?;
-            node = QuickListNode::get(&tr, next_key.as_ref())?.context("next node")?;
-            it_index += node.len_list() as i32;
-        }
-
-        let value_key = node.values_key().context("value key")?;
-        let zip = ZipList::get(&tr, value_key.as_ref())?.context("zip list")?;
-        let zip_index = index - (it_index - node.len_list() as i32);
-        let v = zip.index(zip_index).ok_or(RrError::not_find(&format!("the index {}", index)))?;
-        tr.commit()?;
-        Ok(v.to_vec())
-    }
-    fn list_insert_before<K: Bytes, V: Bytes>(&mut self, key: &K, pivot: &V, value: &V) -> Result<i32, RrError> {
-        let mut quick = {
-            match QuickList::get(&self.db, key.as_ref())? {
-                None => return Ok(0),
-                Some(q) => q,
-            }
-        };
-        let tr = self.db.transaction();
-        let result = quick.list_insert(&tr, key.as_ref(), pivot.as_ref(), value.as_ref(), ZipList::insert_value_left)?;
-        tr.commit()?;
-        Ok(result)
-    }
-
-    fn list_insert_after<K: Bytes, V: Bytes>(&mut self, key: &K, pivot: &V, value: &V) -> Result<i32, RrError> {
-        let mut quick = {
-            match QuickList::get(&self.db, key.as_ref())? {
-                None => return Ok(0),
-                Some(q) => q,
-            }
-        };
-
-        let tr = self.db.transaction();
-        let result = quick.list_insert(&tr, key.as_ref(), pivot.as_ref(), value.as_ref(), ZipList::insert_value_right)?;
-        tr.commit()?;
-        Ok(result)
+        Ok(real as i32)
     }
 
-    fn list_len<K: Bytes>(&self, key: &K) -> Result<i32, RrError> {
-        match QuickList::get(&self.db, key.as_ref())? {
-            None => Ok(-1),
-            Some(quick) => Ok(quick.len_list() as i32),
-        }
+    /// 通过索引找到index所在的node，返回node的key、node、index在zip中的下标与node的结束坐标，index必须在list的范围内
+    fn find_node<T: WrapDb>(t: &T, key: &[u8], quick: &QuickList, index: i32) -> Result<(MetaKey, QuickListNode, i32, i64), RrError> {
+        let coord = quick.head() + index as i64;
+        let (node_key, end) = QuickListIndex::find(t, key, coord)?;
+        let node = QuickListNode::get(t, node_key.as_ref())?.context("node of index")?;
+        let zip_index = coord - (end - node.len_list() as i64);
+        Ok((node_key, node, zip_index as i32, end))
     }
 
-    fn list_pop_front<K: Bytes>(&mut self, key: &K) -> Result<Option<Vec<u8>>, RrError> {
-        let tr = self.db.transaction();
-        let mut quick = match QuickList::get(&self.db, key.as_ref())? {
+    /// front为true时从左边弹出，否则从右边弹出
+    fn pop<T: WrapDb>(t: &T, key: &[u8], front: bool) -> Result<Option<Vec<u8>>, RrError> {
+        let mut quick = match QuickList::get(t, key)? {
             None => return Ok(None),
             Some(q) => q,
         };
         if quick.len_list() < 1 {
             return Ok(None);
         }
-        let node_key = quick.left().ok_or(RrError::none_error("left key"))?.clone();
-        let mut node = QuickListNode::get(&tr, node_key.as_ref())?.ok_or(RrError::none_error("left node"))?;
+        let node_key = if front { quick.left() } else { quick.right() }.ok_or(RrError::none_error("end key"))?.clone();
+        let mut node = QuickListNode::get(t, node_key.as_ref())?.ok_or(RrError::none_error("end node"))?;
         let zip_key = node.values_key().ok_or(RrError::none_error("zip key"))?.clone();
-        let mut zip = ZipList::get(&tr, zip_key.as_ref())?.ok_or(RrError::none_error("zip list"))?;
-        let value = zip.pop_left();
+        let mut zip = ZipList::get(t, zip_key.as_ref())?.ok_or(RrError::none_error("zip list"))?;
+        let value = if front { zip.pop_left() } else { zip.pop_right() };
+        let len_node = quick.len_node();
 
+        let tail = quick.tail();
         if zip.len() == 0 {
-            //没有数据，删除quick list node
-            if quick.len_node() == 1 {
-                tr.delete(zip_key)?;
-                tr.delete(node_key)?;
-                quick.set_right(&None);
-                quick.set_left(&None);
-                quick.set_len_list(0);
-                quick.set_len_node(0);
-                tr.put(key.as_ref(), quick)?;
-            } else {
-                let _left = node.right();
-                quick.set_right(&node.left());
-                quick.set_len_node(quick.len_node() - 1);
-                quick.set_len_list(quick.len_list() - 1);
-                tr.delete(zip_key)?;
-                tr.delete(node_key)?;
-                tr.put(key.as_ref(), quick)?;
+            QuickListIndex::delete(t, key, if front { quick.head() + 1 } else { tail })?;
+            //没有数据，删除quick list node，下一个node成为端点
+            t.delete(zip_key.as_ref())?;
+            t.delete(node_key.as_ref())?;
+            let next_key = if front { node.right() } else { node.left() };
+            match next_key {
+                None => {
+                    quick.set_right(&None);
+                    quick.set_left(&None);
+                }
+                Some(next_key) => {
+                    let mut next = QuickListNode::get(t, next_key.as_ref())?.ok_or(RrError::none_error("next node"))?;
+                    if front {
+                        next.set_left(&None);
+                        quick.set_left(&Some(next_key));
+                    } else {
+                        next.set_right(&None);
+                        quick.set_right(&Some(next_key));
+                    }
+                    t.put(next_key.as_ref(), next.as_ref())?;
+                }
             }
+            quick.set_len_node(quick.len_node() - 1);
         } else {
             node.set_len_list(zip.len());
             node.set_len_bytes(zip.as_ref().len() as LenType);
-            quick.set_len_list(quick.len_list() - 1);
-
-            tr.put(zip_key, zip)?;
-            tr.put(node_key, node.as_ref())?;
+            zip.put(t, zip_key.as_ref(), node.compressed())?;
+            t.put(node_key.as_ref(), node.as_ref())?;
+            if !front {
+                QuickListIndex::move_end(t, key, tail, tail - 1, &node_key)?;
+            }
+            let end = if front { quick.head() + 1 + node.len_list() as i64 } else { tail - 1 };
+            quick.merge_node(t, key, &node_key, Some(end))?;
+        }
+        if front {
+            quick.set_head(quick.head() + 1);
+        }
+        quick.set_len_list(quick.len_list() - 1);
+        t.put(key, quick.as_ref())?;
+        if quick.len_node() != len_node {
+            quick.decompress_ends(t)?;
         }
-
-        tr.commit()?;
         Ok(value)
     }
 
-    fn list_push_front<K: Bytes, V: Bytes>(&mut self, key: &K, value: &V) -> Result<i32, RrError> {
-        let tr = self.db.transaction();
-        let mut quick = match QuickList::get(&self.db, key.as_ref())? {
-            None => {
-                let mut q = QuickList::new();
-                q.init_meta_key(key.as_ref());
-                q
-            }
-            Some(q) => q,
+    fn move_value<T: WrapDb>(&self, t: &T, src: &[u8], dst: &[u8], from: ListEnd, to: ListEnd) -> Result<Option<Vec<u8>>, RrError> {
+        let v = match ListImpl::pop(t, src, from == ListEnd::Front)? {
+            None => return Ok(None),
+            Some(v) => v,
         };
-        let re = quick.lpush(&tr, key.as_ref(), value.as_ref())?;
-        tr.commit()?;
-        Ok(re)
+        let mut quick = self.get_or_new(t, dst)?;
+        match to {
+            ListEnd::Front => quick.lpush(t, dst, &v)?,
+            ListEnd::Back => quick.rpush(t, dst, &v)?,
+        };
+        Ok(Some(v))
     }
 
-    fn list_push_front_exists<K: Bytes, V: Bytes>(&mut self, key: &K, value: &V) -> Result<i32, RrError> {
-        let mut quick = match QuickList::get(&self.db, key.as_ref())? {
-            None => return Ok(0),
-            Some(q) => q,
-        };
-        let tr = self.db.transaction();
-        let re = quick.lpush(&tr, key.as_ref(), value.as_ref())?;
-        tr.commit()?;
-        Ok(re)
+    /// 从一端开始删除整个node，直到遇到包含第skip个元素(从这一端开始计数)的node
+    /// 返回这个node及其key，和需要在它的zip中删除的元素个数
+    fn trim_end<T: WrapDb>(t: &T, key: &[u8], quick: &mut QuickList, skip: LenType, front: bool) -> Result<(MetaKey, QuickListNode, LenType), RrError> {
+        let mut node_key = if front { quick.left() } else { quick.right() }.ok_or(RrError::none_error("end key"))?.clone();
+        let mut offset: LenType = 0;
+        loop {
+            let node = QuickListNode::get(t, node_key.as_ref())?.ok_or(RrError::none_error("node"))?;
+            if offset + node.len_list() > skip {
+                return Ok((node_key, node, skip - offset));
+            }
+            let end = if front {
+                quick.head() + (offset + node.len_list()) as i64
+            } else {
+                quick.tail() - offset as i64
+            };
+            QuickListIndex::delete(t, key, end)?;
+            offset += node.len_list();
+            t.delete(node.values_key().ok_or(RrError::none_error("zip key"))?.as_ref())?;
+            t.delete(node_key.as_ref())?;
+            quick.set_len_node(quick.len_node() - 1);
+            node_key = if front { node.right() } else { node.left() }.ok_or(RrError::none_error("next node"))?.clone();
+        }
     }
 
-    fn list_range<K: Bytes>(&self, key: &K, start: i32, stop: i32) -> Result<Vec<Vec<u8>>, RrError> {
+    /// 只保留zip中下标在[start, stop]之间的值，并保存zip
+    fn trim_zip<T: WrapDb>(t: &T, node: &mut QuickListNode, start: LenType, stop: LenType) -> Result<(), RrError> {
+        let zip_key = node.values_key().ok_or(RrError::none_error("zip key"))?.clone();
+        let mut zip = ZipList::get(t, zip_key.as_ref())?.ok_or(RrError::none_error("zip"))?;
+        zip.trim(start as usize, stop as usize);
+        node.set_len_list(zip.len());
+        node.set_len_bytes(zip.as_ref().len() as LenType);
+        zip.put(t, zip_key.as_ref(), node.compressed())
+    }
+}
+
+impl<T: WrapDb> List<T> for ListImpl {
+    fn index(&self, t: &T, key: &[u8], index: i32) -> Result<Vec<u8>, RrError> {
+        let quick = QuickList::get(t, key)?.ok_or(RrError::not_find("key of list"))?;
+        let index = ListImpl::real_index(&quick, index)?;
+        let (_, node, zip_index, _) = ListImpl::find_node(t, key, &quick, index)?;
+        let value_key = node.values_key().context("value key")?;
+        let zip = ZipList::get(t, value_key.as_ref())?.context("zip list")?;
+        let v = zip.index(zip_index).ok_or(RrError::not_find(&format!("the index {}", index)))?;
+        Ok(v.to_vec())
+    }
+
+    fn insert_before(&self, t: &T, key: &[u8], pivot: &[u8], value: &[u8]) -> Result<i32, RrError> {
+        match QuickList::get(t, key)? {
+            None => Ok(0),
+            Some(mut quick) => quick.list_insert(t, key, pivot, value, ZipList::insert_value_left),
+        }
+    }
+
+    fn insert_after(&self, t: &T, key: &[u8], pivot: &[u8], value: &[u8]) -> Result<i32, RrError> {
+        match QuickList::get(t, key)? {
+            None => Ok(0),
+            Some(mut quick) => quick.list_insert(t, key, pivot, value, ZipList::insert_value_right),
+        }
+    }
+
+    fn len(&self, t: &T, key: &[u8]) -> Result<i32, RrError> {
+        match QuickList::get(t, key)? {
+            None => Ok(-1),
+            Some(quick) => Ok(quick.len_list() as i32),
+        }
+    }
+
+    fn pop_front(&self, t: &T, key: &[u8]) -> Result<Option<Vec<u8>>, RrError> {
+        ListImpl::pop(t, key, true)
+    }
+
+    fn push_front(&self, t: &T, key: &[u8], value: &[u8]) -> Result<i32, RrError> {
+        self.get_or_new(t, key)?.lpush(t, key, value)
+    }
+
+    fn push_front_many(&self, t: &T, key: &[u8], values: &[&[u8]]) -> Result<i32, RrError> {
+        if values.is_empty() {
+            return self.len(t, key).map(|len| len.max(0));
+        }
+        self.get_or_new(t, key)?.push_many(t, key, values, true)
+    }
+
+    fn push_front_exists(&self, t: &T, key: &[u8], value: &[u8]) -> Result<i32, RrError> {
+        match QuickList::get(t, key)? {
+            None => Ok(0),
+            Some(mut quick) => quick.lpush(t, key, value),
+        }
+    }
+
+    fn range(&self, t: &T, key: &[u8], start: i32, stop: i32) -> Result<Vec<Vec<u8>>, RrError> {
         let mut result = Vec::new();
-        let quick = match QuickList::get(&self.db, key.as_ref())? {
+        let quick = match QuickList::get(t, key)? {
             None => return Ok(result),
             Some(q) => q,
         };
@@ -169,19 +229,17 @@ impl RedisList for RedisRocksdb {
             return Ok(result);
         }
 
-        //todo read only
-        let tr = self.db.transaction();
-
-        let node_key = quick.left().ok_or(RrError::none_error("left key"))?;
-        let mut node = QuickListNode::get(&tr, node_key.as_ref())?.ok_or(RrError::none_error("quick list node"))?;
-        let offset = 0usize;
+        let (node_key, end) = QuickListIndex::find(t, key, quick.head() + start_index as i64)?;
+        let mut node = QuickListNode::get(t, node_key.as_ref())?.ok_or(RrError::none_error("quick list node"))?;
+        // 第一个node之前的元素个数
+        let mut offset = (end - node.len_list() as i64 - quick.head()) as usize;
         loop {
             let len_zip = node.len_list();
             if start_index < len_zip as usize + offset {
                 let temp = ZipList::count_in_index(len_zip, offset, start_index, stop_index);
                 if let Some((start_in, stop_in)) = temp {
                     let zip_key = node.values_key().ok_or(RrError::none_error("zip key"))?;
-                    let zip = ZipList::get(&tr, zip_key.as_ref())?.ok_or(RrError::none_error("zip"))?;
+                    let zip = ZipList::get(t, zip_key.as_ref())?.ok_or(RrError::none_error("zip"))?;
                     let one = zip.range(start_in as i32, stop_in as i32);
                     result.extend(one);
                 } //else 是没有数据
@@ -191,234 +249,393 @@ impl RedisList for RedisRocksdb {
                 //取了所有数据
                 break;
             }
+            offset += len_zip as usize;
 
-            if let Some(t) = node.right() {
-                node = QuickListNode::get(&tr, t.as_ref())?.ok_or(RrError::none_error("quick list node"))?;
+            if let Some(next) = node.right() {
+                node = QuickListNode::get(t, next.as_ref())?.ok_or(RrError::none_error("quick list node"))?;
             } else {
                 // 没有更多的节点
                 break;
             }
         }
-
-        tr.commit()?;
         Ok(result)
     }
 
-    fn list_rem<K: Bytes, V: Bytes>(&mut self, list_key: &K, count: i32, value: &V) -> Result<LenType, RrError> {
-        let mut quick = match QuickList::get(&self.db, list_key.as_ref())? {
+    fn rem(&self, t: &T, list_key: &[u8], count: i32, value: &[u8]) -> Result<LenType, RrError> {
+        let mut quick = match QuickList::get(t, list_key)? {
             None => return Ok(0),
             Some(q) => q,
         };
+        if quick.len_node() == 0 {
+            return Ok(0);
+        }
 
-        let mut rem_count = 0u64;
-
-        let tr = self.db.transaction();
-
-        if count > 0 {
-            //正向遍历
-            let count = count as usize;
-            let mut node_key = quick.left().ok_or(RrError::none_error("left key"))?.clone();
-            let mut node = QuickListNode::get(&tr, node_key.as_ref())?.ok_or(RrError::none_error("left node"))?;
-
-            loop {
-                let zip_key = node.values_key().ok_or(RrError::none_error("zip key"))?.clone();
-                let mut zip = ZipList::get(&tr, zip_key.as_ref())?.ok_or(RrError::none_error("zip"))?;
-
-                let done = zip.rem((count - rem_count as usize) as i32, value.as_ref());
-                rem_count += done;
-
-                if done != 0 {
-                    quick.modify_node(&tr, list_key.as_ref(), zip_key.as_ref(), &mut zip, node_key.as_ref(), &mut node)?;
-                }
-
-                if rem_count == count as u64 {
-                    break;
-                }
-                if let Some(t) = node.right() {
-                    node_key = t.clone();
-                } else {
-                    break;
+        // count > 0: 从左向右，count < 0: 从右向左，count = 0: 从左向右删除所有
+        let front = count >= 0;
+        let count = count.unsigned_abs() as LenType;
+        let mut rem_count: LenType = 0;
+        let mut node_key = if front { quick.left() } else { quick.right() }.ok_or(RrError::none_error("end key"))?.clone();
+        loop {
+            let mut node = QuickListNode::get(t, node_key.as_ref())?.ok_or(RrError::none_error("node"))?;
+            let zip_key = node.values_key().ok_or(RrError::none_error("zip key"))?.clone();
+            let mut zip = ZipList::get(t, zip_key.as_ref())?.ok_or(RrError::none_error("zip"))?;
+
+            let will_count = if count == 0 { 0 } else { (count - rem_count) as i32 };
+            let done = zip.rem(if front { will_count } else { -will_count }, value);
+            rem_count += done;
+
+            // 合并后，未查找的元素可能并入了当前的node，需要重新查找合并后的node
+            let mut merged = None;
+            if done != 0 {
+                quick.modify_node(t, zip_key.as_ref(), &mut zip, node_key.as_ref(), &mut node)?;
+                if zip.len() > 0 {
+                    let len_node = quick.len_node();
+                    let k = quick.merge_node(t, list_key, &node_key, None)?;
+                    if quick.len_node() != len_node {
+                        merged = Some(k);
+                    }
                 }
-                node = QuickListNode::get(&tr, node_key.as_ref())?.ok_or(RrError::none_error("right node"))?;
             }
-        } else if count < 0 {
-            //反向遍历
-            let count = count.abs() as usize;
-            let mut node_key = quick.right().ok_or(RrError::none_error("left key"))?.clone();
-            let mut node = QuickListNode::get(&tr, node_key.as_ref())?.ok_or(RrError::none_error("node"))?;
-
-            loop {
-                let zip_key = node.values_key().ok_or(RrError::none_error("zip key"))?.clone();
-                let mut zip = ZipList::get(&tr, zip_key.as_ref())?.ok_or(RrError::none_error("zip"))?;
-
-                let will_count = count as i32 - rem_count as i32;
-                let done = zip.rem(-will_count, value.as_ref());
-                rem_count += done;
-
-                if done != 0 {
-                    quick.modify_node(&tr, list_key.as_ref(), zip_key.as_ref(), &mut zip, node_key.as_ref(), &mut node)?;
-                }
 
-                if rem_count == count as u64 {
-                    break;
-                }
-                if let Some(t) = node.left() {
-                    node_key = t.clone();
-                } else {
-                    break;
-                }
-                node = QuickListNode::get(&tr, node_key.as_ref())?.ok_or(RrError::none_error("right node"))?;
+            if count != 0 && rem_count == count {
+                break;
             }
-        } else {
-            //正向删除所有相等的值
-            let count = count as usize;
-            let mut node_key = quick.left().ok_or(RrError::none_error("left key"))?.clone();
-            let mut node = QuickListNode::get(&tr, node_key.as_ref())?.ok_or(RrError::none_error("left node"))?;
+            if let Some(k) = merged {
+                node_key = k;
+                continue;
+            }
+            match if front { node.right() } else { node.left() } {
+                Some(next) => node_key = next.clone(),
+                None => break,
+            }
+        }
 
-            loop {
-                let zip_key = node.values_key().ok_or(RrError::none_error("zip key"))?.clone();
-                let mut zip = ZipList::get(&tr, zip_key.as_ref())?.ok_or(RrError::none_error("zip"))?;
+        if rem_count > 0 {
+            quick.set_len_list(quick.len_list() - rem_count);
+            t.put(list_key, quick.as_ref())?;
+            // 删除的元素可能分布在多个node中，重建索引
+            QuickListIndex::rebuild(t, list_key, &quick)?;
+            quick.decompress_ends(t)?;
+        }
+        Ok(rem_count)
+    }
 
-                let done = zip.rem((count - rem_count as usize) as i32, value.as_ref());
-                rem_count += done;
+    fn trim(&self, t: &T, key: &[u8], start: i32, stop: i32) -> Result<i32, RrError> {
+        let mut quick = match QuickList::get(t, key)? {
+            None => return Ok(0),
+            Some(q) => q,
+        };
+        let len = quick.len_list() as i64;
+        let start = if start < 0 { (len + start as i64).max(0) } else { start as i64 };
+        let stop = if stop < 0 { len + stop as i64 } else { (stop as i64).min(len - 1) };
+        if start > stop || start >= len {
+            quick.clear(t, key)?;
+            return Ok(len as i32);
+        }
+        let (head, tail) = (start as LenType, (len - 1 - stop) as LenType);
+        if head == 0 && tail == 0 {
+            return Ok(0);
+        }
 
-                if done != 0 {
-                    quick.modify_node(&tr, list_key.as_ref(), zip_key.as_ref(), &mut zip, node_key.as_ref(), &mut node)?;
+        let (left_key, mut left_node, left_cut) = ListImpl::trim_end(t, key, &mut quick, head, true)?;
+        let (right_key, mut right_node, right_cut) = ListImpl::trim_end(t, key, &mut quick, tail, false)?;
+        // 右边node的结束坐标减少right_cut
+        let right_end = quick.tail() - (tail - right_cut) as i64;
+        QuickListIndex::move_end(t, key, right_end, right_end - right_cut as i64, &right_key)?;
+        if left_key.as_ref() == right_key.as_ref() {
+            // 保留的元素都在一个node中
+            let stop_in = left_node.len_list() - 1 - right_cut;
+            if left_cut > 0 || right_cut > 0 {
+                ListImpl::trim_zip(t, &mut left_node, left_cut, stop_in)?;
+            }
+            left_node.set_left(&None);
+            left_node.set_right(&None);
+            t.put(left_key.as_ref(), left_node.as_ref())?;
+        } else {
+            // 只有两端的node会被修改
+            if head > 0 {
+                if left_cut > 0 {
+                    let stop_in = left_node.len_list() - 1;
+                    ListImpl::trim_zip(t, &mut left_node, left_cut, stop_in)?;
                 }
-                if let Some(t) = node.right() {
-                    node_key = t.clone();
-                } else {
-                    break;
+                left_node.set_left(&None);
+                t.put(left_key.as_ref(), left_node.as_ref())?;
+            }
+            if tail > 0 {
+                if right_cut > 0 {
+                    let stop_in = right_node.len_list() - 1 - right_cut;
+                    ListImpl::trim_zip(t, &mut right_node, 0, stop_in)?;
                 }
-                node = QuickListNode::get(&tr, node_key.as_ref())?.ok_or(RrError::none_error("right node"))?;
+                right_node.set_right(&None);
+                t.put(right_key.as_ref(), right_node.as_ref())?;
             }
         }
+        quick.set_left(&Some(&left_key));
+        quick.set_right(&Some(&right_key));
+        quick.set_head(quick.head() + head as i64);
+        quick.set_len_list(quick.len_list() - head - tail);
+        t.put(key, quick.as_ref())?;
+        quick.decompress_ends(t)?;
+        Ok((head + tail) as i32)
+    }
 
-        if rem_count > 0 {
-            quick.set_len_list(quick.len_list() - rem_count);
-            tr.put(list_key, quick)?;
+    fn set(&self, t: &T, key: &[u8], index: i32, value: &[u8]) -> Result<Vec<u8>, RrError> {
+        let mut quick = QuickList::get(t, key)?.ok_or(RrError::not_find("key of list"))?;
+        let index = ListImpl::real_index(&quick, index)?;
+        let (node_key, mut node, zip_index, end) = ListImpl::find_node(t, key, &quick, index)?;
+        let value_key = node.values_key().context("value key")?.clone();
+        let mut zip = ZipList::get(t, value_key.as_ref())?.context("zip list")?;
+        let v = zip.set(zip_index, value).ok_or(RrError::not_find(&format!("the index {}", index)))?;
+        let old_bytes = node.len_bytes();
+        node.set_len_bytes(zip.as_ref().len() as LenType);
+        zip.put(t, value_key.as_ref(), node.compressed())?;
+        t.put(node_key.as_ref(), node.as_ref())?;
+        if node.len_bytes() < old_bytes {
+            let len_node = quick.len_node();
+            quick.merge_node(t, key, &node_key, Some(end))?;
+            t.put(key, quick.as_ref())?;
+            if quick.len_node() != len_node {
+                quick.decompress_ends(t)?;
+            }
         }
+        Ok(v)
+    }
 
-        tr.commit()?;
-        Ok(rem_count)
+    fn pop_back(&self, t: &T, key: &[u8]) -> Result<Option<Vec<u8>>, RrError> {
+        ListImpl::pop(t, key, false)
     }
 
-    fn list_trim<K: Bytes>(&mut self, _key: K, _start: i32, _stop: i32) -> Result<i32, RrError> {
-        todo!()
+    fn push_back(&self, t: &T, key: &[u8], value: &[u8]) -> Result<i32, RrError> {
+        self.get_or_new(t, key)?.rpush(t, key, value)
     }
 
-    fn list_set<K: Bytes, V: Bytes>(&mut self, key: &K, index: i32, value: &V) -> Result<Vec<u8>, RrError> {
-        let t = QuickList::get(&self.db, key.as_ref())?.ok_or(RrError::not_find("key of list"))?;
-        if index >= t.len_list() as i32 || index < 0 {
-            return Err(RrError::not_find(&format!("the index {}", index)));
-        }
-        //todo read only
-        let tr = self.db.transaction();
-        let node_key = t.left().context("left of quick list")?;
-        let mut node = QuickListNode::get(&tr, node_key.as_ref())?.context("left node")?;
-        let mut it_index = 0i32;
-        it_index += node.len_list() as i32;
-        while index >= it_index {
-            let next_key = node.right().context("right node")?;
-            node = QuickListNode::get(&tr, next_key.as_ref())?.context("next node")?;
-            it_index += node.len_list() as i32;
+    fn push_back_many(&self, t: &T, key: &[u8], values: &[&[u8]]) -> Result<i32, RrError> {
+        if values.is_empty() {
+            return self.len(t, key).map(|len| len.max(0));
         }
+        self.get_or_new(t, key)?.push_many(t, key, values, false)
+    }
 
-        let value_key = node.values_key().context("value key")?;
-        let mut zip = ZipList::get(&tr, value_key.as_ref())?.context("zip list")?;
-        let zip_index = index - (it_index - node.len_list() as i32);
-        let v = zip.set(zip_index, value.as_ref()).ok_or(RrError::not_find(&format!("the index {}", index)))?;
-        tr.put(value_key, zip)?;
-        tr.commit()?;
-        Ok(v)
+    fn push_back_exists(&self, t: &T, key: &[u8], value: &[u8]) -> Result<i32, RrError> {
+        match QuickList::get(t, key)? {
+            None => Ok(0),
+            Some(mut quick) => quick.rpush(t, key, value),
+        }
     }
 
-    fn list_pop_back<K: Bytes>(&mut self, key: &K) -> Result<Option<Vec<u8>>, RrError> {
-        let tr = self.db.transaction();
-        let mut quick = match QuickList::get(&self.db, key.as_ref())? {
-            None => return Ok(None),
+    fn pos(&self, t: &T, key: &[u8], value: &[u8], rank: i64, count: LenType, max_len: LenType) -> Result<Vec<LenType>, RrError> {
+        if rank == 0 {
+            return Err(RrError::message("rank can not be zero".to_owned()));
+        }
+        let mut re = Vec::new();
+        let quick = match QuickList::get(t, key)? {
+            None => return Ok(re),
             Some(q) => q,
         };
-        if quick.len_list() < 1 {
-            return Ok(None);
+        if quick.len_node() == 0 {
+            return Ok(re);
         }
-        let node_key = quick.right().ok_or(RrError::none_error("right key"))?.clone();
-        let mut node = QuickListNode::get(&tr, node_key.as_ref())?.ok_or(RrError::none_error("right node"))?;
-        let zip_key = node.values_key().ok_or(RrError::none_error("zip key"))?.clone();
-        let mut zip = ZipList::get(&tr, zip_key.as_ref())?.ok_or(RrError::none_error("zip list"))?;
-        let value = zip.pop_right();
-
-        if zip.len() == 0 {
-            //没有数据，删除quick list node
-            if quick.len_node() == 1 {
-                tr.delete(zip_key)?;
-                tr.delete(node_key)?;
-                quick.set_right(&None);
-                quick.set_left(&None);
-                quick.set_len_list(0);
-                quick.set_len_node(0);
-                tr.put(key.as_ref(), quick)?;
-            } else {
-                let _left = node.left();
-                quick.set_right(&node.left());
-                quick.set_len_node(quick.len_node() - 1);
-                quick.set_len_list(quick.len_list() - 1);
-                tr.delete(zip_key)?;
-                tr.delete(node_key)?;
-                tr.put(key.as_ref(), quick)?;
+        let len = quick.len_list();
+        let front = rank > 0;
+        let mut skip = rank.unsigned_abs() - 1;
+        // 已经比较过的元素个数
+        let mut scanned: LenType = 0;
+        let mut node_key = if front { quick.left() } else { quick.right() }.ok_or(RrError::none_error("end key"))?.clone();
+        'nodes: loop {
+            let node = QuickListNode::get(t, node_key.as_ref())?.ok_or(RrError::none_error("node"))?;
+            let zip_key = node.values_key().ok_or(RrError::none_error("zip key"))?;
+            let zip = ZipList::get(t, zip_key.as_ref())?.ok_or(RrError::none_error("zip"))?;
+            let n = zip.len();
+            for p in zip.positions(value, !front) {
+                // 从查找的一端开始计算的下标
+                let from_end = scanned + if front { p as LenType } else { n - 1 - p as LenType };
+                if max_len > 0 && from_end >= max_len {
+                    break 'nodes;
+                }
+                if skip > 0 {
+                    skip -= 1;
+                    continue;
+                }
+                re.push(if front { from_end } else { len - 1 - from_end });
+                if count > 0 && re.len() as LenType == count {
+                    break 'nodes;
+                }
+            }
+            scanned += n;
+            if max_len > 0 && scanned >= max_len {
+                break;
+            }
+            match if front { node.right() } else { node.left() } {
+                Some(next) => node_key = next.clone(),
+                None => break,
             }
-        } else {
-            node.set_len_list(zip.len());
-            node.set_len_bytes(zip.as_ref().len() as LenType);
-            quick.set_len_list(quick.len_list() - 1);
-
-            tr.put(zip_key.as_ref(), zip)?;
-            tr.put(node_key.as_ref(), node.as_ref())?;
         }
+        Ok(re)
+    }
 
-        tr.commit()?;
-        Ok(value)
+    fn move_to(&self, t: &T, src: &[u8], dst: &[u8], from: ListEnd, to: ListEnd) -> Result<Option<Vec<u8>>, RrError> {
+        self.move_value(t, src, dst, from, to)
     }
 
-    fn list_replace_back<K: Bytes, V: Bytes>(&mut self, _key: &K, _dstkey: &K) -> Result<V, RrError> {
-        todo!()
+    fn clear(&self, t: &T, key: &[u8]) -> Result<i32, RrError> {
+        match QuickList::get(t, key)? {
+            None => Ok(0),
+            Some(mut quick) => quick.clear(t, key),
+        }
+    }
+}
+
+impl RedisRocksdb {
+    fn list_impl(&self) -> ListImpl {
+        ListImpl { config: self.list_config }
     }
 
-    fn list_push_back<K: Bytes, V: Bytes>(&mut self, key: &K, value: &V) -> Result<i32, RrError> {
+    /// 在一个事务中执行list的操作，成功后提交
+    fn list_transaction<R>(&self, f: impl FnOnce(&WrapTransaction) -> Result<R, RrError>) -> Result<R, RrError> {
         let tr = self.db.transaction();
-        let mut quick = match QuickList::get(&self.db, key.as_ref())? {
-            None => {
-                let mut q = QuickList::new();
-                q.init_meta_key(key.as_ref());
-                q
-            }
-            Some(q) => q,
-        };
-        let re = quick.rpush(&tr, key.as_ref(), value.as_ref())?;
+        let re = f(&WrapTransaction { db: &tr })?;
         tr.commit()?;
         Ok(re)
     }
 
-    fn list_push_back_exists<K: Bytes, V: Bytes>(&mut self, key: &K, value: &V) -> Result<i32, RrError> {
-        let tr = self.db.transaction();
-        let mut quick = match QuickList::get(&self.db, key.as_ref())? {
-            None => return Ok(0),
-            Some(q) => q,
-        };
-        let re = quick.rpush(&tr, key.as_ref(), value.as_ref())?;
-        tr.commit()?;
+    /// list的写操作，pushed为增加了元素的key，提交后唤醒等待它的线程
+    /// 事务在list_waiters的锁外执行，只在notify时加锁，所以不同key的写操作不会互相等待
+    fn list_write<R>(&self, pushed: Option<&[u8]>, f: impl FnOnce(&WrapTransaction) -> Result<R, RrError>) -> Result<R, RrError> {
+        let re = self.list_transaction(f)?;
+        if let Some(key) = pushed {
+            self.list_waiters.lock()?.notify(key);
+        }
         Ok(re)
     }
 
-    fn list_clear<K: Bytes>(&mut self, key: &K) -> Result<i32, RrError> {
-        let tr = self.db.transaction();
-        let mut quick = match QuickList::get(&self.db, key.as_ref())? {
-            None => return Ok(0),
-            Some(q) => q,
-        };
-        let re = quick.len_node();
-        quick.clear(&tr, key.as_ref())?;
-        tr.commit()?;
-        Ok(re as i32)
+    fn list_blocking_pop<K: Bytes>(&self, keys: &[&K], timeout: i64, front: bool) -> Result<Option<(Vec<u8>, Vec<u8>)>, RrError> {
+        let keys: Vec<&[u8]> = keys.iter().map(|k| k.as_ref()).collect();
+        self.list_waiters.wait(&keys, timeout, || {
+            self.list_transaction(|t| {
+                for key in &keys {
+                    if let Some(v) = ListImpl::pop(t, key, front)? {
+                        return Ok(Some((key.to_vec(), v)));
+                    }
+                }
+                Ok(None)
+            })
+        })
+    }
+}
+
+impl RedisList for RedisRocksdb {
+    fn list_blpop<K: Bytes>(&self, keys: &[&K], timeout: i64) -> Result<Option<(Vec<u8>, Vec<u8>)>, RrError> {
+        self.list_blocking_pop(keys, timeout, true)
+    }
+
+    fn list_brpop<K: Bytes>(&self, keys: &[&K], timeout: i64) -> Result<Option<(Vec<u8>, Vec<u8>)>, RrError> {
+        self.list_blocking_pop(keys, timeout, false)
+    }
+
+    fn list_brpoplpush<K: Bytes>(&self, srckey: &K, dstkey: &K, timeout: i64) -> Result<Option<Vec<u8>>, RrError> {
+        self.list_blmove(srckey, dstkey, ListEnd::Back, ListEnd::Front, timeout)
+    }
+
+    fn list_blmove<K: Bytes>(&self, srckey: &K, dstkey: &K, from: ListEnd, to: ListEnd, timeout: i64) -> Result<Option<Vec<u8>>, RrError> {
+        let (src, dst) = (srckey.as_ref(), dstkey.as_ref());
+        let re = self.list_waiters.wait(&[src], timeout, || {
+            self.list_transaction(|t| Ok(self.list_impl().move_value(t, src, dst, from, to)?.map(|v| (src.to_vec(), v))))
+        })?;
+        if re.is_some() {
+            self.list_waiters.lock()?.notify(dst);
+        }
+        Ok(re.map(|(_, v)| v))
+    }
+
+    fn list_index<K: Bytes>(&self, key: &K, index: i32) -> Result<Vec<u8>, RrError> {
+        self.list_transaction(|t| self.list_impl().index(t, key.as_ref(), index))
+    }
+
+    fn list_insert_before<K: Bytes, V: Bytes>(&self, key: &K, pivot: &V, value: &V) -> Result<i32, RrError> {
+        self.list_write(Some(key.as_ref()), |t| {
+            self.list_impl().insert_before(t, key.as_ref(), pivot.as_ref(), value.as_ref())
+        })
+    }
+
+    fn list_insert_after<K: Bytes, V: Bytes>(&self, key: &K, pivot: &V, value: &V) -> Result<i32, RrError> {
+        self.list_write(Some(key.as_ref()), |t| {
+            self.list_impl().insert_after(t, key.as_ref(), pivot.as_ref(), value.as_ref())
+        })
+    }
+
+    fn list_len<K: Bytes>(&self, key: &K) -> Result<i32, RrError> {
+        self.list_transaction(|t| self.list_impl().len(t, key.as_ref()))
+    }
+
+    fn list_pop_front<K: Bytes>(&self, key: &K) -> Result<Option<Vec<u8>>, RrError> {
+        self.list_write(None, |t| self.list_impl().pop_front(t, key.as_ref()))
+    }
+
+    fn list_push_front<K: Bytes, V: Bytes>(&self, key: &K, value: &V) -> Result<i32, RrError> {
+        self.list_write(Some(key.as_ref()), |t| self.list_impl().push_front(t, key.as_ref(), value.as_ref()))
+    }
+
+    fn list_push_front_many<K: Bytes, V: Bytes>(&self, key: &K, values: &[&V]) -> Result<i32, RrError> {
+        let values: Vec<&[u8]> = values.iter().map(|v| v.as_ref()).collect();
+        self.list_write(Some(key.as_ref()), |t| self.list_impl().push_front_many(t, key.as_ref(), &values))
+    }
+
+    fn list_push_front_exists<K: Bytes, V: Bytes>(&self, key: &K, value: &V) -> Result<i32, RrError> {
+        self.list_write(Some(key.as_ref()), |t| self.list_impl().push_front_exists(t, key.as_ref(), value.as_ref()))
+    }
+
+    fn list_range<K: Bytes>(&self, key: &K, start: i32, stop: i32) -> Result<Vec<Vec<u8>>, RrError> {
+        self.list_transaction(|t| self.list_impl().range(t, key.as_ref(), start, stop))
+    }
+
+    fn list_iter<K: Bytes>(&self, key: &K, from: ListEnd) -> Result<ListIter<'_, TransactionDB>, RrError> {
+        ListIter::new(self.db.snapshot(), key.as_ref(), from)
+    }
+
+    fn list_rem<K: Bytes, V: Bytes>(&self, list_key: &K, count: i32, value: &V) -> Result<LenType, RrError> {
+        self.list_write(None, |t| self.list_impl().rem(t, list_key.as_ref(), count, value.as_ref()))
+    }
+
+    fn list_trim<K: Bytes>(&self, key: K, start: i32, stop: i32) -> Result<i32, RrError> {
+        self.list_write(None, |t| self.list_impl().trim(t, key.as_ref(), start, stop))
+    }
+
+    fn list_set<K: Bytes, V: Bytes>(&self, key: &K, index: i32, value: &V) -> Result<Vec<u8>, RrError> {
+        self.list_write(None, |t| self.list_impl().set(t, key.as_ref(), index, value.as_ref()))
+    }
+
+    fn list_pop_back<K: Bytes>(&self, key: &K) -> Result<Option<Vec<u8>>, RrError> {
+        self.list_write(None, |t| self.list_impl().pop_back(t, key.as_ref()))
+    }
+
+    fn list_pos<K: Bytes, V: Bytes>(&self, key: &K, value: &V, rank: i64, count: LenType, max_len: LenType) -> Result<Vec<LenType>, RrError> {
+        self.list_transaction(|t| self.list_impl().pos(t, key.as_ref(), value.as_ref(), rank, count, max_len))
+    }
+
+    fn list_replace_back<K: Bytes>(&self, key: &K, dstkey: &K) -> Result<Option<Vec<u8>>, RrError> {
+        self.list_move(key, dstkey, ListEnd::Back, ListEnd::Front)
+    }
+
+    fn list_move<K: Bytes>(&self, srckey: &K, dstkey: &K, from: ListEnd, to: ListEnd) -> Result<Option<Vec<u8>>, RrError> {
+        self.list_write(Some(dstkey.as_ref()), |t| {
+            self.list_impl().move_value(t, srckey.as_ref(), dstkey.as_ref(), from, to)
+        })
+    }
+
+    fn list_push_back<K: Bytes, V: Bytes>(&self, key: &K, value: &V) -> Result<i32, RrError> {
+        self.list_write(Some(key.as_ref()), |t| self.list_impl().push_back(t, key.as_ref(), value.as_ref()))
+    }
+
+    fn list_push_back_many<K: Bytes, V: Bytes>(&self, key: &K, values: &[&V]) -> Result<i32, RrError> {
+        let values: Vec<&[u8]> = values.iter().map(|v| v.as_ref()).collect();
+        self.list_write(Some(key.as_ref()), |t| self.list_impl().push_back_many(t, key.as_ref(), &values))
+    }
+
+    fn list_push_back_exists<K: Bytes, V: Bytes>(&self, key: &K, value: &V) -> Result<i32, RrError> {
+        self.list_write(Some(key.as_ref()), |t| self.list_impl().push_back_exists(t, key.as_ref(), value.as_ref()))
+    }
+
+    fn list_clear<K: Bytes>(&self, key: &K) -> Result<i32, RrError> {
+        self.list_write(None, |t| self.list_impl().clear(t, key.as_ref()))
     }
 }
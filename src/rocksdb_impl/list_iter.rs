@@ -0,0 +1,85 @@
+use rocksdb::{DBAccess, SnapshotWithThreadMode};
+
+use crate::{
+    rocksdb_impl::{quick_list::QuickList, quick_list_node::QuickListNode, zip_list::ZipList},
+    ListEnd, MetaKey, RrError,
+};
+
+/// 在snapshot上遍历list，需要时才读取下一个node，所以长时间的遍历也是一致的list，不受之后修改的影响
+/// 值是当前zip list中的切片，在下一次调用[ListIter::next_value]之前有效，所以没有实现[Iterator]
+pub struct ListIter<'a, D: DBAccess> {
+    snapshot: SnapshotWithThreadMode<'a, D>,
+    from: ListEnd,
+    next_node: Option<MetaKey>,
+    zip: ZipList,
+    /// Front时为下一个值的offset，Back时为下一个值结束的offset
+    offset: usize,
+}
+
+impl<'a, D: DBAccess> ListIter<'a, D> {
+    /// from为Front时从头部到尾部，为Back时从尾部到头部；list不存在时没有值
+    pub fn new(snapshot: SnapshotWithThreadMode<'a, D>, key: &[u8], from: ListEnd) -> Result<Self, RrError> {
+        let next_node = match snapshot.get(key)? {
+            None => None,
+            Some(v) => {
+                let quick = QuickList::decode(&v)?;
+                match from {
+                    ListEnd::Front => quick.left().cloned(),
+                    ListEnd::Back => quick.right().cloned(),
+                }
+            }
+        };
+        let zip = ZipList::new();
+        let offset = zip.begin_offset();
+        Ok(ListIter {
+            snapshot,
+            from,
+            next_node,
+            zip,
+            offset,
+        })
+    }
+
+    /// 返回下一个值，没有时返回None
+    pub fn next_value(&mut self) -> Result<Option<&[u8]>, RrError> {
+        while !self.has_value() {
+            if !self.read_node()? {
+                return Ok(None);
+            }
+        }
+        let (value, offset) = match self.from {
+            ListEnd::Front => self.zip.value_after(self.offset),
+            ListEnd::Back => self.zip.value_before(self.offset),
+        }
+        .ok_or(RrError::data_error("zip list"))?;
+        self.offset = offset;
+        Ok(Some(value))
+    }
+
+    fn has_value(&self) -> bool {
+        match self.from {
+            ListEnd::Front => self.offset < self.zip.end_offset(),
+            ListEnd::Back => self.offset > self.zip.begin_offset(),
+        }
+    }
+
+    /// 读取下一个node的zip list，没有node时返回false
+    fn read_node(&mut self) -> Result<bool, RrError> {
+        let node_key = match self.next_node.take() {
+            None => return Ok(false),
+            Some(k) => k,
+        };
+        let node = QuickListNode::decode(&self.snapshot.get(node_key)?.ok_or(RrError::none_error("node"))?)?;
+        let values_key = node.values_key().ok_or(RrError::none_error("values key"))?;
+        self.zip = ZipList::decode(self.snapshot.get(values_key)?.ok_or(RrError::none_error("zip list"))?)?;
+        self.next_node = match self.from {
+            ListEnd::Front => node.right().cloned(),
+            ListEnd::Back => node.left().cloned(),
+        };
+        self.offset = match self.from {
+            ListEnd::Front => self.zip.begin_offset(),
+            ListEnd::Back => self.zip.end_offset(),
+        };
+        Ok(true)
+    }
+}
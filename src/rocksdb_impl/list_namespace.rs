@@ -0,0 +1,35 @@
+use std::sync::Mutex;
+
+use crate::{RrError, WrapDb};
+
+/// 分配quick list中node与zip的key的命名空间(即[crate::MetaKey]的key部分)
+/// 保存在数据库中，单调递增，所以不同的list不会重复，也不依赖于hash算法
+pub(crate) struct ListNamespace {}
+
+/// 不在事务中时，同一进程中的分配是串行的，不会读到相同的值
+static LOCK: Mutex<()> = Mutex::new(());
+
+impl ListNamespace {
+    const KEY: &'static [u8] = b"__redis_rocksdb:list_namespace";
+
+    /// 返回一个没有使用过的命名空间，从1开始(0与sep 0组成的MetaKey表示None)
+    /// 在事务中只使用get_for_update锁定计数器，其它事务要等这个事务结束后才能分配
+    /// 事务中不能再使用[LOCK]，否则持有[LOCK]等待行锁的线程与持有行锁等待[LOCK]的事务会互相等待，直到锁超时
+    pub(crate) fn next<T: WrapDb>(t: &T) -> Result<u64, RrError> {
+        if t.in_transaction() {
+            return ListNamespace::allocate(t);
+        }
+        let _guard = LOCK.lock().map_err(|e| RrError::message(e.to_string()))?;
+        ListNamespace::allocate(t)
+    }
+
+    fn allocate<T: WrapDb>(t: &T) -> Result<u64, RrError> {
+        let last: u64 = match t.get_for_update(ListNamespace::KEY)? {
+            None => 0,
+            Some(v) => u64::from_le_bytes(v.as_slice().try_into()?),
+        };
+        let next = last.checked_add(1).ok_or(RrError::message("list namespace is exhausted".to_owned()))?;
+        t.put(ListNamespace::KEY, &next.to_le_bytes())?;
+        Ok(next)
+    }
+}
@@ -0,0 +1,217 @@
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Condvar, Mutex, MutexGuard},
+    time::{Duration, Instant},
+};
+
+use crate::RrError;
+
+/// 阻塞在list上的线程，按阻塞的先后顺序(FIFO)唤醒
+/// 写操作提交后才notify，不持有这个锁；等待的线程在取数据之前已在队列中，取数据时收到的notify会设置ready，
+/// 睡眠之前会再检查ready，所以不会丢失唤醒
+#[derive(Default)]
+pub(crate) struct ListWaiters {
+    queue: Mutex<WaitQueue>,
+}
+
+#[derive(Default)]
+pub(crate) struct WaitQueue {
+    next_id: u64,
+    waiters: VecDeque<Waiter>,
+}
+
+struct Waiter {
+    id: u64,
+    keys: Vec<Vec<u8>>,
+    /// 需要(再)取一次数据：被唤醒了，或者取数据期间有notify
+    ready: bool,
+    cond: Arc<Condvar>,
+}
+
+impl Waiter {
+    fn waits(&self, key: &[u8]) -> bool {
+        self.keys.iter().any(|k| k.as_slice() == key)
+    }
+}
+
+impl WaitQueue {
+    /// key中增加了一个元素，唤醒最早等待key且还没有被唤醒的线程
+    pub(crate) fn notify(&mut self, key: &[u8]) {
+        if let Some(w) = self.waiters.iter_mut().find(|w| !w.ready && w.waits(key)) {
+            w.ready = true;
+            w.cond.notify_one();
+        }
+    }
+
+    /// 取到数据或出错的线程离开队列，它可能是被别的key唤醒的，所以它等待的每个key都要交给下一个等待的线程
+    /// 否则如: W1等待[A, B]，被B唤醒后取走了A的数据，只等待B的线程不会被唤醒
+    fn leave(&mut self, i: usize) {
+        if let Some(w) = self.waiters.remove(i) {
+            for key in &w.keys {
+                self.notify(key);
+            }
+        }
+    }
+
+    fn position(&self, id: u64) -> Result<usize, RrError> {
+        self.waiters.iter().position(|w| w.id == id).ok_or_else(|| RrError::none_error("list waiter"))
+    }
+}
+
+impl ListWaiters {
+    pub(crate) fn lock(&self) -> Result<MutexGuard<'_, WaitQueue>, RrError> {
+        self.queue.lock().map_err(|e| RrError::message(e.to_string()))
+    }
+
+    /// 先执行一次try_pop，没有数据时阻塞，直到被唤醒并且try_pop返回Some，或者超时(返回None)
+    /// timeout的单位为毫秒，0表示一直等待；try_pop返回的Vec<u8>为取到数据的key
+    /// try_pop在锁外执行，所以不会阻塞其它list的读写
+    pub(crate) fn wait<R>(
+        &self,
+        keys: &[&[u8]],
+        timeout: i64,
+        mut try_pop: impl FnMut() -> Result<Option<(Vec<u8>, R)>, RrError>,
+    ) -> Result<Option<(Vec<u8>, R)>, RrError> {
+        if timeout < 0 {
+            return Err(RrError::message(format!("timeout is negative: {}", timeout)));
+        }
+        let deadline = if timeout == 0 {
+            None
+        } else {
+            Some(Instant::now() + Duration::from_millis(timeout as u64))
+        };
+
+        let mut queue = self.lock()?;
+        let id = queue.next_id;
+        queue.next_id += 1;
+        let cond = Arc::new(Condvar::new());
+        // 已被唤醒的线程还没有取走数据时，不能抢先，等它离开时再唤醒
+        let ready = !queue.waiters.iter().any(|w| w.ready && keys.iter().any(|k| w.waits(k)));
+        queue.waiters.push_back(Waiter {
+            id,
+            keys: keys.iter().map(|k| k.to_vec()).collect(),
+            ready,
+            cond: cond.clone(),
+        });
+
+        loop {
+            let i = queue.position(id)?;
+            if queue.waiters[i].ready {
+                queue.waiters[i].ready = false;
+                drop(queue);
+                let re = try_pop();
+                queue = self.lock()?;
+                let i = queue.position(id)?;
+                match re {
+                    Ok(Some((key, v))) => {
+                        // 可能还有数据，交给下一个等待的线程
+                        queue.leave(i);
+                        return Ok(Some((key, v)));
+                    }
+                    //数据被别的线程取走了，在队列中的位置不变；取数据期间有notify时ready为true，再取一次
+                    Ok(None) => continue,
+                    Err(e) => {
+                        queue.leave(i);
+                        return Err(e);
+                    }
+                }
+            }
+
+            let remaining = match deadline {
+                None => None,
+                Some(d) => match d.checked_duration_since(Instant::now()) {
+                    Some(r) if !r.is_zero() => Some(r),
+                    _ => {
+                        queue.waiters.remove(i);
+                        return Ok(None);
+                    }
+                },
+            };
+            queue = match remaining {
+                None => cond.wait(queue).map_err(|e| RrError::message(e.to_string()))?,
+                Some(r) => cond.wait_timeout(queue, r).map_err(|e| RrError::message(e.to_string()))?.0,
+            };
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{
+        collections::HashMap,
+        sync::{Arc, Mutex},
+        thread,
+        time::{Duration, Instant},
+    };
+
+    use crate::rocksdb_impl::list_waiters::ListWaiters;
+
+    type Lists = Arc<Mutex<HashMap<Vec<u8>, Vec<u8>>>>;
+
+    fn blocking_pop(waiters: &ListWaiters, lists: &Lists, keys: &[&[u8]]) -> Option<(Vec<u8>, u8)> {
+        waiters
+            .wait(keys, 2000, || {
+                let mut lists = lists.lock().expect("");
+                for key in keys {
+                    if let Some(v) = lists.get_mut(*key).and_then(|l| l.pop()) {
+                        return Ok(Some((key.to_vec(), v)));
+                    }
+                }
+                Ok(None)
+            })
+            .expect("")
+    }
+
+    #[test]
+    fn test_list_waiters_multi_keys() {
+        let waiters = Arc::new(ListWaiters::default());
+        let lists: Lists = Arc::default();
+        let spawn = |keys: Vec<&'static [u8]>| {
+            let count = waiters.lock().expect("").waiters.len();
+            let (w, l) = (waiters.clone(), lists.clone());
+            let h = thread::spawn(move || blocking_pop(&w, &l, &keys));
+            while waiters.lock().expect("").waiters.len() == count {
+                thread::sleep(Duration::from_millis(1));
+            }
+            h
+        };
+        let w1 = spawn(vec![b"a", b"b"]);
+        let w3 = spawn(vec![b"b"]);
+
+        // B的数据唤醒W1，W1运行前A也有了数据，W1取走A的数据后，要唤醒等待B的W3
+        {
+            let mut queue = waiters.lock().expect("");
+            lists.lock().expect("").insert(b"b".to_vec(), vec![2]);
+            queue.notify(b"b");
+            lists.lock().expect("").insert(b"a".to_vec(), vec![1]);
+            queue.notify(b"a");
+        }
+        assert_eq!(Some((b"a".to_vec(), 1)), w1.join().expect(""));
+        assert_eq!(Some((b"b".to_vec(), 2)), w3.join().expect(""));
+        assert!(waiters.lock().expect("").waiters.is_empty());
+    }
+
+    #[test]
+    fn test_list_waiters_push_during_pop() {
+        // try_pop没有取到数据，返回之前另一个线程写入并notify，睡眠之前要再取一次
+        let waiters = ListWaiters::default();
+        let lists: Lists = Arc::default();
+        let mut calls = 0;
+        let start = Instant::now();
+        let re = waiters
+            .wait(&[b"a"], 2000, || {
+                calls += 1;
+                let v = lists.lock().expect("").get_mut(&b"a".to_vec()).and_then(|l| l.pop());
+                if calls == 1 {
+                    lists.lock().expect("").insert(b"a".to_vec(), vec![1]);
+                    waiters.lock()?.notify(b"a");
+                }
+                Ok(v.map(|v| (b"a".to_vec(), v)))
+            })
+            .expect("");
+        assert_eq!(Some((b"a".to_vec(), 1)), re);
+        assert_eq!(2, calls);
+        assert!(start.elapsed() < Duration::from_millis(1000));
+        assert!(waiters.lock().expect("").waiters.is_empty());
+    }
+}
@@ -1,19 +1,31 @@
+pub use bptree::*;
 pub use heap::*;
+pub use list_impl::*;
+pub use list_iter::*;
 pub use object_bit::*;
 pub use object_impl::*;
 pub use redis_rocksdb::RedisRocksdb;
+pub use set_impl::*;
+pub use sorted_set_impl::*;
+pub use stack_impl::*;
 pub use wrap_db_impl::*;
 
 mod bptree;
 mod heap;
 mod key_value_impl;
 mod list_impl;
+mod list_iter;
+mod list_namespace;
+mod list_waiters;
 mod object_bit;
 mod object_impl;
 mod quick_list;
+mod quick_list_index;
 mod quick_list_node;
 mod redis_rocksdb;
+mod set_impl;
 mod shared;
+mod sorted_set_impl;
 mod stack_impl;
 mod wrap_db_impl;
 mod zip_list;
@@ -1,15 +1,9 @@
 use core::mem;
-use std::{
-    collections::hash_map::DefaultHasher,
-    hash::{Hash, Hasher},
-};
-
-use rocksdb::{Transaction, TransactionDB};
 
 use crate::{
-    read_len_type,
-    rocksdb_impl::{quick_list_node::QuickListNode, zip_list::ZipList},
-    write_len_type, LenType, MetaKey, RrError, BYTES_LEN_TYPE,
+    read_int, read_len_type,
+    rocksdb_impl::{list_namespace::ListNamespace, quick_list_index::QuickListIndex, quick_list_node::QuickListNode, zip_list::ZipList},
+    write_int, write_len_type, LenType, ListConfig, MetaKey, RrError, WrapDb, BYTES_LEN_TYPE,
 };
 
 struct _QuickList {
@@ -22,9 +16,14 @@ struct _QuickList {
     left: Option<MetaKey>,
     /// 当只有一个node时， right == left
     right: Option<MetaKey>,
-    // //todo 对于大的list的read进行优化
-    // index_middle: LenType,
-    // middle: Option<MetaKey>,
+    /// 第一个元素的坐标，参见[QuickListIndex]
+    head: i64,
+    /// 是否已经建立索引，旧版本的header没有这个字段
+    indexed: bool,
+    /// node的容量与压缩，参见[ListConfig]，旧版本的header没有这些字段，为0时使用默认值
+    max_len: LenType,
+    max_bytes: LenType,
+    compress_depth: LenType,
 }
 
 pub struct QuickList([u8; mem::size_of::<_QuickList>()]);
@@ -33,28 +32,57 @@ impl QuickList {
     const OFFSET_META_KEY: usize = BYTES_LEN_TYPE + BYTES_LEN_TYPE;
     const OFFSET_LEFT: usize = QuickList::OFFSET_META_KEY + mem::size_of::<MetaKey>();
     const OFFSET_RIGHT: usize = QuickList::OFFSET_LEFT + mem::size_of::<MetaKey>();
+    const OFFSET_HEAD: usize = QuickList::OFFSET_RIGHT + mem::size_of::<MetaKey>();
+    const OFFSET_INDEXED: usize = QuickList::OFFSET_HEAD + mem::size_of::<i64>();
+    const OFFSET_MAX_LEN: usize = QuickList::OFFSET_INDEXED + 1;
+    const OFFSET_MAX_BYTES: usize = QuickList::OFFSET_MAX_LEN + BYTES_LEN_TYPE;
+    const OFFSET_COMPRESS_DEPTH: usize = QuickList::OFFSET_MAX_BYTES + BYTES_LEN_TYPE;
+    /// 没有索引的旧版本header的长度，(head与indexed之前的部分)
+    const LEN_NO_INDEX: usize = 48;
+    /// 没有node容量的旧版本header的长度
+    const LEN_NO_CONFIG: usize = 64;
+    /// 没有compress_depth的旧版本header的长度
+    const LEN_NO_COMPRESS: usize = 80;
 
     pub fn new() -> Self {
-        QuickList([0; mem::size_of::<_QuickList>()])
+        let mut q = QuickList([0; mem::size_of::<_QuickList>()]);
+        q.0[QuickList::OFFSET_INDEXED] = 1;
+        q
     }
 
-    pub(crate) fn get(db: &TransactionDB, key: &[u8]) -> Result<Option<QuickList>, RrError> {
-        let v = db.get(key)?;
+    /// 没有索引时(旧版本的header)，第一次读取时建立索引并保存
+    pub(crate) fn get<T: WrapDb>(t: &T, key: &[u8]) -> Result<Option<QuickList>, RrError> {
+        let v = t.get(key)?;
         match v {
             None => Ok(None),
             Some(v) => {
-                if v.len() == mem::size_of::<QuickList>() {
-                    let t: [u8; mem::size_of::<QuickList>()] = v.to_vec().as_slice().try_into()?;
-                    Ok(Some(QuickList::from(t)))
-                } else {
-                    Err(RrError::message("can not convert vec to QuickList, the len is not eq".to_owned()))
+                let mut quick = QuickList::decode(&v)?;
+                if quick.0[QuickList::OFFSET_INDEXED] == 0 {
+                    QuickListIndex::rebuild(t, key, &quick)?;
+                    quick.0[QuickList::OFFSET_INDEXED] = 1;
+                    t.put(key, quick.as_ref())?;
                 }
+                Ok(Some(quick))
             }
         }
     }
 
-    pub(crate) fn get_node(db: &TransactionDB, key: &[u8]) -> Result<Option<QuickListNode>, RrError> {
-        let v = db.get(key)?;
+    /// 旧版本的header较短，缺少的字段为0，不会建立索引
+    pub(crate) fn decode(v: &[u8]) -> Result<QuickList, RrError> {
+        if v.len() == mem::size_of::<QuickList>() {
+            let bytes: [u8; mem::size_of::<QuickList>()] = v.try_into()?;
+            Ok(QuickList::from(bytes))
+        } else if [QuickList::LEN_NO_INDEX, QuickList::LEN_NO_CONFIG, QuickList::LEN_NO_COMPRESS].contains(&v.len()) {
+            let mut quick = QuickList([0; mem::size_of::<_QuickList>()]);
+            quick.0[..v.len()].copy_from_slice(v);
+            Ok(quick)
+        } else {
+            Err(RrError::message("can not convert vec to QuickList, the len is not eq".to_owned()))
+        }
+    }
+
+    pub(crate) fn get_node<T: WrapDb>(t: &T, key: &[u8]) -> Result<Option<QuickListNode>, RrError> {
+        let v = t.get(key)?;
         match v {
             None => Ok(None),
             Some(v) => {
@@ -68,153 +96,105 @@ impl QuickList {
         }
     }
 
-    pub(crate) fn lpush(&mut self, tr: &Transaction<TransactionDB>, list_key: &[u8], value: &[u8]) -> Result<i32, RrError> {
+    pub(crate) fn lpush<T: WrapDb>(&mut self, t: &T, list_key: &[u8], value: &[u8]) -> Result<i32, RrError> {
+        self.push_many(t, list_key, &[value], true)
+    }
+
+    pub(crate) fn rpush<T: WrapDb>(&mut self, t: &T, list_key: &[u8], value: &[u8]) -> Result<i32, RrError> {
+        self.push_many(t, list_key, &[value], false)
+    }
+
+    /// 依次把values加入到一端(front为true时是左边)，返回list的len
+    /// 每个node只读写一次，zip中的元素过多，或内存过大，都会新增加node
+    pub(crate) fn push_many<T: WrapDb>(&mut self, t: &T, list_key: &[u8], values: &[&[u8]], front: bool) -> Result<i32, RrError> {
         let quick = self;
-        if quick.len_node() == 0 {
+        if values.is_empty() {
+            return Ok(quick.len_list() as i32);
+        }
+        let end_key = if front { quick.left() } else { quick.right() }.cloned();
+        // 左边的新node的结束坐标在创建时确定，已有的左边node的结束坐标不变
+        let mut front_end = if end_key.is_none() { Some(quick.head()) } else { None };
+        let (mut node_key, mut node, mut zip) = match end_key {
             //可能是第一次创建，也可能是删除后，没有数据了
-            let node_key = quick.next_meta_key().ok_or(RrError::none_error("next_meta_key"))?;
-            let mut node = QuickListNode::new();
-            {
-                let zip_key = quick.next_meta_key().ok_or(RrError::none_error("next_meta_key"))?;
-                let mut zip = ZipList::new();
-                zip.push_left(value.as_ref());
-                tr.put(zip_key.as_ref(), zip.as_ref())?;
-
-                node.set_len_list(1);
-                node.set_len_bytes(zip.as_ref().len() as LenType);
-                node.set_values_key(&Some(&zip_key));
+            None => {
+                let (node_key, node) = quick.new_node(t)?;
+                quick.set_left(&Some(&node_key));
+                quick.set_right(&Some(&node_key));
+                quick.set_len_node(1);
+                (node_key, node, ZipList::new())
             }
-            tr.put(node_key.as_ref(), node.as_ref())?;
-
-            quick.set_len_node(1);
-            quick.set_len_list(node.len_list());
-            quick.set_left(&Some(&node_key));
-            quick.set_right(&Some(&node_key));
-            tr.put(list_key.as_ref(), quick.as_ref())?;
-        } else {
-            let node_key = quick.left().ok_or(RrError::message("quick.left() return None".to_owned()))?.clone();
-
-            let mut node = QuickListNode::get(&tr, node_key.as_ref())?.ok_or(RrError::message("quick.left() return None".to_owned()))?;
-
-            // zip中的元素过多，或内存过大，都会新增加node
-            if node.len_list() > QuickListNode::MAX_LEN || node.len_bytes() > QuickListNode::MAX_BYTES {
-                //增加node
-                let new_node_key = quick.next_meta_key().ok_or(RrError::none_error("next_meta_key"))?;
-                let new_node = {
-                    let mut new_node = QuickListNode::new();
-                    let mut zip = ZipList::new();
-                    zip.push_left(value.as_ref());
-                    let zip_key = quick.next_meta_key().ok_or(RrError::message("next_meta_key return None".to_owned()))?;
-                    tr.put(zip_key.as_ref(), zip.as_ref())?;
-
-                    new_node.set_values_key(&Some(&zip_key));
-                    new_node.set_len_list(zip.len());
-                    new_node.set_len_bytes(zip.as_ref().len() as LenType);
+            Some(node_key) => {
+                let node = QuickListNode::get(t, node_key.as_ref())?.ok_or(RrError::none_error("end node"))?;
+                let zip_key = node.values_key().ok_or(RrError::none_error("values_key"))?;
+                let zip = ZipList::get(t, zip_key.as_ref())?.ok_or(RrError::none_error("ZipList::get"))?;
+                if !front {
+                    //右边node的结束坐标会改变，保存node时重新加入
+                    QuickListIndex::delete(t, list_key, quick.tail())?;
+                }
+                (node_key, node, zip)
+            }
+        };
+        let mut new_nodes = 0;
 
+        for value in values {
+            if quick.is_full(&node) {
+                let (new_key, mut new_node) = quick.new_node(t)?;
+                if front {
                     new_node.set_right(&Some(&node_key));
-                    node.set_left(&Some(&new_node_key));
-
-                    new_node
-                };
-                tr.put(new_node_key.as_ref(), new_node.as_ref())?;
-                tr.put(node_key.as_ref(), node.as_ref())?;
-
+                    node.set_left(&Some(&new_key));
+                    quick.set_left(&Some(&new_key));
+                } else {
+                    new_node.set_left(&Some(&node_key));
+                    node.set_right(&Some(&new_key));
+                    quick.set_right(&Some(&new_key));
+                }
+                let end = if front { front_end } else { Some(quick.tail()) };
+                quick.save_node(t, list_key, &node_key, &node, &zip, end)?;
+                front_end = Some(quick.head());
                 quick.set_len_node(quick.len_node() + 1);
-                quick.set_len_list(quick.len_list() + 1);
-                quick.set_left(&Some(&new_node_key));
-                tr.put(list_key.as_ref(), quick.as_ref())?;
+                new_nodes += 1;
+                (node_key, node, zip) = (new_key, new_node, ZipList::new());
+            }
+            if front {
+                zip.push_left(value);
+                quick.set_head(quick.head() - 1);
             } else {
-                let zip_key = node.values_key().ok_or(RrError::none_error("values_key"))?.clone();
-                let mut zip = ZipList::get(&tr, zip_key.as_ref())?.ok_or(RrError::none_error("ZipList::get"))?;
-                zip.push_left(value.as_ref());
-
-                node.set_len_list(zip.len());
-                node.set_len_bytes(zip.as_ref().len() as LenType);
-                tr.put(zip_key.as_ref(), zip.as_ref())?;
-                tr.put(node_key.as_ref(), node.as_ref())?;
-
-                quick.set_len_list(quick.len_list() + 1);
-                tr.put(list_key.as_ref(), quick.as_ref())?;
+                zip.push_right(value);
             }
+            node.set_len_list(zip.len());
+            node.set_len_bytes(zip.as_ref().len() as LenType);
+            quick.set_len_list(quick.len_list() + 1);
         }
+        let end = if front { front_end } else { Some(quick.tail()) };
+        quick.save_node(t, list_key, &node_key, &node, &zip, end)?;
+        t.put(list_key, quick.as_ref())?;
+        quick.compress_inner(t, front, new_nodes)?;
         Ok(quick.len_list() as i32)
     }
 
-    pub(crate) fn rpush(&mut self, tr: &Transaction<TransactionDB>, list_key: &[u8], value: &[u8]) -> Result<i32, RrError> {
-        let quick = self;
-        if quick.len_node() == 0 {
-            //可能是第一次创建，也可能是删除后，没有数据了
-            let node_key = quick.next_meta_key().ok_or(RrError::none_error("next_meta_key"))?;
-            let mut node = QuickListNode::new();
-            {
-                let zip_key = quick.next_meta_key().ok_or(RrError::none_error("next_meta_key"))?;
-                let mut zip = ZipList::new();
-                zip.push_right(value.as_ref());
-                tr.put(zip_key.as_ref(), zip.as_ref())?;
-
-                node.set_len_list(1);
-                node.set_len_bytes(zip.as_ref().len() as LenType);
-                node.set_values_key(&Some(&zip_key));
-            }
-            tr.put(node_key.as_ref(), node.as_ref())?;
-
-            quick.set_len_node(1);
-            quick.set_len_list(node.len_list());
-            quick.set_left(&Some(&node_key));
-            quick.set_right(&Some(&node_key));
-            tr.put(list_key.as_ref(), quick.as_ref())?;
-        } else {
-            let node_key = quick.right().ok_or(RrError::message("quick.right() return None".to_owned()))?.clone();
-
-            let mut node = QuickListNode::get(&tr, node_key.as_ref())?.ok_or(RrError::message("quick.right() return None".to_owned()))?;
-
-            // zip中的元素过多，或内存过大，都会新增加node
-            if node.len_list() > QuickListNode::MAX_LEN || node.len_bytes() > QuickListNode::MAX_BYTES {
-                //增加node
-                let new_node_key = quick.next_meta_key().ok_or(RrError::none_error("next_meta_key"))?;
-                let new_node = {
-                    let mut new_node = QuickListNode::new();
-                    let mut zip = ZipList::new();
-                    zip.push_right(value.as_ref());
-                    let zip_key = quick.next_meta_key().ok_or(RrError::message("next_meta_key return None".to_owned()))?;
-                    tr.put(zip_key.as_ref(), zip.as_ref())?;
-
-                    new_node.set_values_key(&Some(&zip_key));
-                    new_node.set_len_list(zip.len());
-                    new_node.set_len_bytes(zip.as_ref().len() as LenType);
-
-                    new_node.set_left(&Some(&node_key));
-                    node.set_right(&Some(&new_node_key));
-
-                    new_node
-                };
-                tr.put(new_node_key.as_ref(), new_node.as_ref())?;
-                tr.put(node_key.as_ref(), node.as_ref())?;
-
-                quick.set_len_node(quick.len_node() + 1);
-                quick.set_len_list(quick.len_list() + 1);
-                quick.set_right(&Some(&new_node_key));
-                tr.put(list_key.as_ref(), quick.as_ref())?;
-            } else {
-                let zip_key = node.values_key().ok_or(RrError::none_error("values_key"))?.clone();
-                let mut zip = ZipList::get(&tr, zip_key.as_ref())?.ok_or(RrError::none_error("ZipList::get"))?;
-                zip.push_right(value.as_ref());
-
-                node.set_len_list(zip.len());
-                node.set_len_bytes(zip.as_ref().len() as LenType);
-                tr.put(zip_key.as_ref(), zip.as_ref())?;
-                tr.put(node_key.as_ref(), node.as_ref())?;
+    /// 分配node与zip的key，返回新的node
+    fn new_node<T: WrapDb>(&mut self, t: &T) -> Result<(MetaKey, QuickListNode), RrError> {
+        let node_key = self.next_meta_key(t)?;
+        let zip_key = self.next_meta_key(t)?;
+        let mut node = QuickListNode::new();
+        node.set_values_key(&Some(&zip_key));
+        Ok((node_key, node))
+    }
 
-                quick.set_len_list(quick.len_list() + 1);
-                tr.put(list_key.as_ref(), quick.as_ref())?;
-            }
+    /// push时保存端点的node，end为node的结束坐标，None表示结束坐标没有改变，不修改索引
+    fn save_node<T: WrapDb>(&self, t: &T, list_key: &[u8], node_key: &MetaKey, node: &QuickListNode, zip: &ZipList, end: Option<i64>) -> Result<(), RrError> {
+        let zip_key = node.values_key().ok_or(RrError::none_error("values_key"))?;
+        zip.put(t, zip_key.as_ref(), node.compressed())?;
+        t.put(node_key.as_ref(), node.as_ref())?;
+        match end {
+            None => Ok(()),
+            Some(end) => QuickListIndex::put(t, list_key, end, node_key),
         }
-        Ok(quick.len_list() as i32)
     }
 
-    pub(crate) fn list_insert(
+    pub(crate) fn list_insert<T: WrapDb>(
         &mut self,
-        tr: &Transaction<TransactionDB>,
+        t: &T,
         list_key: &[u8],
         pivot: &[u8],
         value: &[u8],
@@ -222,20 +202,19 @@ impl QuickList {
     ) -> Result<i32, RrError> {
         let quick = self;
         let mut node_key = quick.left().ok_or(RrError::none_error("left key"))?.clone();
-        let mut node = QuickListNode::get(&tr, node_key.as_ref())?.ok_or(RrError::none_error("left node"))?;
+        let mut node = QuickListNode::get(t, node_key.as_ref())?.ok_or(RrError::none_error("left node"))?;
 
         let (zip, zip_key) = loop {
             let zip_key = node.values_key().ok_or(RrError::none_error("zip key"))?;
-            let mut zip = ZipList::get(&tr, zip_key.as_ref())?.ok_or(RrError::none_error("zip list"))?;
-            let t = f(&mut zip, pivot.as_ref(), value.as_ref());
-            if t.is_some() {
+            let mut zip = ZipList::get(t, zip_key.as_ref())?.ok_or(RrError::none_error("zip list"))?;
+            if f(&mut zip, pivot.as_ref(), value.as_ref()).is_some() {
                 break (Some(zip), zip_key.clone());
             }
             match node.right() {
                 None => break (None, zip_key.clone()), //双向链表完成
-                Some(t) => {
-                    node_key = t.clone();
-                    node = QuickListNode::get(&tr, node_key.as_ref())?.ok_or(RrError::none_error("right node"))?;
+                Some(next) => {
+                    node_key = next.clone();
+                    node = QuickListNode::get(t, node_key.as_ref())?.ok_or(RrError::none_error("right node"))?;
                 }
             }
         };
@@ -246,14 +225,27 @@ impl QuickList {
             quick.set_len_list(quick.len_list() + 1);
             result = quick.len_list() as i32;
 
-            tr.put(zip_key, zip.as_ref())?;
-            tr.put(node_key.as_ref(), node.as_ref())?;
-            tr.put(list_key.as_ref(), quick.as_ref())?;
+            zip.put(t, zip_key.as_ref(), node.compressed())?;
+            t.put(node_key.as_ref(), node.as_ref())?;
+            t.put(list_key.as_ref(), quick.as_ref())?;
+
+            //插入位置及其右边node的结束坐标都加一，从右向左修改，避免覆盖
+            let mut end = quick.tail() - 1;
+            let mut key = quick.right().cloned();
+            while let Some(k) = key {
+                QuickListIndex::move_end(t, list_key, end, end + 1, &k)?;
+                if k.as_ref() == node_key.as_ref() {
+                    break;
+                }
+                let right = QuickListNode::get(t, k.as_ref())?.ok_or(RrError::none_error("node"))?;
+                end -= right.len_list() as i64;
+                key = right.left().cloned();
+            }
         }
         Ok(result)
     }
 
-    pub(crate) fn clear(&mut self, tr: &Transaction<TransactionDB>, list_key: &[u8]) -> Result<i32, RrError> {
+    pub(crate) fn clear<T: WrapDb>(&mut self, t: &T, list_key: &[u8]) -> Result<i32, RrError> {
         let l = self.len_node();
         let quick = self;
 
@@ -263,25 +255,28 @@ impl QuickList {
         let mut node;
         while let Some(key) = node_key {
             let key = key.clone();
-            node = QuickListNode::get(&tr, key.as_ref())?.ok_or(RrError::none_error("node"))?;
+            node = QuickListNode::get(t, key.as_ref())?.ok_or(RrError::none_error("node"))?;
             let value_key = node.values_key().ok_or(RrError::none_error("value key"))?;
-            tr.delete(value_key)?;
-            tr.delete(key)?;
+            t.delete(value_key.as_ref())?;
+            t.delete(key.as_ref())?;
             node_key = node.left();
         }
-        tr.delete(list_key)?;
+        QuickListIndex::clear(t, list_key)?;
+        t.delete(list_key)?;
         // tr.commit()?;
         Ok(l as i32)
     }
 
-    pub(crate) fn next_meta_key(&mut self) -> Option<MetaKey> {
-        match MetaKey::read_mut(&self.0[QuickList::OFFSET_META_KEY..]) {
-            None => None,
-            Some(k) => {
-                k.add_sep(1);
-                Some(k.clone())
-            }
+    /// 产生node或zip的key，sep用完时分配新的命名空间
+    pub(crate) fn next_meta_key<T: WrapDb>(&mut self, t: &T) -> Result<MetaKey, RrError> {
+        let mut key = self.meta_key().ok_or(RrError::none_error("meta key"))?.clone();
+        if key.sep() == u16::MAX {
+            key = MetaKey::new();
+            key.set_key(ListNamespace::next(t)?);
         }
+        key.add_sep(1);
+        self.set_meta_key(&Some(&key));
+        Ok(key)
     }
 
     //node的个数
@@ -312,19 +307,15 @@ impl QuickList {
         MetaKey::write(&mut self.0[QuickList::OFFSET_META_KEY..], meta_key)
     }
 
-    #[inline]
-    pub fn init_meta_key(&mut self, list_key: &[u8]) -> MetaKey {
-        // todo 不能初始化两次
-        let meta_key = {
-            let mut meta_key = MetaKey::new();
-            let mut hasher = DefaultHasher::new();
-            list_key.hash(&mut hasher);
-            meta_key.set_key(hasher.finish());
-            meta_key
-        };
-
-        MetaKey::write(&mut self.0[QuickList::OFFSET_META_KEY..], &Some(&meta_key));
-        meta_key
+    /// 新建list时分配命名空间，只能初始化一次
+    pub(crate) fn init_meta_key<T: WrapDb>(&mut self, t: &T) -> Result<(), RrError> {
+        if self.meta_key().is_some() {
+            return Err(RrError::message("the meta key of quick list is initialized".to_owned()));
+        }
+        let mut meta_key = MetaKey::new();
+        meta_key.set_key(ListNamespace::next(t)?);
+        self.set_meta_key(&Some(&meta_key));
+        Ok(())
     }
 
     pub fn left(&self) -> Option<&MetaKey> {
@@ -343,11 +334,47 @@ impl QuickList {
         MetaKey::write(&mut self.0[QuickList::OFFSET_RIGHT..], meta_key)
     }
 
-    //////
-    pub(crate) fn modify_node(
+    /// node的容量，header中为0的字段使用默认值
+    pub fn config(&self) -> ListConfig {
+        let or_default = |v: LenType, d: LenType| if v == 0 { d } else { v };
+        ListConfig {
+            max_len: or_default(read_len_type(&self.0[QuickList::OFFSET_MAX_LEN..]), ListConfig::DEFAULT_MAX_LEN),
+            max_bytes: or_default(read_len_type(&self.0[QuickList::OFFSET_MAX_BYTES..]), ListConfig::DEFAULT_MAX_BYTES),
+            compress_depth: read_len_type(&self.0[QuickList::OFFSET_COMPRESS_DEPTH..]),
+        }
+    }
+
+    pub fn set_config(&mut self, config: &ListConfig) {
+        write_len_type(&mut self.0[QuickList::OFFSET_MAX_LEN..], config.max_len);
+        write_len_type(&mut self.0[QuickList::OFFSET_MAX_BYTES..], config.max_bytes);
+        write_len_type(&mut self.0[QuickList::OFFSET_COMPRESS_DEPTH..], config.compress_depth);
+    }
+
+    /// node已满，不能再加入元素
+    fn is_full(&self, node: &QuickListNode) -> bool {
+        let config = self.config();
+        node.len_list() > config.max_len || node.len_bytes() > config.max_bytes
+    }
+
+    /// 第一个元素的坐标
+    pub fn head(&self) -> i64 {
+        read_int(&self.0[QuickList::OFFSET_HEAD..])
+    }
+
+    pub fn set_head(&mut self, head: i64) {
+        write_int(&mut self.0[QuickList::OFFSET_HEAD..], head)
+    }
+
+    /// 最后一个元素的坐标加一，也是最右边node的结束坐标
+    pub fn tail(&self) -> i64 {
+        self.head() + self.len_list() as i64
+    }
+
+    /// zip中的数据修改后，保存zip与node，zip为空时删除node并修改相邻node与quick的指针
+    /// quick的len_list由调用者修改并保存
+    pub(crate) fn modify_node<T: WrapDb>(
         &mut self,
-        tr: &Transaction<TransactionDB>,
-        list_key: &[u8],
+        t: &T,
         zip_key: &[u8],
         zip: &mut ZipList,
         node_key: &[u8],
@@ -356,52 +383,175 @@ impl QuickList {
         let quick = self;
         if zip.len() == 0 {
             //删除当前node
-            tr.delete(zip_key)?;
-
-            let left = node.left();
-            let right = node.right();
+            t.delete(zip_key)?;
+            t.delete(node_key)?;
 
-            match (left, right) {
+            match (node.left(), node.right()) {
                 (None, None) => {
                     //都没有数据，清空list
-                    tr.delete(&node_key)?;
                     quick.set_left(&None);
                     quick.set_right(&None);
-                    quick.set_len_node(0);
-                    quick.set_len_list(0);
-                    tr.put(list_key, quick.as_ref())?;
                 }
                 (Some(left_key), None) => {
-                    let mut left_node = QuickListNode::get(&tr, left_key.as_ref())?.ok_or(RrError::none_error("left node"))?;
+                    let mut left_node = QuickListNode::get(t, left_key.as_ref())?.ok_or(RrError::none_error("left node"))?;
                     left_node.set_right(&None);
-                    tr.delete(node_key)?;
-                    tr.put(&list_key, &left_node)?;
+                    t.put(left_key.as_ref(), left_node.as_ref())?;
+                    quick.set_right(&Some(left_key));
                 }
                 (Some(left_key), Some(right_key)) => {
-                    let mut left_node = QuickListNode::get(&tr, left_key.as_ref())?.ok_or(RrError::none_error("left node"))?;
-                    let mut right_node = QuickListNode::get(&tr, right_key.as_ref())?.ok_or(RrError::none_error("right node"))?;
+                    let mut left_node = QuickListNode::get(t, left_key.as_ref())?.ok_or(RrError::none_error("left node"))?;
+                    let mut right_node = QuickListNode::get(t, right_key.as_ref())?.ok_or(RrError::none_error("right node"))?;
                     left_node.set_right(&Some(right_key));
-                    right_node.set_right(&Some(left_key));
-                    tr.delete(node_key)?;
-                    tr.put(left_key, &left_node)?;
-                    tr.put(right_key, &right_node)?;
+                    right_node.set_left(&Some(left_key));
+                    t.put(left_key.as_ref(), left_node.as_ref())?;
+                    t.put(right_key.as_ref(), right_node.as_ref())?;
                 }
                 (None, Some(right_key)) => {
-                    let mut right_node = QuickListNode::get(&tr, right_key.as_ref())?.ok_or(RrError::none_error("right node"))?;
+                    let mut right_node = QuickListNode::get(t, right_key.as_ref())?.ok_or(RrError::none_error("right node"))?;
                     right_node.set_left(&None);
-                    //todo quick 的right是否要处理
+                    t.put(right_key.as_ref(), right_node.as_ref())?;
                     quick.set_left(&Some(right_key));
                 }
             }
+            quick.set_len_node(quick.len_node() - 1);
         } else {
             node.set_len_list(zip.len());
             node.set_len_bytes(zip.as_ref().len() as LenType);
-            tr.put(zip_key, &zip)?;
-            tr.put(&node_key, &node)?;
+            zip.put(t, zip_key, node.compressed())?;
+            t.put(node_key, node.as_ref())?;
+        }
+
+        Ok(())
+    }
+
+    /// 两个相邻的node合并后不超过node的容量
+    fn can_merge(&self, left: &QuickListNode, right: &QuickListNode) -> bool {
+        let config = self.config();
+        left.len_list() + right.len_list() <= config.max_len && left.len_bytes() + right.len_bytes() <= config.max_bytes
+    }
+
+    /// 把left的zip合并到right的zip的左边，删除left的zip
+    fn merge_zip<T: WrapDb>(t: &T, left: &QuickListNode, right: &mut QuickListNode) -> Result<(), RrError> {
+        let left_zip_key = left.values_key().ok_or(RrError::none_error("zip key"))?;
+        let right_zip_key = right.values_key().ok_or(RrError::none_error("zip key"))?.clone();
+        let mut zip = ZipList::get(t, left_zip_key.as_ref())?.ok_or(RrError::none_error("zip"))?;
+        let right_zip = ZipList::get(t, right_zip_key.as_ref())?.ok_or(RrError::none_error("zip"))?;
+        zip.append(&right_zip);
+        t.delete(left_zip_key.as_ref())?;
+        zip.put(t, right_zip_key.as_ref(), right.compressed())?;
+        right.set_len_list(zip.len());
+        right.set_len_bytes(zip.as_ref().len() as LenType);
+        Ok(())
+    }
+
+    /// node的元素减少后，与相邻的node合并，合并时左边的node并入右边的node
+    /// end为node的结束坐标，用于修改索引，为None时不修改索引(由调用者重建)
+    /// 返回合并后保留的node的key，quick的header由调用者保存
+    pub(crate) fn merge_node<T: WrapDb>(&mut self, t: &T, list_key: &[u8], node_key: &MetaKey, end: Option<i64>) -> Result<MetaKey, RrError> {
+        let quick = self;
+        let mut key = node_key.clone();
+        let mut node = QuickListNode::get(t, key.as_ref())?.ok_or(RrError::none_error("node"))?;
+        if let Some(left_key) = node.left().cloned() {
+            let left = QuickListNode::get(t, left_key.as_ref())?.ok_or(RrError::none_error("left node"))?;
+            if quick.can_merge(&left, &node) {
+                if let Some(end) = end {
+                    QuickListIndex::delete(t, list_key, end - node.len_list() as i64)?;
+                }
+                QuickList::merge_zip(t, &left, &mut node)?;
+                t.delete(left_key.as_ref())?;
+                match left.left() {
+                    None => quick.set_left(&Some(&key)),
+                    Some(k) => {
+                        let mut n = QuickListNode::get(t, k.as_ref())?.ok_or(RrError::none_error("left node"))?;
+                        n.set_right(&Some(&key));
+                        t.put(k.as_ref(), n.as_ref())?;
+                    }
+                }
+                node.set_left(&left.left());
+                quick.set_len_node(quick.len_node() - 1);
+                t.put(key.as_ref(), node.as_ref())?;
+            }
         }
+        if let Some(right_key) = node.right().cloned() {
+            let mut right = QuickListNode::get(t, right_key.as_ref())?.ok_or(RrError::none_error("right node"))?;
+            if quick.can_merge(&node, &right) {
+                if let Some(end) = end {
+                    QuickListIndex::delete(t, list_key, end)?;
+                }
+                QuickList::merge_zip(t, &node, &mut right)?;
+                t.delete(key.as_ref())?;
+                match node.left() {
+                    None => quick.set_left(&Some(&right_key)),
+                    Some(k) => {
+                        let mut n = QuickListNode::get(t, k.as_ref())?.ok_or(RrError::none_error("left node"))?;
+                        n.set_right(&Some(&right_key));
+                        t.put(k.as_ref(), n.as_ref())?;
+                    }
+                }
+                right.set_left(&node.left());
+                quick.set_len_node(quick.len_node() - 1);
+                t.put(right_key.as_ref(), right.as_ref())?;
+                key = right_key;
+            }
+        }
+        Ok(key)
+    }
 
+    /// 在一端新增count个node后，离这一端compress_depth个node开始的count个node成为中间的node，压缩它们
+    pub(crate) fn compress_inner<T: WrapDb>(&self, t: &T, front: bool, count: LenType) -> Result<(), RrError> {
+        let depth = self.config().compress_depth;
+        if depth == 0 || count == 0 || self.len_node() < depth * 2 + 1 {
+            return Ok(());
+        }
+        let mut node_key = if front { self.left() } else { self.right() }.ok_or(RrError::none_error("end key"))?.clone();
+        for _ in 0..depth {
+            let node = QuickListNode::get(t, node_key.as_ref())?.ok_or(RrError::none_error("node"))?;
+            node_key = if front { node.right() } else { node.left() }.ok_or(RrError::none_error("next node"))?.clone();
+        }
+        // 离另一端不足compress_depth个node的不压缩
+        for _ in 0..count.min(self.len_node() - depth * 2) {
+            let mut node = QuickListNode::get(t, node_key.as_ref())?.ok_or(RrError::none_error("node"))?;
+            if !node.compressed() {
+                QuickList::set_compressed(t, &node_key, &mut node, true)?;
+            }
+            node_key = match if front { node.right() } else { node.left() } {
+                None => break,
+                Some(k) => k.clone(),
+            };
+        }
         Ok(())
     }
+
+    /// node减少后，保证两端各compress_depth个node没有压缩
+    pub(crate) fn decompress_ends<T: WrapDb>(&self, t: &T) -> Result<(), RrError> {
+        let depth = self.config().compress_depth;
+        if depth == 0 {
+            return Ok(());
+        }
+        for front in [true, false] {
+            let mut node_key = if front { self.left() } else { self.right() }.cloned();
+            for _ in 0..depth {
+                let key = match node_key {
+                    None => break,
+                    Some(k) => k,
+                };
+                let mut node = QuickListNode::get(t, key.as_ref())?.ok_or(RrError::none_error("node"))?;
+                if node.compressed() {
+                    QuickList::set_compressed(t, &key, &mut node, false)?;
+                }
+                node_key = if front { node.right() } else { node.left() }.cloned();
+            }
+        }
+        Ok(())
+    }
+
+    fn set_compressed<T: WrapDb>(t: &T, node_key: &MetaKey, node: &mut QuickListNode, compressed: bool) -> Result<(), RrError> {
+        let zip_key = node.values_key().ok_or(RrError::none_error("zip key"))?;
+        let zip = ZipList::get(t, zip_key.as_ref())?.ok_or(RrError::none_error("zip"))?;
+        zip.put(t, zip_key.as_ref(), compressed)?;
+        node.set_compressed(compressed);
+        t.put(node_key.as_ref(), node.as_ref())
+    }
 }
 
 impl From<[u8; mem::size_of::<QuickList>()]> for QuickList {
@@ -0,0 +1,97 @@
+use crate::{
+    rocksdb_impl::{quick_list::QuickList, quick_list_node::QuickListNode},
+    MetaKey, RrError, WrapDb,
+};
+
+/// quick list的稀疏索引，每个node一条，key为 list_key + ":_l" + node的结束坐标(不包含)，值为node的key
+/// 元素的坐标: 第一个元素为[QuickList::head]，依次加一；lpush时head减一，所以在两端push/pop时最多修改一条索引
+/// 查找坐标所在的node时，seek到第一个结束坐标大于它的索引，是O(log nodes)的
+pub(crate) struct QuickListIndex {}
+
+impl QuickListIndex {
+    const LEN_COORD: usize = 8;
+
+    fn prefix(list_key: &[u8]) -> Vec<u8> {
+        let mut v = Vec::with_capacity(list_key.len() + 3 + QuickListIndex::LEN_COORD);
+        v.extend_from_slice(list_key);
+        v.extend_from_slice(b":_l");
+        v
+    }
+
+    /// 大端并翻转符号位，使坐标的字节序与大小顺序一致
+    fn make_key(list_key: &[u8], end: i64) -> Vec<u8> {
+        let mut v = QuickListIndex::prefix(list_key);
+        v.extend_from_slice(&((end as u64) ^ (1 << 63)).to_be_bytes());
+        v
+    }
+
+    fn read_coord(bytes: &[u8]) -> Result<i64, RrError> {
+        let b: [u8; QuickListIndex::LEN_COORD] = bytes.try_into()?;
+        Ok((u64::from_be_bytes(b) ^ (1 << 63)) as i64)
+    }
+
+    pub(crate) fn put<T: WrapDb>(t: &T, list_key: &[u8], end: i64, node_key: &MetaKey) -> Result<(), RrError> {
+        t.put(&QuickListIndex::make_key(list_key, end), node_key.as_ref())
+    }
+
+    pub(crate) fn delete<T: WrapDb>(t: &T, list_key: &[u8], end: i64) -> Result<(), RrError> {
+        t.delete(&QuickListIndex::make_key(list_key, end))
+    }
+
+    /// node的结束坐标由old_end变为new_end
+    pub(crate) fn move_end<T: WrapDb>(t: &T, list_key: &[u8], old_end: i64, new_end: i64, node_key: &MetaKey) -> Result<(), RrError> {
+        if old_end != new_end {
+            QuickListIndex::delete(t, list_key, old_end)?;
+            QuickListIndex::put(t, list_key, new_end, node_key)?;
+        }
+        Ok(())
+    }
+
+    /// 返回包含坐标coord的node的key及其结束坐标
+    pub(crate) fn find<T: WrapDb>(t: &T, list_key: &[u8], coord: i64) -> Result<(MetaKey, i64), RrError> {
+        let prefix = QuickListIndex::prefix(list_key);
+        let seek = QuickListIndex::make_key(list_key, coord + 1);
+        // prefix_iterator 会越过前缀，需要自己判断
+        for item in t.prefix_iterator(&seek) {
+            let (k, v) = item?;
+            if !k.starts_with(&prefix) {
+                break;
+            }
+            if k.len() == prefix.len() + QuickListIndex::LEN_COORD {
+                let node_key: [u8; 10] = v.as_ref().try_into()?;
+                return Ok((MetaKey::from(node_key), QuickListIndex::read_coord(&k[prefix.len()..])?));
+            }
+        }
+        Err(RrError::data_error("index of quick list"))
+    }
+
+    pub(crate) fn clear<T: WrapDb>(t: &T, list_key: &[u8]) -> Result<(), RrError> {
+        let prefix = QuickListIndex::prefix(list_key);
+        let keys: Vec<Box<[u8]>> = t
+            .prefix_iterator(&prefix)
+            .map(|item| item.map(|(k, _)| k).map_err(RrError::from))
+            .take_while(|item| match item {
+                Ok(k) => k.starts_with(&prefix),
+                Err(_) => true,
+            })
+            .collect::<Result<_, _>>()?;
+        for k in keys.iter().filter(|k| k.len() == prefix.len() + QuickListIndex::LEN_COORD) {
+            t.delete(k)?;
+        }
+        Ok(())
+    }
+
+    /// 按node的链表重新建立索引
+    pub(crate) fn rebuild<T: WrapDb>(t: &T, list_key: &[u8], quick: &QuickList) -> Result<(), RrError> {
+        QuickListIndex::clear(t, list_key)?;
+        let mut end = quick.head();
+        let mut node_key = quick.left().cloned();
+        while let Some(key) = node_key {
+            let node = QuickListNode::get(t, key.as_ref())?.ok_or(RrError::none_error("node"))?;
+            end += node.len_list() as i64;
+            QuickListIndex::put(t, list_key, end, &key)?;
+            node_key = node.right().cloned();
+        }
+        Ok(())
+    }
+}
@@ -1,8 +1,6 @@
 use core::mem;
 
-use rocksdb::{Transaction, TransactionDB};
-
-use crate::{read_len_type, write_len_type, LenType, MetaKey, RrError, BYTES_LEN_TYPE};
+use crate::{read_len_type, write_len_type, LenType, MetaKey, RrError, WrapDb, BYTES_LEN_TYPE};
 
 ///
 /// ```rust
@@ -14,6 +12,7 @@ use crate::{read_len_type, write_len_type, LenType, MetaKey, RrError, BYTES_LEN_
 ///     left: Option<MetaKey>,
 ///     right: Option<MetaKey>,
 ///     value_key: Option<MetaKey>,
+///     compressed: bool,
 /// }
 /// ```
 
@@ -23,33 +22,35 @@ struct _QuickListNode {
     left: Option<MetaKey>,
     right: Option<MetaKey>,
     values_key: Option<MetaKey>,
+    /// zip list是否压缩保存
+    compressed: bool,
 }
 
 pub(crate) struct QuickListNode([u8; mem::size_of::<_QuickListNode>()]);
 
 impl QuickListNode {
-    pub const MAX_LEN: LenType = 124;
-    pub const MAX_BYTES: LenType = QuickListNode::MAX_LEN * 4;
-
     const OFFSET_LEFT: usize = BYTES_LEN_TYPE + BYTES_LEN_TYPE;
     const OFFSET_RIGHT: usize = QuickListNode::OFFSET_LEFT + mem::size_of::<MetaKey>();
     const OFFSET_VALUES_KEY: usize = QuickListNode::OFFSET_RIGHT + mem::size_of::<MetaKey>();
+    const OFFSET_COMPRESSED: usize = QuickListNode::OFFSET_VALUES_KEY + mem::size_of::<MetaKey>();
     pub fn new() -> Self {
         QuickListNode([0; mem::size_of::<_QuickListNode>()])
     }
 
-    pub(crate) fn get(tr: &Transaction<TransactionDB>, key: &[u8]) -> Result<Option<QuickListNode>, RrError> {
-        let v = tr.get(key)?;
+    pub(crate) fn get<T: WrapDb>(t: &T, key: &[u8]) -> Result<Option<QuickListNode>, RrError> {
+        let v = t.get(key)?;
         match v {
             None => Ok(None),
-            Some(v) => {
-                if v.len() == mem::size_of::<QuickListNode>() {
-                    let t: [u8; mem::size_of::<QuickListNode>()] = v.to_vec().as_slice().try_into()?;
-                    Ok(Some(QuickListNode::from(t)))
-                } else {
-                    Err(RrError::message("can not convert vec to QuickListNode, the len is not eq".to_owned()))
-                }
-            }
+            Some(v) => Ok(Some(QuickListNode::decode(&v)?)),
+        }
+    }
+
+    pub(crate) fn decode(v: &[u8]) -> Result<QuickListNode, RrError> {
+        if v.len() == mem::size_of::<QuickListNode>() {
+            let t: [u8; mem::size_of::<QuickListNode>()] = v.try_into()?;
+            Ok(QuickListNode::from(t))
+        } else {
+            Err(RrError::message("can not convert vec to QuickListNode, the len is not eq".to_owned()))
         }
     }
 
@@ -94,6 +95,14 @@ impl QuickListNode {
     pub fn set_values_key(&mut self, meta_key: &Option<&MetaKey>) {
         MetaKey::write(&mut self.0[QuickListNode::OFFSET_VALUES_KEY..], meta_key)
     }
+
+    pub fn compressed(&self) -> bool {
+        self.0[QuickListNode::OFFSET_COMPRESSED] != 0
+    }
+
+    pub fn set_compressed(&mut self, compressed: bool) {
+        self.0[QuickListNode::OFFSET_COMPRESSED] = compressed as u8;
+    }
 }
 
 impl From<[u8; mem::size_of::<_QuickListNode>()]> for QuickListNode {
@@ -1,12 +1,30 @@
-use crate::{BitObject, MaxHeap, MinHeap, ObjectImp};
+use crate::{
+    rocksdb_impl::{heap::FieldHeapImpl, list_waiters::ListWaiters},
+    BPTreeImpl, BitObject, HeapOrder, ListConfig, ListImpl, MaxHeap, MinHeap, ObjectImp, SetImpl, SortedSetImpl, StackImpl,
+};
 
 pub struct RedisRocksdb {
     pub(crate) db: rocksdb::TransactionDB,
+    pub(crate) list_waiters: ListWaiters,
+    pub(crate) list_config: ListConfig,
 }
 
 impl RedisRocksdb {
     pub fn new(db: rocksdb::TransactionDB) -> Self {
-        RedisRocksdb { db }
+        RedisRocksdb {
+            db,
+            list_waiters: ListWaiters::default(),
+            list_config: ListConfig::default(),
+        }
+    }
+
+    /// 之后新建的list使用这个配置，已有的list使用创建时的配置
+    pub fn set_list_config(&mut self, config: ListConfig) {
+        self.list_config = config;
+    }
+
+    pub fn list_config(&self) -> &ListConfig {
+        &self.list_config
     }
 
     pub fn object() -> ObjectImp {
@@ -18,11 +36,50 @@ impl RedisRocksdb {
     }
 
     pub fn max_heap() -> MaxHeap {
-        return MaxHeap {};
+        return MaxHeap::default();
+    }
+
+    /// 新建的heap使用order比较field，参见[HeapOrder]
+    pub fn max_heap_with(order: HeapOrder) -> MaxHeap {
+        return MaxHeap {
+            heap: FieldHeapImpl::new(order),
+        };
     }
 
     pub fn mix_heap() -> MinHeap {
-        return MinHeap {};
+        return MinHeap::default();
+    }
+
+    /// 参见[RedisRocksdb::max_heap_with]
+    pub fn min_heap_with(order: HeapOrder) -> MinHeap {
+        return MinHeap {
+            heap: FieldHeapImpl::new(order),
+        };
+    }
+
+    pub fn bp_tree() -> BPTreeImpl {
+        return BPTreeImpl {};
+    }
+
+    pub fn set() -> SetImpl {
+        return SetImpl {};
+    }
+
+    pub fn sorted_set() -> SortedSetImpl {
+        return SortedSetImpl {};
+    }
+
+    pub fn stack() -> StackImpl {
+        return StackImpl {};
+    }
+
+    pub fn list() -> ListImpl {
+        return ListImpl::default();
+    }
+
+    /// 新建的list使用config中node的容量
+    pub fn list_with(config: ListConfig) -> ListImpl {
+        return ListImpl { config };
     }
 
     pub fn get_db(&self) -> &rocksdb::TransactionDB {
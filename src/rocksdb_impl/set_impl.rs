@@ -0,0 +1,343 @@
+use std::{
+    collections::{hash_map::RandomState, BTreeMap, HashSet},
+    hash::{BuildHasher, Hasher},
+};
+
+use crate::{
+    read_len_type,
+    rocksdb_impl::shared::{make_field_key, make_head_key, make_scope_key},
+    write_len_type, LenType, RrError, Set, WrapDb, BYTES_LEN_TYPE,
+};
+
+/// 对应redis中的set
+/// member存放在 make_field_key(make_scope_key(key), member) 中，没有值；member的个数存放在 make_head_key(make_scope_key(key)) 中
+/// 按前缀遍历member时不会遍历到其它set的数据；因为空的member与head key相同，所以member不能为空
+pub struct SetImpl {}
+
+/// 按字节序读取一个集合的member，用于merge join
+struct MemberCursor<I: Iterator<Item = Result<Vec<u8>, RrError>>> {
+    it: I,
+    current: Option<Vec<u8>>,
+}
+
+impl<I: Iterator<Item = Result<Vec<u8>, RrError>>> MemberCursor<I> {
+    fn new(mut it: I) -> Result<Self, RrError> {
+        let current = it.next().transpose()?;
+        Ok(MemberCursor { it, current })
+    }
+
+    fn advance(&mut self) -> Result<(), RrError> {
+        self.current = self.it.next().transpose()?;
+        Ok(())
+    }
+
+    /// 前进到第一个不小于member的位置，返回此位置是否就是member
+    fn seek(&mut self, member: &[u8]) -> Result<bool, RrError> {
+        while matches!(&self.current, Some(c) if c.as_slice() < member) {
+            self.advance()?;
+        }
+        Ok(self.current.as_deref() == Some(member))
+    }
+}
+
+/// 0到n(不包含n)之间的随机数
+fn random_below(n: LenType) -> LenType {
+    let mut hasher = RandomState::new().build_hasher();
+    hasher.write_u64(n);
+    hasher.finish() % n
+}
+
+impl SetImpl {
+    fn head_key(key: &[u8]) -> Vec<u8> {
+        make_head_key(&make_scope_key(key))
+    }
+
+    fn member_key(key: &[u8], member: &[u8]) -> Vec<u8> {
+        make_field_key(&make_scope_key(key), member)
+    }
+
+    pub(crate) fn get_len<T: WrapDb>(t: &T, key: &[u8]) -> Result<Option<LenType>, RrError> {
+        match t.get(&SetImpl::head_key(key))? {
+            None => Ok(None),
+            Some(v) if v.len() < BYTES_LEN_TYPE => Err(RrError::data_error("set head")),
+            Some(v) => Ok(Some(read_len_type(&v))),
+        }
+    }
+
+    /// len为0时删除head key
+    fn set_len<T: WrapDb>(t: &T, key: &[u8], len: LenType) -> Result<(), RrError> {
+        let head_key = SetImpl::head_key(key);
+        if len == 0 {
+            t.delete(&head_key)
+        } else {
+            let mut v = [0; BYTES_LEN_TYPE];
+            write_len_type(&mut v, len);
+            t.put(&head_key, &v)
+        }
+    }
+
+    /// 按字节序遍历key的所有member
+    pub(crate) fn member_iter<'a, T: WrapDb>(t: &'a T, key: &[u8]) -> impl Iterator<Item = Result<Vec<u8>, RrError>> + 'a {
+        let head_key = SetImpl::head_key(key);
+        let head_len = head_key.len();
+        t.prefix_iterator(&head_key)
+            .map(|item| item.map_err(RrError::from))
+            // prefix_iterator 会越过前缀，需要自己判断
+            .take_while(move |item| match item {
+                Ok((k, _)) => k.starts_with(&head_key),
+                Err(_) => true,
+            })
+            .filter_map(move |item| match item {
+                Ok((k, _)) if k.len() == head_len => None,
+                Ok((k, _)) => Some(Ok(k[head_len..].to_vec())),
+                Err(e) => Some(Err(e)),
+            })
+    }
+
+    /// 随机选出count个下标(在0到len之间)，distinct为true时不重复
+    fn random_indexes(len: LenType, count: LenType, distinct: bool) -> Vec<LenType> {
+        if !distinct {
+            return (0..count).map(|_| random_below(len)).collect();
+        }
+        if count >= len {
+            return (0..len).collect();
+        }
+        // Floyd's algorithm，不需要生成0到len的全部下标
+        let mut selected = HashSet::with_capacity(count as usize);
+        let mut re = Vec::with_capacity(count as usize);
+        for j in len - count..len {
+            let i = random_below(j + 1);
+            let i = if selected.contains(&i) { j } else { i };
+            selected.insert(i);
+            re.push(i);
+        }
+        re
+    }
+
+    /// 返回下标为indexes的member，顺序与indexes相同
+    fn members_at<T: WrapDb>(t: &T, key: &[u8], indexes: &[LenType]) -> Result<Vec<Vec<u8>>, RrError> {
+        let mut wanted: BTreeMap<LenType, Option<Vec<u8>>> = indexes.iter().map(|i| (*i, None)).collect();
+        let last = match wanted.keys().next_back() {
+            None => return Ok(Vec::new()),
+            Some(l) => *l,
+        };
+        for (i, member) in SetImpl::member_iter(t, key).enumerate() {
+            let i = i as LenType;
+            if let Some(v) = wanted.get_mut(&i) {
+                *v = Some(member?);
+            }
+            if i >= last {
+                break;
+            }
+        }
+        indexes
+            .iter()
+            .map(|i| wanted.get(i).cloned().flatten().ok_or_else(|| RrError::data_error("set len")))
+            .collect()
+    }
+
+    fn check_keys(keys: &[&[u8]]) -> Result<(), RrError> {
+        if keys.is_empty() {
+            return Err(RrError::message("at least 1 input key is needed".to_owned()));
+        }
+        Ok(())
+    }
+
+    /// 用members替换dest，返回member个数
+    fn store<T: WrapDb>(&self, t: &T, dest: &[u8], members: Vec<Vec<u8>>) -> Result<LenType, RrError> {
+        self.del_key(t, dest)?;
+        let members: Vec<&[u8]> = members.iter().map(|m| m.as_slice()).collect();
+        self.add(t, dest, &members)
+    }
+
+    fn check_member(member: &[u8]) -> Result<(), RrError> {
+        if member.is_empty() {
+            return Err(RrError::message("member of set can not be empty".to_owned()));
+        }
+        Ok(())
+    }
+}
+
+impl<T: WrapDb> Set<T> for SetImpl {
+    fn add(&self, t: &T, key: &[u8], members: &[&[u8]]) -> Result<LenType, RrError> {
+        for member in members {
+            SetImpl::check_member(member)?;
+        }
+        let mut added = 0;
+        for member in members {
+            let field_key = SetImpl::member_key(key, member);
+            if t.get(&field_key)?.is_none() {
+                t.put(&field_key, &[])?;
+                added += 1;
+            }
+        }
+        if added > 0 {
+            let len = SetImpl::get_len(t, key)?.unwrap_or_default();
+            SetImpl::set_len(t, key, len + added)?;
+        }
+        Ok(added)
+    }
+
+    fn rem(&self, t: &T, key: &[u8], members: &[&[u8]]) -> Result<LenType, RrError> {
+        let mut removed = 0;
+        for member in members {
+            if member.is_empty() {
+                continue;
+            }
+            let field_key = SetImpl::member_key(key, member);
+            if t.get(&field_key)?.is_some() {
+                t.delete(&field_key)?;
+                removed += 1;
+            }
+        }
+        if removed > 0 {
+            let len = SetImpl::get_len(t, key)?.unwrap_or_default();
+            SetImpl::set_len(t, key, len.saturating_sub(removed))?;
+        }
+        Ok(removed)
+    }
+
+    fn is_member(&self, t: &T, key: &[u8], member: &[u8]) -> Result<bool, RrError> {
+        if member.is_empty() {
+            return Ok(false);
+        }
+        Ok(t.get(&SetImpl::member_key(key, member))?.is_some())
+    }
+
+    fn members(&self, t: &T, key: &[u8]) -> Result<Vec<Vec<u8>>, RrError> {
+        SetImpl::member_iter(t, key).collect()
+    }
+
+    fn len(&self, t: &T, key: &[u8]) -> Result<Option<LenType>, RrError> {
+        SetImpl::get_len(t, key)
+    }
+
+    fn pop(&self, t: &T, key: &[u8], count: LenType) -> Result<Vec<Vec<u8>>, RrError> {
+        let len = SetImpl::get_len(t, key)?.unwrap_or_default();
+        if len == 0 || count == 0 {
+            return Ok(Vec::new());
+        }
+        let members = SetImpl::members_at(t, key, &SetImpl::random_indexes(len, count, true))?;
+        for member in &members {
+            t.delete(&SetImpl::member_key(key, member))?;
+        }
+        SetImpl::set_len(t, key, len - members.len() as LenType)?;
+        Ok(members)
+    }
+
+    fn rand_member(&self, t: &T, key: &[u8], count: i64) -> Result<Vec<Vec<u8>>, RrError> {
+        let len = SetImpl::get_len(t, key)?.unwrap_or_default();
+        if len == 0 || count == 0 {
+            return Ok(Vec::new());
+        }
+        let indexes = SetImpl::random_indexes(len, count.unsigned_abs(), count > 0);
+        SetImpl::members_at(t, key, &indexes)
+    }
+
+    fn move_member(&self, t: &T, src: &[u8], dst: &[u8], member: &[u8]) -> Result<bool, RrError> {
+        if !self.is_member(t, src, member)? {
+            return Ok(false);
+        }
+        if src != dst {
+            self.rem(t, src, &[member])?;
+            self.add(t, dst, &[member])?;
+        }
+        Ok(true)
+    }
+
+    fn inter(&self, t: &T, keys: &[&[u8]]) -> Result<Vec<Vec<u8>>, RrError> {
+        SetImpl::check_keys(keys)?;
+        let mut sized = Vec::with_capacity(keys.len());
+        for key in keys {
+            match SetImpl::get_len(t, key)? {
+                None => return Ok(Vec::new()),
+                Some(len) => sized.push((len, *key)),
+            }
+        }
+        // 遍历最小的集合，在其它集合中从小到大查找，越早排除越好
+        sized.sort_by_key(|(len, _)| *len);
+        let mut re = Vec::new();
+        for member in SetImpl::member_iter(t, sized[0].1) {
+            let member = member?;
+            let mut all = true;
+            for (_, key) in &sized[1..] {
+                if t.get(&SetImpl::member_key(key, &member))?.is_none() {
+                    all = false;
+                    break;
+                }
+            }
+            if all {
+                re.push(member);
+            }
+        }
+        Ok(re)
+    }
+
+    fn inter_store(&self, t: &T, dest: &[u8], keys: &[&[u8]]) -> Result<LenType, RrError> {
+        let members = self.inter(t, keys)?;
+        self.store(t, dest, members)
+    }
+
+    fn union(&self, t: &T, keys: &[&[u8]]) -> Result<Vec<Vec<u8>>, RrError> {
+        SetImpl::check_keys(keys)?;
+        let mut cursors = keys
+            .iter()
+            .map(|key| MemberCursor::new(SetImpl::member_iter(t, key)))
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut re = Vec::new();
+        loop {
+            let min = match cursors.iter().filter_map(|c| c.current.as_ref()).min() {
+                None => break,
+                Some(m) => m.clone(),
+            };
+            for c in cursors.iter_mut() {
+                if c.current.as_ref() == Some(&min) {
+                    c.advance()?;
+                }
+            }
+            re.push(min);
+        }
+        Ok(re)
+    }
+
+    fn union_store(&self, t: &T, dest: &[u8], keys: &[&[u8]]) -> Result<LenType, RrError> {
+        let members = self.union(t, keys)?;
+        self.store(t, dest, members)
+    }
+
+    fn diff(&self, t: &T, keys: &[&[u8]]) -> Result<Vec<Vec<u8>>, RrError> {
+        SetImpl::check_keys(keys)?;
+        let mut others = keys[1..]
+            .iter()
+            .map(|key| MemberCursor::new(SetImpl::member_iter(t, key)))
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut re = Vec::new();
+        for member in SetImpl::member_iter(t, keys[0]) {
+            let member = member?;
+            let mut found = false;
+            for c in others.iter_mut() {
+                if c.seek(&member)? {
+                    found = true;
+                    break;
+                }
+            }
+            if !found {
+                re.push(member);
+            }
+        }
+        Ok(re)
+    }
+
+    fn diff_store(&self, t: &T, dest: &[u8], keys: &[&[u8]]) -> Result<LenType, RrError> {
+        let members = self.diff(t, keys)?;
+        self.store(t, dest, members)
+    }
+
+    fn del_key(&self, t: &T, key: &[u8]) -> Result<(), RrError> {
+        let members: Vec<Vec<u8>> = SetImpl::member_iter(t, key).collect::<Result<_, _>>()?;
+        for member in &members {
+            t.delete(&SetImpl::member_key(key, member))?;
+        }
+        t.delete(&SetImpl::head_key(key))
+    }
+}
@@ -1,5 +1,7 @@
 use std::ptr;
 
+use crate::{write_len_type, LenType, BYTES_LEN_TYPE};
+
 pub(crate) fn make_field_key(key: &[u8], field: &[u8]) -> Vec<u8> {
     let mut new_key = Vec::with_capacity(key.len() + field.len() + 3);
     unsafe {
@@ -25,3 +27,12 @@ pub(crate) fn make_head_key(key: &[u8]) -> Vec<u8> {
 pub(crate) fn get_field_from_key<'a>(key: &[u8], field_key: &'a [u8]) -> &'a [u8] {
     &field_key[key.len() + 3..]
 }
+
+/// 在key的前面加上key的长度，不同的key生成的结果互相不是前缀
+/// 按前缀遍历field的类型使用它作为key，否则遍历 "a" 的field时，会遍历到 "a:__b" 的数据
+pub(crate) fn make_scope_key(key: &[u8]) -> Vec<u8> {
+    let mut scope_key = vec![0; BYTES_LEN_TYPE];
+    write_len_type(&mut scope_key, key.len() as LenType);
+    scope_key.extend_from_slice(key);
+    scope_key
+}
@@ -0,0 +1,694 @@
+use std::{collections::BTreeMap, mem::size_of, ops::Bound};
+
+use crate::{
+    rocksdb_impl::{
+        bptree::BTree,
+        shared::{get_field_from_key, make_field_key, make_head_key, make_scope_key},
+    },
+    Aggregate, LenType, RrError, SortedSet, WrapDb, ZAddOptions,
+};
+
+/// 对应redis中的sorted set(zset)
+/// member -> score: 存放在 make_field_key(make_scope_key(key), member) 中(参见[make_member_key])，值为编码后的score
+/// score -> member: 存放在b+ tree中(tree的key为[make_score_key])，field为 编码后的score + member，没有值
+/// score编码为8个字节的大端序，字节序与score的大小一致，所以b+ tree中field的顺序就是 (score, member) 的顺序
+/// rank、第n个、count都是 O(log(N))
+pub struct SortedSetImpl {}
+
+/// 编码后score的字节数
+const LEN_SCORE: usize = size_of::<u64>();
+
+/// member的key，前面是key的长度，所以按前缀遍历member时不会遍历到其它sorted set的数据
+fn make_member_key(key: &[u8], member: &[u8]) -> Vec<u8> {
+    make_field_key(&make_scope_key(key), member)
+}
+
+/// score -> member 的b+ tree的key: make_scope_key(key) + ":_z"
+/// 与member的key(make_scope_key(key) + ":__" + member)在第三个分隔字符上不同，其它key的make_scope_key(key)也不同，所以不会重复
+fn make_score_key(key: &[u8]) -> Vec<u8> {
+    let mut score_key = make_scope_key(key);
+    score_key.extend_from_slice(b":_z");
+    score_key
+}
+
+/// 把f64编码为u64，编码后的大小顺序与score一致，-0.0与0.0编码相同
+fn score_to_u64(score: f64) -> u64 {
+    let bits = (score + 0.0).to_bits();
+    if bits >> 63 == 1 {
+        !bits
+    } else {
+        bits | (1 << 63)
+    }
+}
+
+fn u64_to_score(v: u64) -> f64 {
+    if v >> 63 == 1 {
+        f64::from_bits(v & !(1 << 63))
+    } else {
+        f64::from_bits(!v)
+    }
+}
+
+fn encode_score(score: f64) -> [u8; LEN_SCORE] {
+    score_to_u64(score).to_be_bytes()
+}
+
+fn decode_score(data: &[u8]) -> Result<f64, RrError> {
+    let bytes: [u8; LEN_SCORE] = data.get(..LEN_SCORE).ok_or_else(|| RrError::data_error("score"))?.try_into()?;
+    Ok(u64_to_score(u64::from_be_bytes(bytes)))
+}
+
+/// b+ tree中的field: 编码后的score + member
+fn make_score_field(score: f64, member: &[u8]) -> Vec<u8> {
+    let mut field = Vec::with_capacity(LEN_SCORE + member.len());
+    field.extend_from_slice(&encode_score(score));
+    field.extend_from_slice(member);
+    field
+}
+
+/// 从b+ tree的field中解析出 (member, score)
+fn parse_score_field(field: Vec<u8>) -> Result<(Vec<u8>, f64), RrError> {
+    let score = decode_score(&field)?;
+    Ok((field[LEN_SCORE..].to_vec(), score))
+}
+
+fn check_score(score: f64) -> Result<(), RrError> {
+    if score.is_nan() {
+        return Err(RrError::message("score is not a number".to_owned()));
+    }
+    Ok(())
+}
+
+fn check_options(options: &ZAddOptions) -> Result<(), RrError> {
+    if options.nx && options.xx {
+        return Err(RrError::message("XX and NX options at the same time are not compatible".to_owned()));
+    }
+    if (options.gt && (options.lt || options.nx)) || (options.lt && options.nx) {
+        return Err(RrError::message("GT, LT, and/or NX options at the same time are not compatible".to_owned()));
+    }
+    Ok(())
+}
+
+/// b+ tree中field的范围 (下限, 上限)
+type FieldRange = (Bound<Vec<u8>>, Bound<Vec<u8>>);
+
+/// 把score的范围转换为b+ tree中field的范围，下限只会是Included或Unbounded，上限只会是Excluded或Unbounded
+/// 范围为空时返回None
+fn score_range(min: Bound<f64>, max: Bound<f64>) -> Result<Option<FieldRange>, RrError> {
+    // 大于score的最小field是 编码后的score + 1
+    let next = |score: f64| score_to_u64(score).checked_add(1).map(|v| v.to_be_bytes().to_vec());
+    let lo = match min {
+        Bound::Unbounded => Bound::Unbounded,
+        Bound::Included(s) => {
+            check_score(s)?;
+            Bound::Included(encode_score(s).to_vec())
+        }
+        Bound::Excluded(s) => {
+            check_score(s)?;
+            match next(s) {
+                None => return Ok(None),
+                Some(f) => Bound::Included(f),
+            }
+        }
+    };
+    let hi = match max {
+        Bound::Unbounded => Bound::Unbounded,
+        Bound::Included(s) => {
+            check_score(s)?;
+            match next(s) {
+                None => Bound::Unbounded,
+                Some(f) => Bound::Excluded(f),
+            }
+        }
+        Bound::Excluded(s) => {
+            check_score(s)?;
+            Bound::Excluded(encode_score(s).to_vec())
+        }
+    };
+    Ok(Some((lo, hi)))
+}
+
+fn as_ref_bound(b: &Bound<Vec<u8>>) -> Bound<&[u8]> {
+    match b {
+        Bound::Unbounded => Bound::Unbounded,
+        Bound::Included(f) => Bound::Included(f.as_slice()),
+        Bound::Excluded(f) => Bound::Excluded(f.as_slice()),
+    }
+}
+
+/// zrangebylex的边界
+#[derive(Clone, Copy)]
+enum LexBound<'a> {
+    /// "-"
+    Min,
+    /// "+"
+    Max,
+    /// "[member"
+    Included(&'a [u8]),
+    /// "(member"
+    Excluded(&'a [u8]),
+}
+
+fn parse_lex_bound(bound: &[u8]) -> Result<LexBound<'_>, RrError> {
+    match bound {
+        b"-" => Ok(LexBound::Min),
+        b"+" => Ok(LexBound::Max),
+        [b'[', member @ ..] => Ok(LexBound::Included(member)),
+        [b'(', member @ ..] => Ok(LexBound::Excluded(member)),
+        _ => Err(RrError::message("min or max not valid string range item".to_owned())),
+    }
+}
+
+/// 集合运算的种类
+#[derive(Clone, Copy)]
+enum SetOp {
+    Union,
+    Inter,
+    Diff,
+}
+
+/// score乘以权重，与redis一样，结果为NaN(如 inf * 0)时为0
+fn weighted(score: f64, weight: f64) -> f64 {
+    let s = score * weight;
+    if s.is_nan() {
+        0.0
+    } else {
+        s
+    }
+}
+
+fn aggregate_score(aggregate: Aggregate, a: f64, b: f64) -> f64 {
+    match aggregate {
+        Aggregate::Sum => {
+            // 与redis一样，inf + -inf 时为0
+            let s = a + b;
+            if s.is_nan() {
+                0.0
+            } else {
+                s
+            }
+        }
+        Aggregate::Min => a.min(b),
+        Aggregate::Max => a.max(b),
+    }
+}
+
+/// 把redis风格的 start, stop(可以为负数)转换为 [start, stop] 的下标，范围为空时返回None
+fn index_range(len: LenType, start: i64, stop: i64) -> Option<(LenType, LenType)> {
+    let len = len as i64;
+    let start = if start < 0 { (len + start).max(0) } else { start };
+    let stop = if stop < 0 { len + stop } else { stop.min(len - 1) };
+    if start > stop || start >= len {
+        None
+    } else {
+        Some((start as LenType, stop as LenType))
+    }
+}
+
+impl SortedSetImpl {
+    /// 设置member的score，old_score为原score(member不存在时为None)
+    fn set_score<T: WrapDb>(tree: &BTree<T>, t: &T, key: &[u8], member: &[u8], old_score: Option<f64>, score: f64) -> Result<(), RrError> {
+        if let Some(old) = old_score {
+            tree.delete(&make_score_field(old, member))?;
+        }
+        tree.insert(&make_score_field(score, member))?;
+        t.put(&make_member_key(key, member), &encode_score(score))?;
+        Ok(())
+    }
+
+    fn get_score<T: WrapDb>(t: &T, key: &[u8], member: &[u8]) -> Result<Option<f64>, RrError> {
+        match t.get(&make_member_key(key, member))? {
+            None => Ok(None),
+            Some(v) => Ok(Some(decode_score(&v)?)),
+        }
+    }
+
+    /// 按options更新或添加一个member，返回 (是否新添加, 是否变化, 新的score)，没有执行时新的score为None
+    fn add_one<T: WrapDb>(
+        tree: &BTree<T>,
+        t: &T,
+        key: &[u8],
+        member: &[u8],
+        options: &ZAddOptions,
+        score: impl FnOnce(Option<f64>) -> f64,
+    ) -> Result<(bool, bool, Option<f64>), RrError> {
+        let old_score = SortedSetImpl::get_score(t, key, member)?;
+        let new_score = score(old_score);
+        if new_score.is_nan() {
+            return Err(RrError::message("resulting score is not a number (NaN)".to_owned()));
+        }
+        match old_score {
+            None => {
+                if options.xx {
+                    return Ok((false, false, None));
+                }
+                SortedSetImpl::set_score(tree, t, key, member, None, new_score)?;
+                Ok((true, true, Some(new_score)))
+            }
+            Some(old) => {
+                if options.nx || (options.gt && new_score <= old) || (options.lt && new_score >= old) {
+                    return Ok((false, false, None));
+                }
+                if encode_score(new_score) == encode_score(old) {
+                    return Ok((false, false, Some(new_score)));
+                }
+                SortedSetImpl::set_score(tree, t, key, member, Some(old), new_score)?;
+                Ok((false, true, Some(new_score)))
+            }
+        }
+    }
+
+    /// 删除b+ tree中的field及member，返回 (member, score)
+    fn del_fields<T: WrapDb>(tree: &BTree<T>, t: &T, key: &[u8], fields: Vec<Vec<u8>>) -> Result<Vec<(Vec<u8>, f64)>, RrError> {
+        let mut re = Vec::with_capacity(fields.len());
+        for field in fields {
+            tree.delete(&field)?;
+            let (member, score) = parse_score_field(field)?;
+            t.delete(&make_member_key(key, &member))?;
+            re.push((member, score));
+        }
+        Ok(re)
+    }
+
+    /// 按member的字节序，遍历min与max之间的member，f的参数为 (member, 编码后的score)，返回遍历的个数
+    /// member存放在 make_member_key(key, member) 中，rocksdb中key的顺序就是member的字节序
+    fn lex_for_each<T: WrapDb>(
+        t: &T,
+        key: &[u8],
+        min: &[u8],
+        max: &[u8],
+        limit: Option<(LenType, LenType)>,
+        mut f: impl FnMut(&[u8], &[u8]) -> Result<(), RrError>,
+    ) -> Result<LenType, RrError> {
+        let (min, max) = (parse_lex_bound(min)?, parse_lex_bound(max)?);
+        let scope_key = make_scope_key(key);
+        let head_key = make_head_key(&scope_key);
+        let start_key = match min {
+            LexBound::Min => head_key.clone(),
+            LexBound::Max => return Ok(0),
+            LexBound::Included(m) | LexBound::Excluded(m) => make_member_key(key, m),
+        };
+        let (mut offset, count) = match limit {
+            None => (0, LenType::MAX),
+            Some((_, 0)) => return Ok(0),
+            Some(l) => l,
+        };
+        let mut visited = 0;
+        for item in t.prefix_iterator(&start_key) {
+            let (k, v) = item?;
+            // prefix_iterator 会越过前缀，需要自己判断
+            if !k.starts_with(&head_key) {
+                break;
+            }
+            let member = get_field_from_key(&scope_key, &k);
+            if let LexBound::Excluded(m) = min {
+                if member == m {
+                    continue;
+                }
+            }
+            let in_range = match max {
+                LexBound::Min => false,
+                LexBound::Max => true,
+                LexBound::Included(m) => member <= m,
+                LexBound::Excluded(m) => member < m,
+            };
+            if !in_range {
+                break;
+            }
+            if offset > 0 {
+                offset -= 1;
+                continue;
+            }
+            f(member, &v)?;
+            visited += 1;
+            if visited >= count {
+                break;
+            }
+        }
+        Ok(visited)
+    }
+
+    /// 按member的字节序，返回min与max之间的 (member, score)，参见[SortedSetImpl::lex_for_each]
+    fn lex_members<T: WrapDb>(t: &T, key: &[u8], min: &[u8], max: &[u8], limit: Option<(LenType, LenType)>) -> Result<Vec<(Vec<u8>, f64)>, RrError> {
+        let mut re = Vec::new();
+        SortedSetImpl::lex_for_each(t, key, min, max, limit, |member, score| {
+            re.push((member.to_vec(), decode_score(score)?));
+            Ok(())
+        })?;
+        Ok(re)
+    }
+
+    fn parse_fields(fields: Vec<Vec<u8>>) -> Result<Vec<(Vec<u8>, f64)>, RrError> {
+        fields.into_iter().map(parse_score_field).collect()
+    }
+
+    /// 集合运算，返回值按 (score, member) 排序
+    fn combine<T: WrapDb>(t: &T, keys: &[&[u8]], weights: Option<&[f64]>, aggregate: Aggregate, op: SetOp) -> Result<Vec<(Vec<u8>, f64)>, RrError> {
+        if keys.is_empty() {
+            return Err(RrError::message("at least 1 input key is needed".to_owned()));
+        }
+        let weights = match weights {
+            None => vec![1.0; keys.len()],
+            Some(w) if w.len() == keys.len() => {
+                for weight in w {
+                    check_score(*weight)?;
+                }
+                w.to_vec()
+            }
+            Some(_) => return Err(RrError::message("the number of weights is not equal to the number of keys".to_owned())),
+        };
+        let mut re = BTreeMap::new();
+        for (member, score) in SortedSetImpl::lex_members(t, keys[0], b"-", b"+", None)? {
+            re.insert(member, weighted(score, weights[0]));
+        }
+        for (key, weight) in keys.iter().zip(weights).skip(1) {
+            match op {
+                SetOp::Union => {
+                    for (member, score) in SortedSetImpl::lex_members(t, key, b"-", b"+", None)? {
+                        let score = weighted(score, weight);
+                        re.entry(member).and_modify(|v| *v = aggregate_score(aggregate, *v, score)).or_insert(score);
+                    }
+                }
+                SetOp::Inter => {
+                    // 只需要查找已有的member，不用读取整个集合
+                    let mut next = BTreeMap::new();
+                    for (member, v) in re {
+                        if let Some(score) = SortedSetImpl::get_score(t, key, &member)? {
+                            next.insert(member, aggregate_score(aggregate, v, weighted(score, weight)));
+                        }
+                    }
+                    re = next;
+                }
+                SetOp::Diff => {
+                    let mut next = BTreeMap::new();
+                    for (member, v) in re {
+                        if SortedSetImpl::get_score(t, key, &member)?.is_none() {
+                            next.insert(member, v);
+                        }
+                    }
+                    re = next;
+                }
+            }
+        }
+        let mut re: Vec<(Vec<u8>, f64)> = re.into_iter().collect();
+        re.sort_by(|a, b| score_to_u64(a.1).cmp(&score_to_u64(b.1)).then_with(|| a.0.cmp(&b.0)));
+        Ok(re)
+    }
+
+    /// 删除key的所有member
+    fn clear<T: WrapDb>(t: &T, key: &[u8]) -> Result<(), RrError> {
+        BTree::new(&make_score_key(key), t).clear(|field| {
+            t.delete(&make_member_key(key, &field[LEN_SCORE..]))?;
+            Ok(())
+        })?;
+        Ok(())
+    }
+
+    /// 用members替换dest，返回member个数
+    fn store<T: WrapDb>(t: &T, dest: &[u8], members: Vec<(Vec<u8>, f64)>) -> Result<LenType, RrError> {
+        SortedSetImpl::clear(t, dest)?;
+        let score_key = make_score_key(dest);
+        let tree = BTree::new(&score_key, t);
+        for (member, score) in &members {
+            SortedSetImpl::set_score(&tree, t, dest, member, None, *score)?;
+        }
+        Ok(members.len() as LenType)
+    }
+}
+
+impl<T: WrapDb> SortedSet<T> for SortedSetImpl {
+    fn add(&self, t: &T, key: &[u8], members: &[(f64, &[u8])], options: ZAddOptions) -> Result<LenType, RrError> {
+        check_options(&options)?;
+        for (score, _) in members {
+            check_score(*score)?;
+        }
+        let score_key = make_score_key(key);
+        let tree = BTree::new(&score_key, t);
+        let mut count = 0;
+        for (score, member) in members {
+            let (added, changed, _) = SortedSetImpl::add_one(&tree, t, key, member, &options, |_| *score)?;
+            if added || (options.ch && changed) {
+                count += 1;
+            }
+        }
+        Ok(count)
+    }
+
+    fn add_incr(&self, t: &T, key: &[u8], increment: f64, member: &[u8], options: ZAddOptions) -> Result<Option<f64>, RrError> {
+        check_options(&options)?;
+        check_score(increment)?;
+        let score_key = make_score_key(key);
+        let tree = BTree::new(&score_key, t);
+        let (_, _, score) = SortedSetImpl::add_one(&tree, t, key, member, &options, |old| old.unwrap_or(0.0) + increment)?;
+        Ok(score)
+    }
+
+    fn incr_by(&self, t: &T, key: &[u8], increment: f64, member: &[u8]) -> Result<f64, RrError> {
+        self.add_incr(t, key, increment, member, ZAddOptions::default())?
+            .ok_or_else(|| RrError::none_error("score"))
+    }
+
+    fn score(&self, t: &T, key: &[u8], member: &[u8]) -> Result<Option<f64>, RrError> {
+        SortedSetImpl::get_score(t, key, member)
+    }
+
+    fn rem(&self, t: &T, key: &[u8], members: &[&[u8]]) -> Result<LenType, RrError> {
+        let score_key = make_score_key(key);
+        let tree = BTree::new(&score_key, t);
+        let mut count = 0;
+        for member in members {
+            if let Some(score) = SortedSetImpl::get_score(t, key, member)? {
+                tree.delete(&make_score_field(score, member))?;
+                t.delete(&make_member_key(key, member))?;
+                count += 1;
+            }
+        }
+        Ok(count)
+    }
+
+    fn rank(&self, t: &T, key: &[u8], member: &[u8]) -> Result<Option<LenType>, RrError> {
+        match SortedSetImpl::get_score(t, key, member)? {
+            None => Ok(None),
+            Some(score) => Ok(BTree::new(&make_score_key(key), t).rank(&make_score_field(score, member))?),
+        }
+    }
+
+    fn rev_rank(&self, t: &T, key: &[u8], member: &[u8]) -> Result<Option<LenType>, RrError> {
+        let rank = match self.rank(t, key, member)? {
+            None => return Ok(None),
+            Some(r) => r,
+        };
+        let len = self.len(t, key)?.unwrap_or_default();
+        Ok(Some(len - 1 - rank))
+    }
+
+    fn range(&self, t: &T, key: &[u8], start: i64, stop: i64) -> Result<Vec<(Vec<u8>, f64)>, RrError> {
+        let score_key = make_score_key(key);
+        let tree = BTree::new(&score_key, t);
+        let (start, stop) = match index_range(tree.len()?.unwrap_or_default(), start, stop) {
+            None => return Ok(Vec::new()),
+            Some(r) => r,
+        };
+        let first = match tree.select(start)? {
+            None => return Ok(Vec::new()),
+            Some(f) => f,
+        };
+        let fields = tree.scan(Bound::Included(&first), Bound::Unbounded, false, (stop - start + 1) as usize)?;
+        SortedSetImpl::parse_fields(fields)
+    }
+
+    fn rev_range(&self, t: &T, key: &[u8], start: i64, stop: i64) -> Result<Vec<(Vec<u8>, f64)>, RrError> {
+        let score_key = make_score_key(key);
+        let tree = BTree::new(&score_key, t);
+        let len = tree.len()?.unwrap_or_default();
+        let (start, stop) = match index_range(len, start, stop) {
+            None => return Ok(Vec::new()),
+            Some(r) => r,
+        };
+        // 逆序的第start个，就是正序的第 len - 1 - start 个
+        let first = match tree.select(len - 1 - start)? {
+            None => return Ok(Vec::new()),
+            Some(f) => f,
+        };
+        let fields = tree.scan(Bound::Included(&first), Bound::Unbounded, true, (stop - start + 1) as usize)?;
+        SortedSetImpl::parse_fields(fields)
+    }
+
+    fn range_by_score(&self, t: &T, key: &[u8], min: Bound<f64>, max: Bound<f64>, limit: Option<(LenType, LenType)>) -> Result<Vec<(Vec<u8>, f64)>, RrError> {
+        let (lo, hi) = match score_range(min, max)? {
+            None => return Ok(Vec::new()),
+            Some(r) => r,
+        };
+        // 与redis的 LIMIT offset 0 一致，返回空
+        if let Some((_, 0)) = limit {
+            return Ok(Vec::new());
+        }
+        let score_key = make_score_key(key);
+        let tree = BTree::new(&score_key, t);
+        // scan的count为0时不限制个数
+        let (offset, count) = limit.unwrap_or((0, 0));
+        let start = if offset > 0 {
+            // 跳过offset个，不需要逐个遍历
+            let less = match &lo {
+                Bound::Included(f) => tree.count(Bound::Unbounded, Bound::Excluded(f))?,
+                _ => 0,
+            };
+            match tree.select(less + offset)? {
+                None => return Ok(Vec::new()),
+                Some(f) => Bound::Included(f),
+            }
+        } else {
+            lo
+        };
+        let fields = tree.scan(as_ref_bound(&start), as_ref_bound(&hi), false, count as usize)?;
+        SortedSetImpl::parse_fields(fields)
+    }
+
+    fn rev_range_by_score(
+        &self,
+        t: &T,
+        key: &[u8],
+        max: Bound<f64>,
+        min: Bound<f64>,
+        limit: Option<(LenType, LenType)>,
+    ) -> Result<Vec<(Vec<u8>, f64)>, RrError> {
+        let (lo, hi) = match score_range(min, max)? {
+            None => return Ok(Vec::new()),
+            Some(r) => r,
+        };
+        // 与redis的 LIMIT offset 0 一致，返回空
+        if let Some((_, 0)) = limit {
+            return Ok(Vec::new());
+        }
+        let score_key = make_score_key(key);
+        let tree = BTree::new(&score_key, t);
+        // scan的count为0时不限制个数
+        let (offset, count) = limit.unwrap_or((0, 0));
+        let start = if offset > 0 {
+            // 范围内最大的field的排名为 小于hi的个数 - 1
+            let less = tree.count(Bound::Unbounded, as_ref_bound(&hi))?;
+            if less <= offset {
+                return Ok(Vec::new());
+            }
+            match tree.select(less - 1 - offset)? {
+                None => return Ok(Vec::new()),
+                Some(f) => Bound::Included(f),
+            }
+        } else {
+            hi
+        };
+        let fields = tree.scan(as_ref_bound(&start), as_ref_bound(&lo), true, count as usize)?;
+        SortedSetImpl::parse_fields(fields)
+    }
+
+    fn count(&self, t: &T, key: &[u8], min: Bound<f64>, max: Bound<f64>) -> Result<LenType, RrError> {
+        match score_range(min, max)? {
+            None => Ok(0),
+            Some((lo, hi)) => Ok(BTree::new(&make_score_key(key), t).count(as_ref_bound(&lo), as_ref_bound(&hi))?),
+        }
+    }
+
+    fn range_by_lex(&self, t: &T, key: &[u8], min: &[u8], max: &[u8], limit: Option<(LenType, LenType)>) -> Result<Vec<Vec<u8>>, RrError> {
+        let members = SortedSetImpl::lex_members(t, key, min, max, limit)?;
+        Ok(members.into_iter().map(|(member, _)| member).collect())
+    }
+
+    fn lex_count(&self, t: &T, key: &[u8], min: &[u8], max: &[u8]) -> Result<LenType, RrError> {
+        SortedSetImpl::lex_for_each(t, key, min, max, None, |_, _| Ok(()))
+    }
+
+    fn rem_range_by_lex(&self, t: &T, key: &[u8], min: &[u8], max: &[u8]) -> Result<LenType, RrError> {
+        let members = SortedSetImpl::lex_members(t, key, min, max, None)?;
+        let score_key = make_score_key(key);
+        let tree = BTree::new(&score_key, t);
+        for (member, score) in &members {
+            tree.delete(&make_score_field(*score, member))?;
+            t.delete(&make_member_key(key, member))?;
+        }
+        Ok(members.len() as LenType)
+    }
+
+    fn pop_min(&self, t: &T, key: &[u8], count: LenType) -> Result<Vec<(Vec<u8>, f64)>, RrError> {
+        if count == 0 {
+            return Ok(Vec::new());
+        }
+        let score_key = make_score_key(key);
+        let tree = BTree::new(&score_key, t);
+        let fields = tree.scan(Bound::Unbounded, Bound::Unbounded, false, count as usize)?;
+        SortedSetImpl::del_fields(&tree, t, key, fields)
+    }
+
+    fn pop_max(&self, t: &T, key: &[u8], count: LenType) -> Result<Vec<(Vec<u8>, f64)>, RrError> {
+        if count == 0 {
+            return Ok(Vec::new());
+        }
+        let score_key = make_score_key(key);
+        let tree = BTree::new(&score_key, t);
+        let fields = tree.scan(Bound::Unbounded, Bound::Unbounded, true, count as usize)?;
+        SortedSetImpl::del_fields(&tree, t, key, fields)
+    }
+
+    fn union(&self, t: &T, keys: &[&[u8]], weights: Option<&[f64]>, aggregate: Aggregate) -> Result<Vec<(Vec<u8>, f64)>, RrError> {
+        SortedSetImpl::combine(t, keys, weights, aggregate, SetOp::Union)
+    }
+
+    fn union_store(&self, t: &T, dest: &[u8], keys: &[&[u8]], weights: Option<&[f64]>, aggregate: Aggregate) -> Result<LenType, RrError> {
+        let members = SortedSetImpl::combine(t, keys, weights, aggregate, SetOp::Union)?;
+        SortedSetImpl::store(t, dest, members)
+    }
+
+    fn inter(&self, t: &T, keys: &[&[u8]], weights: Option<&[f64]>, aggregate: Aggregate) -> Result<Vec<(Vec<u8>, f64)>, RrError> {
+        SortedSetImpl::combine(t, keys, weights, aggregate, SetOp::Inter)
+    }
+
+    fn inter_store(&self, t: &T, dest: &[u8], keys: &[&[u8]], weights: Option<&[f64]>, aggregate: Aggregate) -> Result<LenType, RrError> {
+        let members = SortedSetImpl::combine(t, keys, weights, aggregate, SetOp::Inter)?;
+        SortedSetImpl::store(t, dest, members)
+    }
+
+    fn diff(&self, t: &T, keys: &[&[u8]], weights: Option<&[f64]>, aggregate: Aggregate) -> Result<Vec<(Vec<u8>, f64)>, RrError> {
+        SortedSetImpl::combine(t, keys, weights, aggregate, SetOp::Diff)
+    }
+
+    fn diff_store(&self, t: &T, dest: &[u8], keys: &[&[u8]], weights: Option<&[f64]>, aggregate: Aggregate) -> Result<LenType, RrError> {
+        let members = SortedSetImpl::combine(t, keys, weights, aggregate, SetOp::Diff)?;
+        SortedSetImpl::store(t, dest, members)
+    }
+
+    fn len(&self, t: &T, key: &[u8]) -> Result<Option<LenType>, RrError> {
+        Ok(BTree::new(&make_score_key(key), t).len()?)
+    }
+
+    fn del_key(&self, t: &T, key: &[u8]) -> Result<(), RrError> {
+        SortedSetImpl::clear(t, key)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::rocksdb_impl::sorted_set_impl::{encode_score, score_to_u64, u64_to_score};
+
+    #[test]
+    fn test_score_encode() {
+        let scores = [
+            f64::NEG_INFINITY,
+            f64::MIN,
+            -1.5,
+            -1.0,
+            -f64::MIN_POSITIVE,
+            0.0,
+            f64::MIN_POSITIVE,
+            1.0,
+            1.5,
+            f64::MAX,
+            f64::INFINITY,
+        ];
+        for w in scores.windows(2) {
+            assert!(encode_score(w[0]) < encode_score(w[1]));
+        }
+        for s in scores {
+            assert_eq!(s, u64_to_score(score_to_u64(s)));
+        }
+        assert_eq!(encode_score(0.0), encode_score(-0.0));
+    }
+}
@@ -1,54 +1,134 @@
-use std::{ops::Deref, ptr};
-
-use crate::{read_int, write_int, Bytes, RedisRocksdb, RrError, Stack};
-
-impl Stack for RedisRocksdb {
-    fn index<K: Bytes>(&self, key: &K, _index: i64) -> Result<Vec<u8>, RrError> {
-        let stack = StackHeader::get_stack(&self.db, key.as_ref())?;
-        match stack {
-            None => Ok(vec![]),
-            Some(v) => Ok(v.size.to_vec()),
+use crate::{read_int, write_int, Bytes, RrError, Stack, WrapDb};
+
+/// 栈的元素个数存放在key中，第index个元素存放在 make_key_index(key, index) 中
+/// 按下标读写元素都是O(1)
+pub struct StackImpl {}
+
+impl StackImpl {
+    /// 把负数的index转换为正数，不在范围内时返回None
+    fn real_index(size: i64, index: i64) -> Option<i64> {
+        let index = if index < 0 { size + index } else { index };
+        if index < 0 || index >= size {
+            None
+        } else {
+            Some(index)
         }
     }
 
-    fn len<K: Bytes>(&self, _key: &K) -> Result<i64, RrError> {
-        todo!()
-    }
-
-    fn push<K: Bytes, V: Bytes>(&mut self, _key: &K, _value: &V) -> Result<i64, RrError> {
-        todo!()
+    fn push_values<T: WrapDb>(t: &T, key: &[u8], mut stack: StackHeader, values: &[&[u8]]) -> Result<i64, RrError> {
+        for v in values {
+            t.put(&StackHeader::make_key_index(key, stack.size_i64()), v)?;
+            stack.add_1();
+        }
+        t.put(key, stack.as_ref())?;
+        Ok(stack.size_i64())
     }
+}
 
-    fn pushs<K: Bytes, V: Bytes>(&mut self, _key: &K, _values: &[&V]) -> Result<i64, RrError> {
-        todo!()
+impl<T: WrapDb> Stack<T> for StackImpl {
+    fn index(&self, t: &T, key: &[u8], index: i64) -> Result<Option<Vec<u8>>, RrError> {
+        let stack = match StackHeader::get_stack(t, key)? {
+            None => return Ok(None),
+            Some(s) => s,
+        };
+        match StackImpl::real_index(stack.size_i64(), index) {
+            None => Ok(None),
+            Some(i) => stack.get_index(t, key, i),
+        }
     }
 
-    fn push_exists<K: Bytes, V: Bytes>(&mut self, _key: &K, _value: &V) -> Result<i64, RrError> {
-        todo!()
+    fn len(&self, t: &T, key: &[u8]) -> Result<i64, RrError> {
+        match StackHeader::get_stack(t, key)? {
+            None => Ok(-1),
+            Some(s) => Ok(s.size_i64()),
+        }
     }
 
-    fn range<K: Bytes>(&self, _key: &K, _start: i64, _stop: i64) -> Result<Vec<Vec<u8>>, RrError> {
-        todo!()
+    fn push(&self, t: &T, key: &[u8], value: &[u8]) -> Result<i64, RrError> {
+        self.pushs(t, key, &[value])
     }
 
-    fn set<K: Bytes, V: Bytes>(&mut self, _key: &K, _index: i64, _value: &V) -> Result<Vec<u8>, RrError> {
-        todo!()
+    fn pushs(&self, t: &T, key: &[u8], values: &[&[u8]]) -> Result<i64, RrError> {
+        let stack = StackHeader::get_stack(t, key)?.unwrap_or_else(|| StackHeader::from(0));
+        StackImpl::push_values(t, key, stack, values)
     }
 
-    fn pop<K: Bytes>(&self, _key: &K) -> Result<Vec<u8>, RrError> {
-        todo!()
+    fn push_exists(&self, t: &T, key: &[u8], value: &[u8]) -> Result<i64, RrError> {
+        match StackHeader::get_stack(t, key)? {
+            None => Ok(-1),
+            Some(stack) => StackImpl::push_values(t, key, stack, &[value]),
+        }
     }
 
-    fn pops<K: Bytes>(&self, _key: &K, _amount: u64) -> Result<Vec<Vec<u8>>, RrError> {
-        todo!()
+    fn range(&self, t: &T, key: &[u8], start: i64, stop: i64) -> Result<Vec<Vec<u8>>, RrError> {
+        let stack = match StackHeader::get_stack(t, key)? {
+            None => return Ok(Vec::new()),
+            Some(s) => s,
+        };
+        let size = stack.size_i64();
+        let start = if start < 0 { (size + start).max(0) } else { start };
+        let stop = if stop < 0 { size + stop } else { stop.min(size - 1) };
+        let mut re = Vec::new();
+        for i in start..=stop {
+            if let Some(v) = stack.get_index(t, key, i)? {
+                re.push(v);
+            }
+        }
+        Ok(re)
+    }
+
+    fn set(&self, t: &T, key: &[u8], index: i64, value: &[u8]) -> Result<Vec<u8>, RrError> {
+        let stack = StackHeader::get_stack(t, key)?.ok_or_else(|| RrError::not_find("stack"))?;
+        let i = StackImpl::real_index(stack.size_i64(), index).ok_or_else(|| RrError::message(format!("index out of range: {}", index)))?;
+        let old = stack.get_index(t, key, i)?.ok_or_else(|| RrError::data_error("stack element"))?;
+        t.put(&StackHeader::make_key_index(key, i), value)?;
+        Ok(old)
+    }
+
+    fn pop(&self, t: &T, key: &[u8]) -> Result<Option<Vec<u8>>, RrError> {
+        Ok(self.pops(t, key, 1)?.pop())
+    }
+
+    fn pops(&self, t: &T, key: &[u8], amount: u64) -> Result<Vec<Vec<u8>>, RrError> {
+        let mut stack = match StackHeader::get_stack(t, key)? {
+            None => return Ok(Vec::new()),
+            Some(s) => s,
+        };
+        // 先在u64中比较，amount大于i64::MAX时转换为i64会变成负数
+        let amount = amount.min(stack.size_i64().max(0) as u64) as i64;
+        let mut re = Vec::with_capacity(amount as usize);
+        for _ in 0..amount {
+            let index_key = StackHeader::make_key_index(key, stack.size_i64() - 1);
+            re.push(t.get(&index_key)?.ok_or_else(|| RrError::data_error("stack element"))?);
+            t.delete(&index_key)?;
+            stack.add(-1);
+        }
+        if stack.size_i64() == 0 {
+            t.delete(key)?;
+        } else {
+            t.put(key, stack.as_ref())?;
+        }
+        Ok(re)
     }
 
-    fn poplpush<K: Bytes, V: Bytes>(&mut self, _key: &K, _dstkey: &K) -> Result<V, RrError> {
-        todo!()
+    fn poplpush(&self, t: &T, key: &[u8], dstkey: &[u8]) -> Result<Option<Vec<u8>>, RrError> {
+        let v = self.pop(t, key)?;
+        if let Some(v) = &v {
+            self.push(t, dstkey, v)?;
+        }
+        Ok(v)
     }
 
-    fn clear<K: Bytes>(&mut self, _key: &K) -> Result<i64, RrError> {
-        todo!()
+    fn clear(&self, t: &T, key: &[u8]) -> Result<i64, RrError> {
+        let stack = match StackHeader::get_stack(t, key)? {
+            None => return Ok(-1),
+            Some(s) => s,
+        };
+        for i in 0..stack.size_i64() {
+            t.delete(&StackHeader::make_key_index(key, i))?;
+        }
+        t.delete(key)?;
+        Ok(stack.size_i64())
     }
 }
 
@@ -77,41 +157,28 @@ impl StackHeader {
 
     fn make_key_index(key: &[u8], index: i64) -> Vec<u8> {
         let mut v = Vec::with_capacity(key.len() + 9);
-        unsafe {
-            v.set_len(v.capacity());
-            ptr::copy(key.as_ptr(), v.as_mut_ptr(), key.len());
-        }
-        let vv = v.as_mut_slice();
-        vv[key.len()] = b'_';
-        write_int(&mut vv[key.len() + 1..], index);
+        v.extend_from_slice(key);
+        v.push(b'_');
+        v.extend_from_slice(&[0; 8]);
+        write_int(&mut v[key.len() + 1..], index);
         v
     }
 
-    fn get_stack(db: &rocksdb::TransactionDB, key: &[u8]) -> Result<Option<Self>, RrError> {
-        let v = db.get(key)?;
+    fn get_stack<T: WrapDb>(t: &T, key: &[u8]) -> Result<Option<Self>, RrError> {
+        let v = t.get(key)?;
         match v {
             None => Ok(None),
             Some(v) => {
                 if v.len() < 8 {
                     Err(RrError::data_error("key < 8"))
                 } else {
-                    Ok(Some(StackHeader::from(v.deref())))
+                    Ok(Some(StackHeader::from(&v[..8])))
                 }
             }
         }
     }
-    fn get_index(&self, db: &rocksdb::TransactionDB, key: &[u8], _index: i64) -> Result<Vec<u8>, RrError> {
-        let v = db.get(key)?;
-        match v {
-            None => Ok(vec![]),
-            Some(v) => {
-                if v.len() < 8 {
-                    Err(RrError::data_error("key < 8"))
-                } else {
-                    Ok(v.to_vec())
-                }
-            }
-        }
+    fn get_index<T: WrapDb>(&self, t: &T, key: &[u8], index: i64) -> Result<Option<Vec<u8>>, RrError> {
+        t.get(&StackHeader::make_key_index(key, index))
     }
 }
 
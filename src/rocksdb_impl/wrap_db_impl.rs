@@ -13,6 +13,18 @@ impl<'a> WrapDb for WrapTransactionDB<'a> {
         Ok(self.db.get(key)?)
     }
 
+    fn get_for_update(&self, key: &[u8]) -> Result<Option<Vec<u8>>, RrError> {
+        self.get(key)
+    }
+
+    fn in_transaction(&self) -> bool {
+        false
+    }
+
+    fn multi_get<K: AsRef<[u8]>>(&self, keys: &[K]) -> Result<Vec<Option<Vec<u8>>>, RrError> {
+        self.db.multi_get(keys).into_iter().map(|v| v.map_err(RrError::from)).collect()
+    }
+
     fn put(&self, key: &[u8], value: &[u8]) -> Result<(), RrError> {
         Ok(self.db.put(key, value)?)
     }
@@ -46,6 +58,18 @@ impl<'a> WrapDb for WrapTransaction<'a> {
         Ok(self.db.get(key)?)
     }
 
+    fn get_for_update(&self, key: &[u8]) -> Result<Option<Vec<u8>>, RrError> {
+        Ok(self.db.get_for_update(key, true)?)
+    }
+
+    fn in_transaction(&self) -> bool {
+        true
+    }
+
+    fn multi_get<K: AsRef<[u8]>>(&self, keys: &[K]) -> Result<Vec<Option<Vec<u8>>>, RrError> {
+        self.db.multi_get(keys).into_iter().map(|v| v.map_err(RrError::from)).collect()
+    }
+
     fn put(&self, key: &[u8], value: &[u8]) -> Result<(), RrError> {
         Ok(self.db.put(key, value)?)
     }
@@ -78,6 +102,18 @@ impl<'a> WrapDb for WrapRocksDb<'a> {
         Ok(self.db.get(key)?)
     }
 
+    fn get_for_update(&self, key: &[u8]) -> Result<Option<Vec<u8>>, RrError> {
+        self.get(key)
+    }
+
+    fn in_transaction(&self) -> bool {
+        false
+    }
+
+    fn multi_get<K: AsRef<[u8]>>(&self, keys: &[K]) -> Result<Vec<Option<Vec<u8>>>, RrError> {
+        self.db.multi_get(keys).into_iter().map(|v| v.map_err(RrError::from)).collect()
+    }
+
     fn put(&self, key: &[u8], value: &[u8]) -> Result<(), RrError> {
         Ok(self.db.put(key, value)?)
     }
@@ -1,8 +1,9 @@
-use core::ptr;
+use core::{
+    ffi::{c_char, c_int},
+    ptr,
+};
 
-use rocksdb::{Transaction, TransactionDB};
-
-use crate::{read_int, write_int, EndianScalar, LenType, RrError, BYTES_LEN_TYPE};
+use crate::{read_int, write_int, EndianScalar, LenType, RrError, WrapDb, BYTES_LEN_TYPE};
 
 ///
 /// ```rust
@@ -85,7 +86,7 @@ impl<'a> ZipListNode<'a> {
         self.1
     }
 
-    fn value(&'a self) -> &'a [u8] {
+    fn value(&self) -> &'a [u8] {
         &self.0[self.1 + ZipListNode::SIZE_NODE_TYPE..self.1 + self.2 - ZipListNode::SIZE_NODE_TYPE]
     }
 }
@@ -117,15 +118,80 @@ impl AsRef<[u8]> for ZipList {
 impl ZipList {
     const LEN_INIT: usize = core::mem::size_of::<LenType>();
     const OFFSET_VALUE: usize = BYTES_LEN_TYPE;
+    /// 压缩后的数据格式:
+    /// [len | COMPRESSED: LenType][原始zip list的字节数: u32 小端][lz4 block]
+    /// lz4 block是lz4压缩的整个zip list，使用rocksdb已经链接的lz4库(lz4-sys)
+    const COMPRESSED: LenType = 1 << (LenType::BITS - 1);
     pub fn new() -> Self {
         ZipList(Vec::from([0; ZipList::LEN_INIT]))
     }
 
-    pub(crate) fn get(tr: &Transaction<TransactionDB>, key: &[u8]) -> Result<Option<ZipList>, RrError> {
-        let v = tr.get(key)?;
+    /// 压缩的数据在这里解压
+    pub(crate) fn get<T: WrapDb>(t: &T, key: &[u8]) -> Result<Option<ZipList>, RrError> {
+        let v = t.get(key)?;
         match v {
             None => Ok(None),
-            Some(v) => Ok(Some(ZipList::from(v.to_vec()))),
+            Some(v) => Ok(Some(ZipList::decode(v)?)),
+        }
+    }
+
+    /// compress为true时压缩后保存
+    pub(crate) fn put<T: WrapDb>(&self, t: &T, key: &[u8], compress: bool) -> Result<(), RrError> {
+        if compress {
+            t.put(key, &self.compress())
+        } else {
+            t.put(key, self.as_ref())
+        }
+    }
+
+    fn compress(&self) -> Vec<u8> {
+        let mut v = vec![0; ZipList::LEN_INIT];
+        write_int(v.as_mut_slice(), self.len() | ZipList::COMPRESSED);
+        v.extend_from_slice(&(self.0.len() as u32).to_le_bytes());
+        let head = v.len();
+        let bound = unsafe { lz4_sys::LZ4_compressBound(self.0.len() as c_int) };
+        v.resize(head + bound as usize, 0);
+        let size = unsafe {
+            lz4_sys::LZ4_compress_default(
+                self.0.as_ptr() as *const c_char,
+                v[head..].as_mut_ptr() as *mut c_char,
+                self.0.len() as c_int,
+                bound,
+            )
+        };
+        v.truncate(head + size as usize);
+        v
+    }
+
+    /// 解压[ZipList::compress]的数据，不包含开头的len
+    fn decompress(bytes: &[u8]) -> Result<Vec<u8>, RrError> {
+        const RAW_LEN: usize = core::mem::size_of::<u32>();
+        if bytes.len() < RAW_LEN {
+            return Err(RrError::message("compressed zip list is too short".to_owned()));
+        }
+        let raw_len = u32::from_le_bytes(bytes[..RAW_LEN].try_into().unwrap()) as usize;
+        let block = &bytes[RAW_LEN..];
+        let mut raw = vec![0u8; raw_len];
+        let size = unsafe {
+            lz4_sys::LZ4_decompress_safe(
+                block.as_ptr() as *const c_char,
+                raw.as_mut_ptr() as *mut c_char,
+                block.len() as c_int,
+                raw_len as c_int,
+            )
+        };
+        if size < 0 || size as usize != raw_len {
+            return Err(RrError::message("failed to decompress zip list".to_owned()));
+        }
+        Ok(raw)
+    }
+
+    pub(crate) fn decode(bytes: Vec<u8>) -> Result<ZipList, RrError> {
+        if bytes.len() > ZipList::LEN_INIT && read_int::<LenType>(&bytes) & ZipList::COMPRESSED != 0 {
+            let raw = ZipList::decompress(&bytes[ZipList::LEN_INIT..])?;
+            Ok(ZipList::from(raw))
+        } else {
+            Ok(ZipList::from(bytes))
         }
     }
 
@@ -137,6 +203,43 @@ impl ZipList {
         write_int(self.0.as_mut_slice(), len)
     }
 
+    /// 第一个值的offset，与[ZipList::end_offset]一起作为[ZipList::value_after]与[ZipList::value_before]的游标
+    pub(crate) fn begin_offset(&self) -> usize {
+        ZipList::OFFSET_VALUE
+    }
+
+    /// 最后一个值之后的offset
+    pub(crate) fn end_offset(&self) -> usize {
+        self.0.len()
+    }
+
+    /// 返回从offset开始的值，及下一个值的offset
+    pub(crate) fn value_after(&self, offset: usize) -> Option<(&[u8], usize)> {
+        if offset < ZipList::OFFSET_VALUE || offset + ZipListNode::SIZE_NODE_TYPE * 2 > self.0.len() {
+            return None;
+        }
+        let next = offset + ZipListNode::read_bytes_of_value(&self.0[offset..]) + ZipListNode::SIZE_NODE_TYPE * 2;
+        if next > self.0.len() {
+            None
+        } else {
+            Some((ZipListNode::read_value(&self.0, offset), next))
+        }
+    }
+
+    /// 返回在offset之前的值，及这个值的offset
+    pub(crate) fn value_before(&self, offset: usize) -> Option<(&[u8], usize)> {
+        if offset > self.0.len() || offset < ZipList::OFFSET_VALUE + ZipListNode::SIZE_NODE_TYPE * 2 {
+            return None;
+        }
+        let bytes_node = ZipListNode::read_bytes_of_value(&self.0[offset - ZipListNode::SIZE_NODE_TYPE..]) + ZipListNode::SIZE_NODE_TYPE * 2;
+        if offset < ZipList::OFFSET_VALUE + bytes_node {
+            None
+        } else {
+            let start = offset - bytes_node;
+            Some((ZipListNode::read_value(&self.0, start), start))
+        }
+    }
+
     fn get_offset_index(&self, index: usize) -> Option<usize> {
         if index == 0 {
             Some(ZipList::OFFSET_VALUE)
@@ -302,10 +405,11 @@ impl ZipList {
                 }
             } else if diff < 0 {
                 unsafe {
+                    //只移动当前node后面的数据，否则会越过vec的末尾
                     ptr::copy(
                         p.offset(old_bytes_node as isize),
                         p.offset(old_bytes_node as isize - diff.abs()),
-                        self.0.len() - offset,
+                        self.0.len() - offset - old_bytes_node,
                     );
                 }
                 self.0.truncate(self.0.len() - diff.abs() as usize);
@@ -413,6 +517,42 @@ impl ZipList {
         self.0.truncate(self.0.len() - (end - start) - 1);
     }
 
+    /// 返回与value相等的值的下标，rev为true时从后向前排列
+    pub fn positions(&self, value: &[u8], rev: bool) -> Vec<usize> {
+        let mut re: Vec<usize> = ZipListIter::new(self)
+            .enumerate()
+            .filter(|(_, node)| node.value() == value)
+            .map(|(i, _)| i)
+            .collect();
+        if rev {
+            re.reverse();
+        }
+        re
+    }
+
+    /// 只保留下标在[start, stop]之间的值，start与stop必须在list的范围之内
+    pub fn trim(&mut self, start: usize, stop: usize) {
+        let len = self.len() as usize;
+        if stop + 1 < len {
+            if let Some(offset) = self.get_offset_index(stop + 1) {
+                let end = self.0.len() - 1;
+                self.remove_start_end(offset, end);
+            }
+        }
+        if start > 0 {
+            if let Some(offset) = self.get_offset_index(start) {
+                self.remove_start_end(ZipList::OFFSET_VALUE, offset - 1);
+            }
+        }
+        self.set_len((stop - start + 1) as LenType);
+    }
+
+    /// 把other中的值全部加到右边
+    pub fn append(&mut self, other: &ZipList) {
+        self.0.extend_from_slice(&other.0[ZipList::OFFSET_VALUE..]);
+        self.set_len(self.len() + other.len());
+    }
+
     pub fn clear(&mut self) {
         self.set_len(0);
         self.0.truncate(ZipList::LEN_INIT);
@@ -476,8 +616,9 @@ impl ZipList {
             return None;
         }
 
-        let start_in_index = start_index - offset;
-        stop_in_index = start_in_index + (stop_index - start_index);
+        // start_index可能在前面的zip中
+        let start_in_index = start_index.saturating_sub(offset);
+        stop_in_index = stop_index - offset;
         if stop_in_index >= len {
             stop_in_index = len - 1;
         }
@@ -533,7 +674,8 @@ impl<'a> ZipListIter<'a> {
 
     fn next_offset(&self) -> Option<usize> {
         if self.start_cur < self.zip_list.len() {
-            let len_value = ZipListNode::read_bytes_of_value(self.zip_list);
+            //当前node的值的长度，不是第一个node的
+            let len_value = ZipListNode::read_bytes_of_value(&self.zip_list[self.start_cur..]);
             let mut cur = self.start_cur;
             cur += len_value + ZipListNode::SIZE_NODE_TYPE * 2;
             Some(cur + ZipList::OFFSET_VALUE)
@@ -591,7 +733,7 @@ impl<'a> Iterator for ZipListIter<'a> {
 
 #[cfg(test)]
 mod test {
-    use crate::rocksdb_impl::zip_list::ZipList;
+    use crate::rocksdb_impl::zip_list::{SizeNodeType, ZipList};
 
     #[test]
     fn test_zip_list_push_left() {
@@ -712,6 +854,29 @@ mod test {
         // }
     }
 
+    #[test]
+    fn test_zip_list_set_shrink() {
+        // 变小时移动的数据超过末尾会写到vec的空间之外，所以使用较大的值
+        let big = vec![7u8; SizeNodeType::MAX as usize];
+        let mut zip = ZipList::new();
+        let mut expected = ZipList::new();
+        zip.push_right(&big);
+        expected.push_right(&[1]);
+        for _ in 0..3 {
+            zip.push_right(&big);
+            expected.push_right(&big);
+        }
+        zip.push_right(&[2, 3]);
+        expected.push_right(&[2, 3]);
+        zip.0.shrink_to_fit();
+
+        assert_eq!(big, zip.set(0, &[1]).expect(""));
+        assert_eq!(expected.0, zip.0);
+        assert_eq!(vec![2, 3], zip.set(4, &[4]).expect(""));
+        assert_eq!(&[4], zip.index(4).expect(""));
+        assert_eq!(&[1], zip.index(0).expect(""));
+    }
+
     #[test]
     fn test_zip_list_insert_left() {
         let mut zip = ZipList::new();
@@ -940,4 +1105,133 @@ mod test {
         rs = zip.range(2, 3);
         assert_eq!(vec![vec![4, 5, 6]], rs);
     }
+
+    #[test]
+    fn test_zip_list_rem_back() {
+        // 从后面删除时，node的结束位置按它自己的值的长度计算
+        let mut zip = ZipList::new();
+        zip.push_right(&[1]);
+        zip.push_right(&[2, 3, 4]);
+        zip.push_right(&[5, 6]);
+        zip.push_right(&[2, 3, 4]);
+        assert_eq!(1, zip.rem(-1, &[2, 3, 4]));
+        assert_eq!(vec![vec![1u8], vec![2, 3, 4], vec![5, 6]], zip.range(0, 10));
+
+        let mut expected = ZipList::new();
+        expected.push_right(&[1]);
+        expected.push_right(&[2, 3, 4]);
+        expected.push_right(&[5, 6]);
+        assert_eq!(expected.0, zip.0);
+
+        assert_eq!(1, zip.rem(-2, &[2, 3, 4]));
+        assert_eq!(vec![vec![1u8], vec![5, 6]], zip.range(0, 10));
+    }
+
+    #[test]
+    fn test_zip_list_count_in_index() {
+        // zip中有3个值，它们在list中的index为 5,6,7
+        assert_eq!(None, ZipList::count_in_index(3, 5, 0, 4));
+        assert_eq!(None, ZipList::count_in_index(3, 5, 8, 10));
+        assert_eq!(Some((0, 2)), ZipList::count_in_index(3, 5, 5, 7));
+        assert_eq!(Some((1, 1)), ZipList::count_in_index(3, 5, 6, 6));
+        assert_eq!(Some((1, 2)), ZipList::count_in_index(3, 5, 6, 20));
+        // start_index在前面的zip中
+        assert_eq!(Some((0, 0)), ZipList::count_in_index(3, 5, 2, 5));
+        assert_eq!(Some((0, 1)), ZipList::count_in_index(3, 5, 2, 6));
+        assert_eq!(Some((0, 2)), ZipList::count_in_index(3, 5, 0, 20));
+    }
+
+    #[test]
+    fn test_zip_list_trim() {
+        let mut zip = ZipList::new();
+        for i in 0..5u8 {
+            zip.push_right(&vec![i; i as usize + 1]);
+        }
+        zip.trim(0, 4);
+        assert_eq!(5, zip.len());
+        zip.trim(1, 3);
+        assert_eq!(3, zip.len());
+        assert_eq!(vec![vec![1u8; 2], vec![2u8; 3], vec![3u8; 4]], zip.range(0, 10));
+        zip.trim(2, 2);
+        assert_eq!(vec![vec![3u8; 4]], zip.range(0, 10));
+        assert_eq!(Some(vec![3u8; 4]), zip.pop_left());
+        assert_eq!(ZipList::new().as_ref(), zip.as_ref());
+    }
+
+    #[test]
+    fn test_zip_list_append() {
+        let mut zip = ZipList::new();
+        let mut other = ZipList::new();
+        zip.append(&other);
+        assert_eq!(ZipList::new().as_ref(), zip.as_ref());
+        for i in 0..3u8 {
+            zip.push_right(&vec![i; i as usize + 1]);
+            other.push_right(&vec![i + 3; 2]);
+        }
+        zip.append(&other);
+        assert_eq!(6, zip.len());
+        assert_eq!(Some(vec![5u8; 2]), zip.pop_right());
+        assert_eq!(vec![vec![2u8; 3], vec![3u8; 2], vec![4u8; 2]], zip.range(2, 4));
+        assert_eq!(Some(vec![0u8]), zip.pop_left());
+    }
+
+    #[test]
+    fn test_zip_list_compress() {
+        let mut zip = ZipList::new();
+        for i in 0..100u8 {
+            zip.push_right(&vec![i % 3; 16]);
+        }
+        let compressed = zip.compress();
+        assert!(compressed.len() < zip.as_ref().len());
+        let decoded = ZipList::decode(compressed).expect("");
+        assert_eq!(zip.as_ref(), decoded.as_ref());
+        let decoded = ZipList::decode(zip.as_ref().to_vec()).expect("");
+        assert_eq!(zip.as_ref(), decoded.as_ref());
+        let decoded = ZipList::decode(ZipList::new().compress()).expect("");
+        assert_eq!(ZipList::new().as_ref(), decoded.as_ref());
+        let mut broken = zip.compress();
+        broken.truncate(broken.len() - 1);
+        assert!(ZipList::decode(broken).is_err());
+    }
+
+    #[test]
+    fn test_zip_list_positions() {
+        let mut zip = ZipList::new();
+        assert_eq!(Vec::<usize>::new(), zip.positions(&[1], false));
+        for v in [[1u8], [2], [1], [3], [1]] {
+            zip.push_right(&v);
+        }
+        assert_eq!(vec![0, 2, 4], zip.positions(&[1], false));
+        assert_eq!(vec![4, 2, 0], zip.positions(&[1], true));
+        assert_eq!(vec![3], zip.positions(&[3], true));
+        assert_eq!(Vec::<usize>::new(), zip.positions(&[4], false));
+    }
+
+    #[test]
+    fn test_zip_list_cursor() {
+        let mut zip = ZipList::new();
+        assert_eq!(None, zip.value_after(zip.begin_offset()));
+        assert_eq!(None, zip.value_before(zip.end_offset()));
+        for v in [vec![1u8], vec![], vec![2, 3]] {
+            zip.push_right(&v);
+        }
+
+        let mut values = Vec::new();
+        let mut offset = zip.begin_offset();
+        while let Some((v, next)) = zip.value_after(offset) {
+            values.push(v.to_vec());
+            offset = next;
+        }
+        assert_eq!(zip.end_offset(), offset);
+        assert_eq!(vec![vec![1u8], vec![], vec![2, 3]], values);
+
+        values.clear();
+        let mut offset = zip.end_offset();
+        while let Some((v, start)) = zip.value_before(offset) {
+            values.push(v.to_vec());
+            offset = start;
+        }
+        assert_eq!(zip.begin_offset(), offset);
+        assert_eq!(vec![vec![2u8, 3], vec![], vec![1]], values);
+    }
 }
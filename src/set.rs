@@ -0,0 +1,37 @@
+use crate::{LenType, RrError};
+
+/// 无序集合，member不能为空
+pub trait Set<T> {
+    /// 对应redis的sadd，返回新添加的member个数
+    fn add(&self, t: &T, key: &[u8], members: &[&[u8]]) -> Result<LenType, RrError>;
+    /// 对应redis的srem，返回删除的member个数
+    fn rem(&self, t: &T, key: &[u8], members: &[&[u8]]) -> Result<LenType, RrError>;
+    /// 对应redis的sismember
+    fn is_member(&self, t: &T, key: &[u8], member: &[u8]) -> Result<bool, RrError>;
+    /// 对应redis的smembers，按member的字节序返回
+    fn members(&self, t: &T, key: &[u8]) -> Result<Vec<Vec<u8>>, RrError>;
+    /// 对应redis的scard，O(1)
+    fn len(&self, t: &T, key: &[u8]) -> Result<Option<LenType>, RrError>;
+    /// 对应redis的spop，随机删除并返回count个member
+    fn pop(&self, t: &T, key: &[u8], count: LenType) -> Result<Vec<Vec<u8>>, RrError>;
+    /// 对应redis的srandmember，count为正数时返回不重复的member，为负数时可能重复，个数为count的绝对值
+    fn rand_member(&self, t: &T, key: &[u8], count: i64) -> Result<Vec<Vec<u8>>, RrError>;
+    /// 对应redis的smove，把member从src移到dst，src中没有member时返回false
+    /// 在WrapTransaction中执行时是原子的
+    fn move_member(&self, t: &T, src: &[u8], dst: &[u8], member: &[u8]) -> Result<bool, RrError>;
+    /// 对应redis的sinter，从member最少的集合开始遍历，再在其它集合中查找，返回值按字节序
+    fn inter(&self, t: &T, keys: &[&[u8]]) -> Result<Vec<Vec<u8>>, RrError>;
+    /// 对应redis的sinterstore，结果存入dest(原dest会被替换)，返回结果的member个数
+    /// 先计算出结果再替换dest，所以dest可以是keys中的一个，在WrapTransaction中执行时替换是原子的
+    fn inter_store(&self, t: &T, dest: &[u8], keys: &[&[u8]]) -> Result<LenType, RrError>;
+    /// 对应redis的sunion，按字节序同时遍历所有集合(merge join)，返回值按字节序
+    fn union(&self, t: &T, keys: &[&[u8]]) -> Result<Vec<Vec<u8>>, RrError>;
+    /// 对应redis的sunionstore，参数参见[Set::inter_store]
+    fn union_store(&self, t: &T, dest: &[u8], keys: &[&[u8]]) -> Result<LenType, RrError>;
+    /// 对应redis的sdiff，第一个集合中有而其它集合中都没有的member，按字节序同时遍历所有集合(merge join)
+    fn diff(&self, t: &T, keys: &[&[u8]]) -> Result<Vec<Vec<u8>>, RrError>;
+    /// 对应redis的sdiffstore，参数参见[Set::inter_store]
+    fn diff_store(&self, t: &T, dest: &[u8], keys: &[&[u8]]) -> Result<LenType, RrError>;
+    /// 删除指定的key，及所有member
+    fn del_key(&self, t: &T, key: &[u8]) -> Result<(), RrError>;
+}
@@ -1,11 +1,97 @@
-use crate::RrError;
+use std::ops::Bound;
 
-/// 可以存储大量的数据，在遍历数据时，性能不如redis hash
-pub trait SortedSet {
-    ///
-    fn add(&mut self, key: &[u8], score: i64, v: &[u8]) -> Result<i64, RrError>;
+use crate::{LenType, RrError};
 
+/// zadd的选项，对应redis zadd的 NX/XX/GT/LT/CH
+/// nx与xx不能同时使用，gt、lt与nx也不能同时使用
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ZAddOptions {
+    /// 只添加新的member，不更新已存在的member
+    pub nx: bool,
+    /// 只更新已存在的member，不添加新的member
+    pub xx: bool,
+    /// 新的score大于原score时才更新，不影响添加新的member
+    pub gt: bool,
+    /// 新的score小于原score时才更新，不影响添加新的member
+    pub lt: bool,
+    /// 返回值为变化(新添加与score被更新)的member个数，而不只是新添加的个数
+    pub ch: bool,
+}
+
+/// 集合运算时相同member的score的聚合方式，对应redis的 AGGREGATE SUM|MIN|MAX
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Aggregate {
+    #[default]
+    Sum,
+    Min,
+    Max,
+}
+
+/// 有序集合，member按score从小到大排序，score相同时按member的字节序
+/// score为f64，不能为NaN
+pub trait SortedSet<T> {
+    /// 对应redis的zadd，返回新添加的member个数(options.ch为true时，为变化的member个数)
+    fn add(&self, t: &T, key: &[u8], members: &[(f64, &[u8])], options: ZAddOptions) -> Result<LenType, RrError>;
+    /// 对应redis的zadd INCR，返回member新的score，因为options没有执行时返回None
+    fn add_incr(&self, t: &T, key: &[u8], increment: f64, member: &[u8], options: ZAddOptions) -> Result<Option<f64>, RrError>;
+    /// 对应redis的zincrby，member不存在时，score为increment
+    fn incr_by(&self, t: &T, key: &[u8], increment: f64, member: &[u8]) -> Result<f64, RrError>;
+    /// 对应redis的zscore
+    fn score(&self, t: &T, key: &[u8], member: &[u8]) -> Result<Option<f64>, RrError>;
+    /// 对应redis的zrem，返回删除的member个数
+    fn rem(&self, t: &T, key: &[u8], members: &[&[u8]]) -> Result<LenType, RrError>;
+    /// 对应redis的zrank，从0开始
+    fn rank(&self, t: &T, key: &[u8], member: &[u8]) -> Result<Option<LenType>, RrError>;
+    /// 对应redis的zrevrank，从0开始
+    fn rev_rank(&self, t: &T, key: &[u8], member: &[u8]) -> Result<Option<LenType>, RrError>;
+    /// 对应redis的zrange，start与stop都包含在内，负数表示从后往前数(-1为最后一个)
+    /// 返回值 0: member, 1: score
+    fn range(&self, t: &T, key: &[u8], start: i64, stop: i64) -> Result<Vec<(Vec<u8>, f64)>, RrError>;
+    /// 对应redis的zrevrange，按score从大到小，参数参见[SortedSet::range]
+    fn rev_range(&self, t: &T, key: &[u8], start: i64, stop: i64) -> Result<Vec<(Vec<u8>, f64)>, RrError>;
+    /// 对应redis的zrangebyscore，limit为 (offset, count)，None时返回所有的，count为0时返回空(与redis的 LIMIT offset 0 一致)
+    /// 返回值 0: member, 1: score
+    fn range_by_score(&self, t: &T, key: &[u8], min: Bound<f64>, max: Bound<f64>, limit: Option<(LenType, LenType)>) -> Result<Vec<(Vec<u8>, f64)>, RrError>;
+    /// 对应redis的zrevrangebyscore，按score从大到小，注意参数是先max后min，limit参见[SortedSet::range_by_score]
+    fn rev_range_by_score(
+        &self,
+        t: &T,
+        key: &[u8],
+        max: Bound<f64>,
+        min: Bound<f64>,
+        limit: Option<(LenType, LenType)>,
+    ) -> Result<Vec<(Vec<u8>, f64)>, RrError>;
+    /// 对应redis的zcount
+    fn count(&self, t: &T, key: &[u8], min: Bound<f64>, max: Bound<f64>) -> Result<LenType, RrError>;
+    /// 对应redis的zrangebylex，按member的字节序返回min与max之间的member，与score无关(redis要求所有member的score相同)
+    /// min与max的格式: "[member"包含, "(member"不包含, "-"最小, "+"最大
+    /// limit参见[SortedSet::range_by_score]
+    fn range_by_lex(&self, t: &T, key: &[u8], min: &[u8], max: &[u8], limit: Option<(LenType, LenType)>) -> Result<Vec<Vec<u8>>, RrError>;
+    /// 对应redis的zlexcount，参数参见[SortedSet::range_by_lex]
+    fn lex_count(&self, t: &T, key: &[u8], min: &[u8], max: &[u8]) -> Result<LenType, RrError>;
+    /// 对应redis的zremrangebylex，返回删除的member个数，参数参见[SortedSet::range_by_lex]
+    fn rem_range_by_lex(&self, t: &T, key: &[u8], min: &[u8], max: &[u8]) -> Result<LenType, RrError>;
+    /// 对应redis的zpopmin，删除并返回score最小的count个member
+    fn pop_min(&self, t: &T, key: &[u8], count: LenType) -> Result<Vec<(Vec<u8>, f64)>, RrError>;
+    /// 对应redis的zpopmax，删除并返回score最大的count个member
+    fn pop_max(&self, t: &T, key: &[u8], count: LenType) -> Result<Vec<(Vec<u8>, f64)>, RrError>;
+    /// 对应redis的zunion WITHSCORES，weights为每个key的权重(None时都为1)，返回值按score排序
+    fn union(&self, t: &T, keys: &[&[u8]], weights: Option<&[f64]>, aggregate: Aggregate) -> Result<Vec<(Vec<u8>, f64)>, RrError>;
+    /// 对应redis的zunionstore，结果存入dest(原dest会被替换)，返回结果的member个数
+    /// 先计算出结果再替换dest，所以dest可以是keys中的一个，在WrapTransaction中执行时替换是原子的
+    fn union_store(&self, t: &T, dest: &[u8], keys: &[&[u8]], weights: Option<&[f64]>, aggregate: Aggregate) -> Result<LenType, RrError>;
+    /// 对应redis的zinter WITHSCORES，参数参见[SortedSet::union]
+    fn inter(&self, t: &T, keys: &[&[u8]], weights: Option<&[f64]>, aggregate: Aggregate) -> Result<Vec<(Vec<u8>, f64)>, RrError>;
+    /// 对应redis的zinterstore，参数参见[SortedSet::union_store]
+    fn inter_store(&self, t: &T, dest: &[u8], keys: &[&[u8]], weights: Option<&[f64]>, aggregate: Aggregate) -> Result<LenType, RrError>;
+    /// 对应redis的zdiff WITHSCORES，只保留第一个key中有而其它key中都没有的member
+    /// score为第一个key中的score乘以权重，因为只有一个score，aggregate不起作用
+    fn diff(&self, t: &T, keys: &[&[u8]], weights: Option<&[f64]>, aggregate: Aggregate) -> Result<Vec<(Vec<u8>, f64)>, RrError>;
+    /// 对应redis的zdiffstore，参数参见[SortedSet::union_store]
+    fn diff_store(&self, t: &T, dest: &[u8], keys: &[&[u8]], weights: Option<&[f64]>, aggregate: Aggregate) -> Result<LenType, RrError>;
     /// 返回集合的数量
     /// 对应redis的zcard
-    fn len(&mut self, key: &[u8]) -> Result<Option<i64>, RrError>;
+    fn len(&self, t: &T, key: &[u8]) -> Result<Option<LenType>, RrError>;
+    /// 删除指定的key，及所有member
+    fn del_key(&self, t: &T, key: &[u8]) -> Result<(), RrError>;
 }
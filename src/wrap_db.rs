@@ -6,6 +6,12 @@ use crate::RrError;
 pub trait WrapDb {
     type Db: DBAccess;
     fn get(&self, key: &[u8]) -> Result<Option<Vec<u8>>, RrError>;
+    /// 读取key，在事务中同时锁定这个key直到事务结束(其它事务的get_for_update会等待)；不是事务时与[WrapDb::get]相同
+    fn get_for_update(&self, key: &[u8]) -> Result<Option<Vec<u8>>, RrError>;
+    /// 是否是事务(如[crate::WrapTransaction])，事务中的[WrapDb::get_for_update]会锁定key
+    fn in_transaction(&self) -> bool;
+    /// 一次读取多个key，返回值的顺序与keys一致
+    fn multi_get<K: AsRef<[u8]>>(&self, keys: &[K]) -> Result<Vec<Option<Vec<u8>>>, RrError>;
     fn put(&self, key: &[u8], value: &[u8]) -> Result<(), RrError>;
     fn delete(&self, key: &[u8]) -> Result<(), RrError>;
     /// 判断key是否存在， true存在，false不存在
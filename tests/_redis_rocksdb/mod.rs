@@ -1,5 +1,9 @@
 mod kits;
 
+mod test_bptree;
 mod test_heap;
 mod test_list_impl;
 mod test_object_impl;
+mod test_set;
+mod test_sorted_set;
+mod test_stack;
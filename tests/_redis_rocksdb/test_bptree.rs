@@ -0,0 +1,201 @@
+use std::ops::Bound;
+
+use function_name::named;
+use redis_rocksdb::{BPTree, RedisRocksdb, WrapDb, WrapRocksDb, WrapTransaction, WrapTransactionDB};
+
+use crate::_redis_rocksdb::kits::{open_rocks_db, open_transaction_db};
+
+#[named]
+#[test]
+fn test_bptree() {
+    {
+        let redis_db = RedisRocksdb::new(open_transaction_db(file!(), function_name!()));
+
+        let wrap_db = WrapTransactionDB { db: redis_db.get_db() };
+        tt_bptree(&wrap_db, RedisRocksdb::bp_tree());
+
+        let trans = redis_db.get_db().transaction();
+        let wrap_trans = WrapTransaction { db: &trans };
+        tt_bptree(&wrap_trans, RedisRocksdb::bp_tree());
+        let _ = trans.rollback();
+    }
+
+    {
+        let rocks_db = open_rocks_db(file!(), function_name!());
+        let wrap_rocks_db = WrapRocksDb { db: &rocks_db };
+        tt_bptree(&wrap_rocks_db, RedisRocksdb::bp_tree());
+    }
+}
+
+/// 字段使用大端序，这样字节序与数字的大小一致
+fn field(i: u32) -> Vec<u8> {
+    i.to_be_bytes().to_vec()
+}
+
+fn tt_bptree<T: WrapDb>(db: &T, tree: impl BPTree<T>) {
+    let key = vec![0 as u8, 1, 2];
+    let value = "data".as_bytes();
+
+    let _ = tree.del_key(db, &key);
+    {
+        let re = tree.len(db, &key);
+        assert_eq!(None, re.expect(""));
+        let re = tree.get_first(db, &key);
+        assert_eq!(None, re.expect(""));
+        let re = tree.del_last(db, &key);
+        assert_eq!(None, re.expect(""));
+        let re = tree.del(db, &key, &field(1));
+        assert_eq!(None, re.expect(""));
+    }
+    {
+        let re = tree.set_exist(db, &key, &field(1), value);
+        assert_eq!((), re.expect(""));
+        let re = tree.get(db, &key, &field(1));
+        assert_eq!(None, re.expect(""));
+
+        let re = tree.set_not_exist(db, &key, &field(1), value);
+        assert_eq!((), re.expect(""));
+        let re = tree.set_not_exist(db, &key, &field(1), "other".as_bytes());
+        assert_eq!((), re.expect(""));
+        let re = tree.get(db, &key, &field(1));
+        assert_eq!(Some(value.to_vec()), re.expect(""));
+
+        let re = tree.set_exist(db, &key, &field(1), "other".as_bytes());
+        assert_eq!((), re.expect(""));
+        let re = tree.get(db, &key, &field(1));
+        assert_eq!(Some("other".as_bytes().to_vec()), re.expect(""));
+
+        let re = tree.set(db, &key, &field(0), value);
+        assert_eq!((), re.expect(""));
+        let re = tree.len(db, &key);
+        assert_eq!(Some(2), re.expect(""));
+        let re = tree.get_first(db, &key);
+        assert_eq!(Some((field(0), value.to_vec())), re.expect(""));
+        let re = tree.get_last(db, &key);
+        assert_eq!(Some((field(1), "other".as_bytes().to_vec())), re.expect(""));
+
+        let re = tree.del(db, &key, &field(1));
+        assert_eq!(Some("other".as_bytes().to_vec()), re.expect(""));
+        let re = tree.del_first(db, &key);
+        assert_eq!(Some((field(0), value.to_vec())), re.expect(""));
+        let re = tree.len(db, &key);
+        assert_eq!(None, re.expect(""));
+    }
+    {
+        // 足够多的数据，使节点分裂、借用与合并
+        const MAX_RANG: u32 = 3000;
+        let order: Vec<u32> = (0..MAX_RANG).map(|i| i * 7919 % MAX_RANG).collect();
+        for i in &order {
+            let re = tree.set(db, &key, &field(*i), &field(*i));
+            assert_eq!((), re.expect(""));
+        }
+        let re = tree.len(db, &key);
+        assert_eq!(Some(MAX_RANG as u64), re.expect(""));
+        let re = tree.get_first(db, &key);
+        assert_eq!(Some((field(0), field(0))), re.expect(""));
+        let re = tree.get_last(db, &key);
+        assert_eq!(Some((field(MAX_RANG - 1), field(MAX_RANG - 1))), re.expect(""));
+
+        // 删除奇数
+        for i in order.iter().filter(|i| *i % 2 == 1) {
+            let re = tree.del(db, &key, &field(*i));
+            assert_eq!(Some(field(*i)), re.expect(""));
+        }
+        let re = tree.len(db, &key);
+        assert_eq!(Some(MAX_RANG as u64 / 2), re.expect(""));
+
+        let (mut first, mut last) = (0, MAX_RANG - 2);
+        while first < last {
+            let re = tree.del_first(db, &key);
+            assert_eq!(Some((field(first), field(first))), re.expect(""));
+            let re = tree.del_last(db, &key);
+            assert_eq!(Some((field(last), field(last))), re.expect(""));
+            first += 2;
+            last -= 2;
+        }
+        let re = tree.len(db, &key);
+        assert_eq!(None, re.expect(""));
+    }
+    {
+        const MAX_RANG: u32 = 1000;
+        for i in 0..MAX_RANG {
+            let _ = tree.set(db, &key, &field(i * 2), &field(i));
+        }
+        let fields = |re: Vec<(Vec<u8>, Vec<u8>)>| re.into_iter().map(|(f, _)| f).collect::<Vec<Vec<u8>>>();
+
+        let re = tree.range(db, &key, Bound::Included(&field(10)), Bound::Included(&field(16)), 0);
+        assert_eq!(vec![field(10), field(12), field(14), field(16)], fields(re.expect("")));
+        let re = tree.range(db, &key, Bound::Excluded(&field(10)), Bound::Excluded(&field(16)), 0);
+        assert_eq!(vec![field(12), field(14)], fields(re.expect("")));
+        let re = tree.range(db, &key, Bound::Included(&field(11)), Bound::Unbounded, 2);
+        assert_eq!(vec![(field(12), field(6)), (field(14), field(7))], re.expect(""));
+        let re = tree.range(db, &key, Bound::Unbounded, Bound::Unbounded, 0);
+        assert_eq!((0..MAX_RANG).map(|i| field(i * 2)).collect::<Vec<_>>(), fields(re.expect("")));
+
+        let re = tree.range_rev(db, &key, Bound::Included(&field(16)), Bound::Excluded(&field(10)), 0);
+        assert_eq!(vec![field(16), field(14), field(12)], fields(re.expect("")));
+        let re = tree.range_rev(db, &key, Bound::Excluded(&field(13)), Bound::Unbounded, 3);
+        assert_eq!(vec![field(12), field(10), field(8)], fields(re.expect("")));
+        let re = tree.range_rev(db, &key, Bound::Unbounded, Bound::Unbounded, 0);
+        assert_eq!((0..MAX_RANG).rev().map(|i| field(i * 2)).collect::<Vec<_>>(), fields(re.expect("")));
+        let re = tree.range(db, &key, Bound::Included(&field(MAX_RANG * 2)), Bound::Unbounded, 0);
+        assert_eq!(Vec::<(Vec<u8>, Vec<u8>)>::new(), re.expect(""));
+
+        let re = tree.rank(db, &key, &field(0));
+        assert_eq!(Some(0), re.expect(""));
+        let re = tree.rank(db, &key, &field(1500));
+        assert_eq!(Some(750), re.expect(""));
+        let re = tree.rank(db, &key, &field(1501));
+        assert_eq!(None, re.expect(""));
+        let re = tree.select(db, &key, 750);
+        assert_eq!(Some((field(1500), field(750))), re.expect(""));
+        let re = tree.select(db, &key, MAX_RANG as u64 - 1);
+        assert_eq!(Some((field(MAX_RANG * 2 - 2), field(MAX_RANG - 1))), re.expect(""));
+        let re = tree.select(db, &key, MAX_RANG as u64);
+        assert_eq!(None, re.expect(""));
+        let re = tree.count(db, &key, Bound::Included(&field(10)), Bound::Included(&field(16)));
+        assert_eq!(4, re.expect(""));
+        let re = tree.count(db, &key, Bound::Excluded(&field(10)), Bound::Excluded(&field(17)));
+        assert_eq!(3, re.expect(""));
+        let re = tree.count(db, &key, Bound::Included(&field(1001)), Bound::Unbounded);
+        assert_eq!(499, re.expect(""));
+        let re = tree.count(db, &key, Bound::Included(&field(16)), Bound::Included(&field(10)));
+        assert_eq!(0, re.expect(""));
+
+        // 分页读取，读取过程中删除数据
+        let mut cursor = tree.cursor(&key, Bound::Unbounded, Bound::Unbounded, false);
+        let mut all = Vec::new();
+        loop {
+            let page = cursor.next_n(db, 300).expect("");
+            if let Some(last) = cursor.last_field() {
+                let _ = tree.del(db, &key, last);
+            }
+            let len = page.len();
+            all.extend(fields(page));
+            if len < 300 {
+                break;
+            }
+        }
+        assert_eq!((0..MAX_RANG).map(|i| field(i * 2)).collect::<Vec<_>>(), all);
+        // 最后一页的最后一个field(MAX_RANG * 2 - 2)已删除
+        let mut cursor = tree.cursor(&key, Bound::Unbounded, Bound::Included(&field(MAX_RANG * 2 - 8)), true);
+        let re = cursor.next(db);
+        assert_eq!(Some((field(MAX_RANG * 2 - 4), field(MAX_RANG - 2))), re.expect(""));
+        let re = cursor.next_n(db, 10);
+        assert_eq!(vec![field(MAX_RANG * 2 - 6), field(MAX_RANG * 2 - 8)], fields(re.expect("")));
+        let re = cursor.next(db);
+        assert_eq!(None, re.expect(""));
+        let _ = tree.del_key(db, &key);
+    }
+    {
+        for i in 0..500 {
+            let _ = tree.set(db, &key, &field(i), value);
+        }
+        let re = tree.del_key(db, &key);
+        assert_eq!((), re.expect(""));
+        let re = tree.len(db, &key);
+        assert_eq!(None, re.expect(""));
+        let re = tree.get(db, &key, &field(1));
+        assert_eq!(None, re.expect(""));
+    }
+}
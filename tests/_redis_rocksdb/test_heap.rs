@@ -1,10 +1,12 @@
 use std::{
     any::{Any, TypeId},
+    collections::BTreeMap,
     mem,
+    sync::Arc,
 };
 
 use function_name::named;
-use redis_rocksdb::{write_int, Heap, MaxHeap, RedisRocksdb, WrapDb, WrapRocksDb, WrapTransaction, WrapTransactionDB};
+use redis_rocksdb::{write_int, Heap, HeapOrder, MaxHeap, RedisRocksdb, WrapDb, WrapRocksDb, WrapTransaction, WrapTransactionDB};
 
 use crate::_redis_rocksdb::kits::{open_rocks_db, open_transaction_db};
 
@@ -102,6 +104,267 @@ fn tt_heap<T: WrapDb>(db: &T, heap: impl Heap<T> + 'static) {
     }
 }
 
+#[named]
+#[test]
+fn test_heap_order() {
+    let redis_db = RedisRocksdb::new(open_transaction_db(file!(), function_name!()));
+    let db = WrapTransactionDB { db: redis_db.get_db() };
+    let key = b"heap_order".to_vec();
+
+    // 长度不同的field按字节比较
+    let heap = RedisRocksdb::max_heap();
+    let _ = heap.remove_key(&db, &key);
+    for f in ["a", "bb", "c", "ab"] {
+        heap.push(&db, &key, f.as_bytes(), f.as_bytes()).expect("");
+    }
+    for f in ["c", "bb", "ab", "a"] {
+        assert_eq!(f.as_bytes().to_vec(), heap.pop(&db, &key).expect("").expect("").0);
+    }
+    let _ = heap.remove_key(&db, &key);
+
+    let heap = RedisRocksdb::min_heap_with(HeapOrder::Int);
+    for i in [7i64, -5, 300, -100, 0] {
+        heap.push(&db, &key, &i.to_le_bytes(), b"").expect("");
+    }
+    heap.push(&db, &key, &(-7i16).to_le_bytes(), b"").expect("");
+    assert!(heap.push(&db, &key, &[1, 2, 3], b"").is_err());
+    // 不能使用其它的比较方式打开
+    assert!(RedisRocksdb::min_heap_with(HeapOrder::UInt).peek(&db, &key).is_err());
+    assert!(RedisRocksdb::mix_heap().len(&db, &key).is_err());
+    assert_eq!((-100i64).to_le_bytes().to_vec(), heap.pop(&db, &key).expect("").expect("").0);
+    assert_eq!((-7i16).to_le_bytes().to_vec(), heap.pop(&db, &key).expect("").expect("").0);
+    for i in [-5i64, 0, 7, 300] {
+        assert_eq!(i.to_le_bytes().to_vec(), heap.pop(&db, &key).expect("").expect("").0);
+    }
+    let _ = heap.remove_key(&db, &key);
+
+    let heap = RedisRocksdb::max_heap_with(HeapOrder::UInt);
+    for i in [1u32, 256, 2, 65536] {
+        heap.push(&db, &key, &i.to_le_bytes(), b"").expect("");
+    }
+    for i in [65536u32, 256, 2, 1] {
+        assert_eq!(i.to_le_bytes().to_vec(), heap.pop(&db, &key).expect("").expect("").0);
+    }
+    let _ = heap.remove_key(&db, &key);
+
+    let heap = RedisRocksdb::min_heap_with(HeapOrder::Float);
+    for f in [2.25f64, -1.5, 0.0, 1e10] {
+        heap.push(&db, &key, &f.to_le_bytes(), b"").expect("");
+    }
+    for f in [-1.5f64, 0.0, 2.25, 1e10] {
+        assert_eq!(f.to_le_bytes().to_vec(), heap.pop(&db, &key).expect("").expect("").0);
+    }
+    let _ = heap.remove_key(&db, &key);
+
+    let by_len = |l: &[u8], r: &[u8]| l.len().cmp(&r.len());
+    let heap = RedisRocksdb::max_heap_with(HeapOrder::Custom {
+        id: 1,
+        compare: Arc::new(by_len),
+    });
+    for f in ["ccc", "a", "dddd", "bb"] {
+        heap.push(&db, &key, f.as_bytes(), b"").expect("");
+    }
+    assert!(RedisRocksdb::max_heap_with(HeapOrder::Custom {
+        id: 2,
+        compare: Arc::new(by_len)
+    })
+    .peek(&db, &key)
+    .is_err());
+    for f in ["dddd", "ccc", "bb", "a"] {
+        assert_eq!(f.as_bytes().to_vec(), heap.pop(&db, &key).expect("").expect("").0);
+    }
+    let _ = heap.remove_key(&db, &key);
+}
+
+#[named]
+#[test]
+fn test_heap_update() {
+    let redis_db = RedisRocksdb::new(open_transaction_db(file!(), function_name!()));
+    let db = WrapTransactionDB { db: redis_db.get_db() };
+    tt_heap_update(&db, RedisRocksdb::max_heap_with(HeapOrder::UInt), true);
+    tt_heap_update(&db, RedisRocksdb::min_heap_with(HeapOrder::UInt), false);
+}
+
+fn tt_heap_update<T: WrapDb>(db: &T, heap: impl Heap<T>, max: bool) {
+    let key = b"heap_update".to_vec();
+    let _ = heap.remove_key(db, &key);
+    let field = |i: u32| i.to_le_bytes().to_vec();
+
+    // push已存在的field时只替换value，push_nx不修改
+    assert_eq!(None, heap.remove(db, &key, &field(1)).expect(""));
+    assert!(!heap.update_priority(db, &key, &field(1), &field(2)).expect(""));
+    heap.push(db, &key, &field(1), b"a").expect("");
+    heap.push(db, &key, &field(1), b"b").expect("");
+    assert!(!heap.push_nx(db, &key, &field(1), b"c").expect(""));
+    assert!(heap.push_nx(db, &key, &field(2), b"d").expect(""));
+    assert_eq!(Some(2), heap.len(db, &key).expect(""));
+    assert!(heap.update_priority(db, &key, &field(1), &field(2)).is_err());
+    assert_eq!(Some(b"b".to_vec()), heap.remove(db, &key, &field(1)).expect(""));
+    assert!(heap.update_priority(db, &key, &field(2), &field(3)).expect(""));
+    assert_eq!((field(3), b"d".to_vec()), heap.peek(db, &key).expect("").expect(""));
+    let _ = heap.remove_key(db, &key);
+
+    // 与BTreeMap的结果比较
+    let mut model = BTreeMap::new();
+    let mut seed = 7u32;
+    let mut next = |n: u32| {
+        seed = seed.wrapping_mul(1103515245).wrapping_add(12345);
+        (seed >> 16) % n
+    };
+    for i in 0..2000u32 {
+        let f = next(500);
+        match next(4) {
+            0 | 1 => {
+                if heap.push_nx(db, &key, &field(f), &i.to_le_bytes()).expect("") {
+                    model.insert(f, i);
+                }
+            }
+            2 => assert_eq!(model.remove(&f).map(|v| v.to_le_bytes().to_vec()), heap.remove(db, &key, &field(f)).expect("")),
+            _ => {
+                let to = next(500);
+                match model.get(&f).copied() {
+                    None => assert!(!heap.update_priority(db, &key, &field(f), &field(to)).expect("")),
+                    Some(_) if f != to && model.contains_key(&to) => assert!(heap.update_priority(db, &key, &field(f), &field(to)).is_err()),
+                    Some(v) => {
+                        assert!(heap.update_priority(db, &key, &field(f), &field(to)).expect(""));
+                        model.remove(&f);
+                        model.insert(to, v);
+                    }
+                }
+            }
+        }
+        assert_eq!(Some(model.len() as u64), heap.len(db, &key).expect("").or(Some(0)));
+        let top = if max { model.iter().next_back() } else { model.iter().next() };
+        assert_eq!(top.map(|(f, v)| (field(*f), v.to_le_bytes().to_vec())), heap.peek(db, &key).expect(""));
+    }
+    while let Some((f, _)) = heap.pop(db, &key).expect("") {
+        let top = if max { model.pop_last() } else { model.pop_first() };
+        assert_eq!(field(top.expect("").0), f);
+    }
+    assert!(model.is_empty());
+    let _ = heap.remove_key(db, &key);
+}
+
+#[named]
+#[test]
+fn test_heap_batch() {
+    {
+        let redis_db = RedisRocksdb::new(open_transaction_db(file!(), function_name!()));
+        let wrap_db = WrapTransactionDB { db: redis_db.get_db() };
+        tt_heap_batch(&wrap_db, RedisRocksdb::max_heap_with(HeapOrder::Int), true);
+        tt_heap_batch(&wrap_db, RedisRocksdb::min_heap_with(HeapOrder::Int), false);
+
+        let trans = redis_db.get_db().transaction();
+        let wrap_trans = WrapTransaction { db: &trans };
+        tt_heap_batch(&wrap_trans, RedisRocksdb::max_heap_with(HeapOrder::Int), true);
+        let _ = trans.rollback();
+    }
+    {
+        let rocks_db = open_rocks_db(file!(), function_name!());
+        let wrap_rocks_db = WrapRocksDb { db: &rocks_db };
+        tt_heap_batch(&wrap_rocks_db, RedisRocksdb::min_heap_with(HeapOrder::Int), false);
+    }
+}
+
+fn tt_heap_batch<T: WrapDb>(db: &T, heap: impl Heap<T>, max: bool) {
+    let key = b"heap_batch".to_vec();
+    let _ = heap.remove_key(db, &key);
+    assert_eq!(0, heap.peek_n(db, &key, 10).expect("").len());
+    assert_eq!(0, heap.pop_n(db, &key, 10).expect("").len());
+    assert_eq!(0, heap.iter(db, &key).expect("").count());
+
+    let mut all: Vec<(Vec<u8>, Vec<u8>)> = (0..300i32)
+        .map(|i| {
+            let f = (i * 37 % 300 - 150) as i16;
+            (f.to_le_bytes().to_vec(), format!("v{}", f).into_bytes())
+        })
+        .collect();
+    for (f, v) in &all {
+        heap.push(db, &key, f, v).expect("");
+    }
+    all.sort_by_key(|(f, _)| i16::from_le_bytes([f[0], f[1]]));
+    if max {
+        all.reverse();
+    }
+
+    assert_eq!(all[..10].to_vec(), heap.peek_n(db, &key, 10).expect(""));
+    assert_eq!(all, heap.iter(db, &key).expect("").collect::<Vec<_>>());
+    assert_eq!(Some(300), heap.len(db, &key).expect(""));
+
+    assert_eq!(all[..5].to_vec(), heap.pop_n(db, &key, 5).expect(""));
+    assert_eq!(Some(295), heap.len(db, &key).expect(""));
+    // 取出的field的value已经删除
+    assert!(heap.push_nx(db, &key, &all[0].0, b"new").expect(""));
+    assert_eq!((all[0].0.clone(), b"new".to_vec()), heap.pop(db, &key).expect("").expect(""));
+
+    assert_eq!(all[5..].to_vec(), heap.pop_n(db, &key, 1000).expect(""));
+    assert_eq!(Some(0), heap.len(db, &key).expect(""));
+    assert_eq!(None, heap.peek(db, &key).expect(""));
+    let _ = heap.remove_key(db, &key);
+}
+
+#[named]
+#[test]
+fn test_heap_bounded() {
+    let redis_db = RedisRocksdb::new(open_transaction_db(file!(), function_name!()));
+    let db = WrapTransactionDB { db: redis_db.get_db() };
+    let key = b"heap_bounded".to_vec();
+    let item = |i: i32| (i.to_le_bytes().to_vec(), format!("v{}", i).into_bytes());
+
+    let heap = RedisRocksdb::max_heap_with(HeapOrder::Int);
+    let _ = heap.remove_key(&db, &key);
+    assert!(heap.push_bounded(&db, &key, &item(1).0, &item(1).1, 0).is_err());
+    for i in [5, 1, 7] {
+        assert_eq!(0, heap.push_bounded(&db, &key, &item(i).0, &item(i).1, 3).expect("").len());
+    }
+    // 超过capacity时删除最小的
+    assert_eq!(vec![item(1)], heap.push_bounded(&db, &key, &item(3).0, &item(3).1, 3).expect(""));
+    assert_eq!(None, heap.remove(&db, &key, &item(1).0).expect(""));
+    // 加入的field就是最小的，不会保存
+    assert_eq!(vec![item(0)], heap.push_bounded(&db, &key, &item(0).0, &item(0).1, 3).expect(""));
+    assert_eq!(None, heap.remove(&db, &key, &item(0).0).expect(""));
+    // 已存在的field只替换value
+    assert_eq!(0, heap.push_bounded(&db, &key, &item(5).0, b"x", 3).expect("").len());
+    assert_eq!(
+        vec![item(7), (item(5).0, b"x".to_vec()), item(3)],
+        heap.iter(&db, &key).expect("").collect::<Vec<_>>()
+    );
+    // capacity变小时删除多个
+    assert_eq!(
+        vec![item(3), (item(5).0, b"x".to_vec()), item(7)],
+        heap.push_bounded(&db, &key, &item(9).0, &item(9).1, 1).expect("")
+    );
+    assert_eq!(vec![item(9)], heap.iter(&db, &key).expect("").collect::<Vec<_>>());
+    let _ = heap.remove_key(&db, &key);
+
+    // 与排序后的前k个比较
+    for max in [true, false] {
+        let heap: Box<dyn Heap<WrapTransactionDB>> = if max {
+            Box::new(RedisRocksdb::max_heap_with(HeapOrder::Int))
+        } else {
+            Box::new(RedisRocksdb::min_heap_with(HeapOrder::Int))
+        };
+        let mut all = Vec::new();
+        let mut evicted = Vec::new();
+        for i in 0..1000 {
+            let v = i * 7919 % 1000;
+            all.push(v);
+            evicted.extend(heap.push_bounded(&db, &key, &item(v).0, &item(v).1, 100).expect(""));
+        }
+        all.sort();
+        if max {
+            all.reverse();
+        }
+        let kept: Vec<_> = all[..100].iter().map(|i| item(*i)).collect();
+        assert_eq!(kept, heap.iter(&db, &key).expect("").collect::<Vec<_>>());
+        evicted.sort();
+        let mut rest: Vec<_> = all[100..].iter().map(|i| item(*i)).collect();
+        rest.sort();
+        assert_eq!(rest, evicted);
+        let _ = heap.remove_key(&db, &key);
+    }
+}
+
 #[cfg(test)]
 mod sample {
     use redis_rocksdb::{Heap, RedisRocksdb, WrapTransactionDB};
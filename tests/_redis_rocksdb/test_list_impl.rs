@@ -1,13 +1,19 @@
+use std::{
+    thread,
+    time::{Duration, Instant},
+};
+
 use function_name::named;
-use redis_rocksdb::{RedisList, RedisRocksdb};
+use redis_rocksdb::{List, ListConfig, ListEnd, ListIter, RedisList, RedisRocksdb, RrError, WrapDb, WrapRocksDb, WrapTransaction, WrapTransactionDB};
+use rocksdb::TransactionDB;
 
-use crate::_redis_rocksdb::kits::open_transaction_db;
+use crate::_redis_rocksdb::kits::{open_rocks_db, open_transaction_db};
 
 #[named]
 #[test]
 fn test_list_lpush() {
     let db = open_transaction_db(file!(), function_name!());
-    let mut redis_db = RedisRocksdb::new(db);
+    let redis_db = RedisRocksdb::new(db);
     let key = function_name!().as_bytes();
     let value = vec![1, 23, 6];
     let _ = redis_db.list_clear(&key); //先清除数据，以便测试可以反复运行
@@ -31,7 +37,7 @@ fn test_list_lpush() {
 #[test]
 fn test_list_rpush() {
     let db = open_transaction_db(file!(), function_name!());
-    let mut redis_db = RedisRocksdb::new(db);
+    let redis_db = RedisRocksdb::new(db);
     let key = function_name!().as_bytes();
     let value = vec![1, 23, 6];
     let _ = redis_db.list_clear(&key); //先清除数据，以便测试可以反复运行
@@ -55,7 +61,7 @@ fn test_list_rpush() {
 #[test]
 fn test_list_lr_pop() {
     let db = open_transaction_db(file!(), function_name!());
-    let mut redis_db = RedisRocksdb::new(db);
+    let redis_db = RedisRocksdb::new(db);
     let key = function_name!().as_bytes();
     let value = vec![1];
     let _ = redis_db.list_clear(&key); //先清除数据，以便测试可以反复运行
@@ -101,7 +107,7 @@ fn test_list_lr_pop() {
 #[test]
 fn test_list_insert_set_rem_range() {
     let db = open_transaction_db(file!(), function_name!());
-    let mut redis_db = RedisRocksdb::new(db);
+    let redis_db = RedisRocksdb::new(db);
     let key = function_name!().as_bytes();
     let value = vec![1, 23, 6];
     let _ = redis_db.list_clear(&key); //先清除数据，以便测试可以反复运行
@@ -236,3 +242,691 @@ fn test_list_insert_set_rem_range() {
         );
     }
 }
+
+#[named]
+#[test]
+fn test_list() {
+    let compressed = ListConfig {
+        compress_depth: 1,
+        ..ListConfig::default()
+    };
+    {
+        let redis_db = RedisRocksdb::new(open_transaction_db(file!(), function_name!()));
+
+        let wrap_db = WrapTransactionDB { db: redis_db.get_db() };
+        tt_list(&wrap_db, RedisRocksdb::list());
+        // 中间的node压缩后，所有的操作结果不变
+        tt_list(&wrap_db, RedisRocksdb::list_with(compressed));
+
+        let trans = redis_db.get_db().transaction();
+        let wrap_trans = WrapTransaction { db: &trans };
+        tt_list(&wrap_trans, RedisRocksdb::list());
+        let _ = trans.rollback();
+    }
+
+    {
+        let rocks_db = open_rocks_db(file!(), function_name!());
+        let wrap_rocks_db = WrapRocksDb { db: &rocks_db };
+        tt_list(&wrap_rocks_db, RedisRocksdb::list());
+        tt_list(&wrap_rocks_db, RedisRocksdb::list_with(compressed));
+    }
+}
+
+fn tt_list<T: WrapDb>(db: &T, list: impl List<T>) {
+    let key = vec![0 as u8, 1, 2];
+    let _ = list.clear(db, &key);
+    {
+        let re = list.len(db, &key);
+        assert_eq!(-1, re.expect(""));
+        let re = list.pop_front(db, &key);
+        assert_eq!(None, re.expect(""));
+        let re = list.push_back_exists(db, &key, b"v");
+        assert_eq!(0, re.expect(""));
+        let re = list.index(db, &key, 0);
+        assert!(re.is_err());
+    }
+    {
+        // 元素多于一个node的容量，覆盖跨node的操作
+        let count = 300;
+        for i in 0..count {
+            let re = list.push_back(db, &key, format!("{}", i).as_bytes());
+            assert_eq!(i + 1, re.expect(""));
+        }
+        let re = list.push_front(db, &key, b"-1");
+        assert_eq!(count + 1, re.expect(""));
+        let re = list.len(db, &key);
+        assert_eq!(count + 1, re.expect(""));
+
+        let re = list.index(db, &key, 0);
+        assert_eq!(b"-1".to_vec(), re.expect(""));
+        let re = list.index(db, &key, 200);
+        assert_eq!(b"199".to_vec(), re.expect(""));
+        let re = list.range(db, &key, 120, 130);
+        let expected: Vec<Vec<u8>> = (119..130).map(|i| format!("{}", i).into_bytes()).collect();
+        assert_eq!(expected, re.expect(""));
+        let re = list.range(db, &key, 0, -1);
+        assert_eq!(count as usize + 1, re.expect("").len());
+
+        let re = list.set(db, &key, 150, b"x");
+        assert_eq!(b"149".to_vec(), re.expect(""));
+        let re = list.push_back(db, &key, b"x");
+        assert_eq!(count + 2, re.expect(""));
+        let re = list.rem(db, &key, -1, b"x");
+        assert_eq!(1, re.expect(""));
+        let re = list.index(db, &key, 150);
+        assert_eq!(b"x".to_vec(), re.expect(""));
+        let re = list.insert_after(db, &key, b"x", b"y");
+        assert_eq!(count + 2, re.expect(""));
+        let re = list.rem(db, &key, 0, b"y");
+        assert_eq!(1, re.expect(""));
+        let re = list.len(db, &key);
+        assert_eq!(count + 1, re.expect(""));
+
+        for i in 0..count {
+            let re = list.pop_back(db, &key);
+            let expected = if i == count - 150 {
+                b"x".to_vec()
+            } else {
+                format!("{}", count - 1 - i).into_bytes()
+            };
+            assert_eq!(Some(expected), re.expect(""));
+        }
+        let re = list.pop_front(db, &key);
+        assert_eq!(Some(b"-1".to_vec()), re.expect(""));
+        let re = list.pop_front(db, &key);
+        assert_eq!(None, re.expect(""));
+        let re = list.len(db, &key);
+        assert_eq!(0, re.expect(""));
+
+        let re = list.push_front(db, &key, b"a");
+        assert_eq!(1, re.expect(""));
+        let _ = list.clear(db, &key);
+        let re = list.len(db, &key);
+        assert_eq!(-1, re.expect(""));
+    }
+    {
+        // ltrim
+        let count = 300;
+        for i in 0..count {
+            let _ = list.push_back(db, &key, format!("{}", i).as_bytes());
+        }
+        let re = list.trim(db, &key, 0, -1);
+        assert_eq!(0, re.expect(""));
+        let re = list.trim(db, &key, 10, -11);
+        assert_eq!(20, re.expect(""));
+        let re = list.len(db, &key);
+        assert_eq!(count - 20, re.expect(""));
+        let re = list.range(db, &key, 0, -1);
+        let expected: Vec<Vec<u8>> = (10..count - 10).map(|i| format!("{}", i).into_bytes()).collect();
+        assert_eq!(expected, re.expect(""));
+        let re = list.push_front(db, &key, b"a");
+        assert_eq!(count - 19, re.expect(""));
+        let re = list.push_back(db, &key, b"z");
+        assert_eq!(count - 18, re.expect(""));
+
+        let re = list.trim(db, &key, 101, 1000);
+        assert_eq!(101, re.expect(""));
+        let re = list.trim(db, &key, -3, -2);
+        assert_eq!(count - 121, re.expect(""));
+        let re = list.range(db, &key, 0, -1);
+        assert_eq!(vec![b"288".to_vec(), b"289".to_vec()], re.expect(""));
+        let re = list.pop_back(db, &key);
+        assert_eq!(Some(b"289".to_vec()), re.expect(""));
+        let re = list.pop_front(db, &key);
+        assert_eq!(Some(b"288".to_vec()), re.expect(""));
+
+        let _ = list.push_back(db, &key, b"a");
+        let re = list.trim(db, &key, 1, 0);
+        assert_eq!(1, re.expect(""));
+        let re = list.len(db, &key);
+        assert_eq!(-1, re.expect(""));
+        let re = list.trim(db, &key, 0, -1);
+        assert_eq!(0, re.expect(""));
+    }
+    {
+        // lmove
+        let dst = vec![0 as u8, 1, 3];
+        let _ = list.clear(db, &dst);
+        let re = list.move_to(db, &key, &dst, ListEnd::Back, ListEnd::Front);
+        assert_eq!(None, re.expect(""));
+        let re = list.len(db, &dst);
+        assert_eq!(-1, re.expect(""));
+
+        for v in [b"a", b"b", b"c"] {
+            let _ = list.push_back(db, &key, v);
+        }
+        let re = list.move_to(db, &key, &dst, ListEnd::Back, ListEnd::Front);
+        assert_eq!(Some(b"c".to_vec()), re.expect(""));
+        let re = list.move_to(db, &key, &dst, ListEnd::Front, ListEnd::Back);
+        assert_eq!(Some(b"a".to_vec()), re.expect(""));
+        let re = list.range(db, &dst, 0, -1);
+        assert_eq!(vec![b"c".to_vec(), b"a".to_vec()], re.expect(""));
+        // 旋转
+        let re = list.move_to(db, &dst, &dst, ListEnd::Front, ListEnd::Back);
+        assert_eq!(Some(b"c".to_vec()), re.expect(""));
+        let re = list.range(db, &dst, 0, -1);
+        assert_eq!(vec![b"a".to_vec(), b"c".to_vec()], re.expect(""));
+        let re = list.move_to(db, &key, &dst, ListEnd::Front, ListEnd::Front);
+        assert_eq!(Some(b"b".to_vec()), re.expect(""));
+        let re = list.len(db, &key);
+        assert_eq!(0, re.expect(""));
+        let re = list.range(db, &dst, 0, -1);
+        assert_eq!(vec![b"b".to_vec(), b"a".to_vec(), b"c".to_vec()], re.expect(""));
+
+        let _ = list.clear(db, &key);
+        let _ = list.clear(db, &dst);
+    }
+    {
+        // 负数下标与lpos
+        let m = |i: i32| format!("v{}", i % 100).into_bytes();
+        for i in 0..300 {
+            let _ = list.push_back(db, &key, &m(i));
+        }
+        let re = list.index(db, &key, -1);
+        assert_eq!(m(99), re.expect(""));
+        let re = list.index(db, &key, -300);
+        assert_eq!(m(0), re.expect(""));
+        let re = list.index(db, &key, 250);
+        assert_eq!(m(50), re.expect(""));
+        let re = list.index(db, &key, -301);
+        assert!(re.is_err());
+        let re = list.index(db, &key, 300);
+        assert!(re.is_err());
+        let re = list.set(db, &key, -2, b"x");
+        assert_eq!(m(98), re.expect(""));
+        let re = list.index(db, &key, 298);
+        assert_eq!(b"x".to_vec(), re.expect(""));
+        let re = list.set(db, &key, -301, b"x");
+        assert!(re.is_err());
+
+        let v5 = m(5);
+        let re = list.pos(db, &key, &v5, 1, 1, 0);
+        assert_eq!(vec![5], re.expect(""));
+        let re = list.pos(db, &key, &v5, 1, 0, 0);
+        assert_eq!(vec![5, 105, 205], re.expect(""));
+        let re = list.pos(db, &key, &v5, 2, 0, 0);
+        assert_eq!(vec![105, 205], re.expect(""));
+        let re = list.pos(db, &key, &v5, -1, 1, 0);
+        assert_eq!(vec![205], re.expect(""));
+        let re = list.pos(db, &key, &v5, -1, 0, 0);
+        assert_eq!(vec![205, 105, 5], re.expect(""));
+        let re = list.pos(db, &key, &v5, -3, 0, 0);
+        assert_eq!(vec![5], re.expect(""));
+        let re = list.pos(db, &key, &v5, 1, 0, 106);
+        assert_eq!(vec![5, 105], re.expect(""));
+        let re = list.pos(db, &key, &v5, 1, 0, 105);
+        assert_eq!(vec![5], re.expect(""));
+        let re = list.pos(db, &key, &v5, -1, 0, 96);
+        assert_eq!(vec![205], re.expect(""));
+        let re = list.pos(db, &key, b"none", 1, 0, 0);
+        assert_eq!(Vec::<u64>::new(), re.expect(""));
+        let re = list.pos(db, &key, &v5, 0, 0, 0);
+        assert!(re.is_err());
+
+        let _ = list.clear(db, &key);
+        let re = list.pos(db, &key, &v5, 1, 0, 0);
+        assert_eq!(Vec::<u64>::new(), re.expect(""));
+    }
+    {
+        // 混合两端的push/pop、trim、insert与rem后，通过node索引的index/set/range与实际的数据一致
+        let _ = list.clear(db, &key);
+        let mut expected: Vec<Vec<u8>> = Vec::new();
+        for i in 0..400 {
+            let v = format!("{}", i).into_bytes();
+            if i % 3 == 0 {
+                list.push_front(db, &key, &v).expect("");
+                expected.insert(0, v);
+            } else {
+                list.push_back(db, &key, &v).expect("");
+                expected.push(v);
+            }
+        }
+        for _ in 0..130 {
+            assert_eq!(expected.remove(0), list.pop_front(db, &key).expect("").expect(""));
+        }
+        for _ in 0..10 {
+            assert_eq!(expected.pop(), list.pop_back(db, &key).expect(""));
+        }
+        list.trim(db, &key, 5, -8).expect("");
+        expected = expected[5..expected.len() - 7].to_vec();
+        let pivot = expected[100].clone();
+        list.insert_before(db, &key, &pivot, b"inserted").expect("");
+        expected.insert(100, b"inserted".to_vec());
+        let v7 = b"7".to_vec();
+        list.rem(db, &key, 0, &v7).expect("");
+        expected.retain(|v| v != &v7);
+        for i in (0..expected.len()).step_by(37) {
+            let v = format!("set{}", i).into_bytes();
+            assert_eq!(expected[i], list.set(db, &key, i as i32, &v).expect(""));
+            expected[i] = v;
+        }
+        for _ in 0..50 {
+            let v = b"front".to_vec();
+            list.push_front(db, &key, &v).expect("");
+            expected.insert(0, v);
+        }
+
+        assert_eq!(expected.len() as i32, list.len(db, &key).expect(""));
+        for (i, v) in expected.iter().enumerate() {
+            assert_eq!(v, &list.index(db, &key, i as i32).expect(""));
+        }
+        assert_eq!(expected, list.range(db, &key, 0, -1).expect(""));
+        assert_eq!(expected[123..=200].to_vec(), list.range(db, &key, 123, 200).expect(""));
+        let _ = list.clear(db, &key);
+    }
+    {
+        // 一次加入多个值，与逐个加入的结果一致
+        let _ = list.clear(db, &key);
+        assert_eq!(0, list.push_back_many(db, &key, &[]).expect(""));
+        assert_eq!(-1, list.len(db, &key).expect(""));
+        let values: Vec<Vec<u8>> = (0..700).map(|i| format!("{}", i).into_bytes()).collect();
+        let refs: Vec<&[u8]> = values.iter().map(|v| v.as_slice()).collect();
+        assert_eq!(3, list.push_back_many(db, &key, &refs[..3]).expect(""));
+        assert_eq!(300, list.push_back_many(db, &key, &refs[3..300]).expect(""));
+        assert_eq!(700, list.push_front_many(db, &key, &refs[300..]).expect(""));
+        let mut expected: Vec<Vec<u8>> = values[300..].iter().rev().cloned().collect();
+        expected.extend_from_slice(&values[..300]);
+        assert_eq!(expected, list.range(db, &key, 0, -1).expect(""));
+        for i in (0..700).step_by(29) {
+            assert_eq!(expected[i], list.index(db, &key, i as i32).expect(""));
+        }
+        assert_eq!(expected[0], list.pop_front(db, &key).expect("").expect(""));
+        assert_eq!(expected[699], list.pop_back(db, &key).expect("").expect(""));
+        let _ = list.clear(db, &key);
+    }
+    {
+        // 删除元素后，相邻的小node会合并
+        let _ = list.clear(db, &key);
+        let mut expected: Vec<Vec<u8>> = (0..600).map(|i| format!("{}", i % 4).into_bytes()).collect();
+        for v in &expected {
+            list.push_back(db, &key, v).expect("");
+        }
+        let nodes = list.clear(db, &key).expect("");
+        for v in &expected {
+            list.push_back(db, &key, v).expect("");
+        }
+        for v in [b"1", b"2", b"3"] {
+            assert_eq!(150, list.rem(db, &key, 0, v).expect(""));
+        }
+        expected.retain(|v| v == b"0");
+        assert_eq!(expected, list.range(db, &key, 0, -1).expect(""));
+        for i in [0, 40, 99, 149] {
+            assert_eq!(expected[i], list.index(db, &key, i as i32).expect(""));
+        }
+        assert_eq!(2, list.clear(db, &key).expect(""));
+        assert!(nodes > 2);
+
+        // 两端pop后，剩下的元素少于一个node的容量时只有一个node
+        for i in 0..300 {
+            list.push_back(db, &key, format!("{}", i % 4).as_bytes()).expect("");
+        }
+        for _ in 0..120 {
+            list.pop_front(db, &key).expect("");
+            list.pop_back(db, &key).expect("");
+        }
+        assert_eq!(60, list.len(db, &key).expect(""));
+        assert_eq!(b"0".to_vec(), list.index(db, &key, 0).expect(""));
+        assert_eq!(1, list.clear(db, &key).expect(""));
+
+        // set为更短的值后，node也会合并
+        for i in 0..300 {
+            list.push_back(db, &key, format!("{:08}", i).as_bytes()).expect("");
+        }
+        let nodes = list.clear(db, &key).expect("");
+        for i in 0..300 {
+            list.push_back(db, &key, format!("{:08}", i).as_bytes()).expect("");
+        }
+        for i in 0..300 {
+            list.set(db, &key, i, b"s").expect("");
+        }
+        assert_eq!(vec![b"s".to_vec(); 300], list.range(db, &key, 0, -1).expect(""));
+        assert_eq!(nodes / 2, list.clear(db, &key).expect(""));
+    }
+}
+
+#[named]
+#[test]
+fn test_list_blocking_pop() {
+    let db = open_transaction_db(file!(), function_name!());
+    let redis_db = RedisRocksdb::new(db);
+    let key1 = b"blocking_1".to_vec();
+    let key2 = b"blocking_2".to_vec();
+    let dst = b"blocking_dst".to_vec();
+    for k in [&key1, &key2, &dst] {
+        let _ = redis_db.list_clear(k);
+    }
+
+    {
+        let start = Instant::now();
+        let re = redis_db.list_blpop(&[&key1, &key2], 50);
+        assert_eq!(None, re.expect(""));
+        assert!(start.elapsed() >= Duration::from_millis(50));
+        let re = redis_db.list_blpop(&[&key1], -1);
+        assert!(re.is_err());
+
+        let _ = redis_db.list_push_back(&key2, &b"a".to_vec());
+        let _ = redis_db.list_push_back(&key2, &b"b".to_vec());
+        let re = redis_db.list_blpop(&[&key1, &key2], 0);
+        assert_eq!(Some((key2.clone(), b"a".to_vec())), re.expect(""));
+        let re = redis_db.list_brpop(&[&key1, &key2], 0);
+        assert_eq!(Some((key2.clone(), b"b".to_vec())), re.expect(""));
+    }
+
+    thread::scope(|s| {
+        // 先阻塞的线程先取得数据
+        let first = s.spawn(|| redis_db.list_blpop(&[&key1, &key2], 0));
+        thread::sleep(Duration::from_millis(50));
+        let second = s.spawn(|| redis_db.list_brpop(&[&key2], 0));
+        thread::sleep(Duration::from_millis(50));
+
+        let _ = redis_db.list_push_back(&key2, &b"1".to_vec());
+        assert_eq!(Some((key2.clone(), b"1".to_vec())), first.join().expect("").expect(""));
+        let _ = redis_db.list_push_front(&key2, &b"2".to_vec());
+        assert_eq!(Some((key2.clone(), b"2".to_vec())), second.join().expect("").expect(""));
+    });
+
+    thread::scope(|s| {
+        let waiter = s.spawn(|| redis_db.list_brpoplpush(&key1, &dst, 1000));
+        thread::sleep(Duration::from_millis(50));
+        let _ = redis_db.list_push_back(&key1, &b"x".to_vec());
+        assert_eq!(Some(b"x".to_vec()), waiter.join().expect("").expect(""));
+    });
+    assert_eq!(0, redis_db.list_len(&key1).expect(""));
+    assert_eq!(vec![b"x".to_vec()], redis_db.list_range(&dst, 0, -1).expect(""));
+    let re = redis_db.list_brpoplpush(&key1, &dst, 10);
+    assert_eq!(None, re.expect(""));
+}
+
+#[named]
+#[test]
+fn test_list_move() {
+    let db = open_transaction_db(file!(), function_name!());
+    let redis_db = RedisRocksdb::new(db);
+    let src = b"move_src".to_vec();
+    let dst = b"move_dst".to_vec();
+    let _ = redis_db.list_clear(&src);
+    let _ = redis_db.list_clear(&dst);
+
+    let re = redis_db.list_replace_back(&src, &dst);
+    assert_eq!(None, re.expect(""));
+    let _ = redis_db.list_push_back(&src, &b"1".to_vec());
+    let _ = redis_db.list_push_back(&src, &b"2".to_vec());
+    let re = redis_db.list_replace_back(&src, &dst);
+    assert_eq!(Some(b"2".to_vec()), re.expect(""));
+    let re = redis_db.list_move(&src, &dst, ListEnd::Front, ListEnd::Back);
+    assert_eq!(Some(b"1".to_vec()), re.expect(""));
+    assert_eq!(vec![b"2".to_vec(), b"1".to_vec()], redis_db.list_range(&dst, 0, -1).expect(""));
+
+    let re = redis_db.list_blmove(&src, &dst, ListEnd::Back, ListEnd::Back, 10);
+    assert_eq!(None, re.expect(""));
+    thread::scope(|s| {
+        let waiter = s.spawn(|| redis_db.list_blmove(&src, &dst, ListEnd::Front, ListEnd::Front, 0));
+        thread::sleep(Duration::from_millis(50));
+        let _ = redis_db.list_push_back(&src, &b"3".to_vec());
+        assert_eq!(Some(b"3".to_vec()), waiter.join().expect("").expect(""));
+    });
+    assert_eq!(vec![b"3".to_vec(), b"2".to_vec(), b"1".to_vec()], redis_db.list_range(&dst, 0, -1).expect(""));
+    assert_eq!(0, redis_db.list_len(&src).expect(""));
+}
+
+#[named]
+#[test]
+fn test_list_push_many() {
+    let db = open_transaction_db(file!(), function_name!());
+    let redis_db = RedisRocksdb::new(db);
+    let key = b"push_many".to_vec();
+    let _ = redis_db.list_clear(&key);
+
+    let values: Vec<Vec<u8>> = (0..10000).map(|i| format!("{}", i).into_bytes()).collect();
+    let refs: Vec<&Vec<u8>> = values.iter().collect();
+    assert_eq!(10000, redis_db.list_push_back_many(&key, &refs).expect(""));
+    assert_eq!(values, redis_db.list_range(&key, 0, -1).expect(""));
+    assert_eq!(10002, redis_db.list_push_front_many(&key, &[&b"a".to_vec(), &b"b".to_vec()]).expect(""));
+    assert_eq!(b"b".to_vec(), redis_db.list_index(&key, 0).expect(""));
+    assert_eq!(b"a".to_vec(), redis_db.list_index(&key, 1).expect(""));
+    assert_eq!(b"9999".to_vec(), redis_db.list_index(&key, -1).expect(""));
+    assert_eq!(b"5000".to_vec(), redis_db.list_index(&key, 5002).expect(""));
+}
+
+#[named]
+#[test]
+fn test_list_config() {
+    let db = open_transaction_db(file!(), function_name!());
+    let mut redis_db = RedisRocksdb::new(db);
+    let small = b"config_small".to_vec();
+    let normal = b"config_normal".to_vec();
+    let _ = redis_db.list_clear(&small);
+    let _ = redis_db.list_clear(&normal);
+    assert_eq!(&ListConfig::default(), redis_db.list_config());
+
+    // 元素个数超过max_len时新建node，每个node最多有 max_len + 1 个元素
+    redis_db.set_list_config(ListConfig {
+        max_len: 4,
+        max_bytes: 1024,
+        ..ListConfig::default()
+    });
+    for i in 0..20 {
+        let _ = redis_db.list_push_back(&small, &format!("{}", i).into_bytes());
+    }
+    // 已有的list使用创建时的配置
+    redis_db.set_list_config(ListConfig::default());
+    for i in 20..40 {
+        let _ = redis_db.list_push_back(&small, &format!("{}", i).into_bytes());
+        let _ = redis_db.list_push_back(&normal, &format!("{}", i).into_bytes());
+    }
+    let expected: Vec<Vec<u8>> = (0..40).map(|i| format!("{}", i).into_bytes()).collect();
+    assert_eq!(expected, redis_db.list_range(&small, 0, -1).expect(""));
+    assert_eq!(b"25".to_vec(), redis_db.list_index(&small, 25).expect(""));
+    assert_eq!(8, redis_db.list_clear(&small).expect(""));
+    assert_eq!(1, redis_db.list_clear(&normal).expect(""));
+
+    // max_bytes也限制node的大小
+    let list = RedisRocksdb::list_with(ListConfig {
+        max_len: 1000,
+        max_bytes: 64,
+        ..ListConfig::default()
+    });
+    let wrap_db = WrapTransactionDB { db: redis_db.get_db() };
+    for _ in 0..10 {
+        let _ = list.push_front(&wrap_db, &small, &[7u8; 20]);
+    }
+    assert_eq!(vec![vec![7u8; 20]; 10], list.range(&wrap_db, &small, 0, -1).expect(""));
+    assert_eq!(4, list.clear(&wrap_db, &small).expect(""));
+}
+
+#[named]
+#[test]
+fn test_list_namespace() {
+    let db = open_transaction_db(file!(), function_name!());
+    let redis_db = RedisRocksdb::new(db);
+    let wrap_db = WrapTransactionDB { db: redis_db.get_db() };
+    let list = RedisRocksdb::list_with(ListConfig {
+        max_len: 1,
+        ..ListConfig::default()
+    });
+    let key1 = b"namespace_1".to_vec();
+    let key2 = b"namespace_2".to_vec();
+    let _ = list.clear(&wrap_db, &key1);
+    let _ = list.clear(&wrap_db, &key2);
+
+    // 每个list使用自己的命名空间，node的key不会重复
+    for i in 0..4 {
+        let _ = list.push_back(&wrap_db, &key1, &[1, i]);
+        let _ = list.push_back(&wrap_db, &key2, &[2, i]);
+    }
+    let values = |k: u8| (0..4).map(|i| vec![k, i]).collect::<Vec<Vec<u8>>>();
+    assert_eq!(values(1), list.range(&wrap_db, &key1, 0, -1).expect(""));
+    assert_eq!(values(2), list.range(&wrap_db, &key2, 0, -1).expect(""));
+
+    // 每次新建node使用两个sep，sep用完后分配新的命名空间，原来的node仍然可以使用
+    for _ in 0..u16::MAX / 2 + 1 {
+        let _ = list.push_back(&wrap_db, &key1, &[9]);
+        assert_eq!(Some(vec![9]), list.pop_back(&wrap_db, &key1).expect(""));
+    }
+    for i in 4..8 {
+        let _ = list.push_back(&wrap_db, &key1, &[1, i]);
+    }
+    let expected: Vec<Vec<u8>> = (0..8).map(|i| vec![1, i]).collect();
+    assert_eq!(expected, list.range(&wrap_db, &key1, 0, -1).expect(""));
+    assert_eq!(values(2), list.range(&wrap_db, &key2, 0, -1).expect(""));
+    assert_eq!(4, list.clear(&wrap_db, &key1).expect(""));
+    assert_eq!(2, list.clear(&wrap_db, &key2).expect(""));
+}
+
+#[named]
+#[test]
+fn test_list_namespace_threads() {
+    let db = open_transaction_db(file!(), function_name!());
+    let redis_db = RedisRocksdb::new(db);
+    let counter = |db: &TransactionDB| {
+        db.get(b"__redis_rocksdb:list_namespace")
+            .expect("")
+            .map_or(0, |v| u64::from_le_bytes(v.as_slice().try_into().expect("")))
+    };
+    const THREADS: u8 = 4;
+    const LISTS: u16 = 500;
+    let key = |t: u8, i: u16| [vec![t], i.to_le_bytes().to_vec()].concat();
+    let wrap_db = WrapTransactionDB { db: redis_db.get_db() };
+    for t in 0..THREADS {
+        for i in 0..LISTS {
+            let _ = RedisRocksdb::list().clear(&wrap_db, &key(t, i));
+        }
+    }
+    let before = counter(redis_db.get_db());
+
+    // 多个线程同时新建list，每个list分配一个命名空间，不能重复
+    thread::scope(|s| {
+        for t in 0..THREADS {
+            let db = redis_db.get_db();
+            s.spawn(move || {
+                let wrap_db = WrapTransactionDB { db };
+                let list = RedisRocksdb::list();
+                for i in 0..LISTS {
+                    list.push_back(&wrap_db, &key(t, i), &key(t, i)).expect("");
+                }
+            });
+        }
+    });
+    assert_eq!(before + THREADS as u64 * LISTS as u64, counter(redis_db.get_db()));
+    for t in 0..THREADS {
+        for i in 0..LISTS {
+            assert_eq!(vec![key(t, i)], RedisRocksdb::list().range(&wrap_db, &key(t, i), 0, -1).expect(""));
+        }
+    }
+}
+
+#[named]
+#[test]
+fn test_list_namespace_transactions() {
+    let db = open_transaction_db(file!(), function_name!());
+    let redis_db = RedisRocksdb::new(db);
+    let keys: Vec<Vec<u8>> = (0..4).map(|i| format!("ns_trans_{}", i).into_bytes()).collect();
+    let wrap_db = WrapTransactionDB { db: redis_db.get_db() };
+    for key in &keys {
+        let _ = RedisRocksdb::list().clear(&wrap_db, key);
+    }
+
+    // 两个事务各新建两个list：第一个事务分配后持有计数器的行锁，第二个事务等待行锁时，第一个事务再分配不能被阻塞
+    let (tx, rx) = std::sync::mpsc::channel();
+    let start = Instant::now();
+    let (db, shared_keys) = (redis_db.get_db(), &keys);
+    thread::scope(|s| {
+        let first = s.spawn(move || {
+            let trans = db.transaction();
+            let wrap_trans = WrapTransaction { db: &trans };
+            let list = RedisRocksdb::list();
+            list.push_back(&wrap_trans, &shared_keys[0], &shared_keys[0])?;
+            let _ = tx.send(());
+            thread::sleep(Duration::from_millis(100));
+            list.push_back(&wrap_trans, &shared_keys[1], &shared_keys[1])?;
+            trans.commit()?;
+            Ok::<(), RrError>(())
+        });
+        let second = s.spawn(move || {
+            let _ = rx.recv();
+            let trans = db.transaction();
+            let wrap_trans = WrapTransaction { db: &trans };
+            let list = RedisRocksdb::list();
+            list.push_back(&wrap_trans, &shared_keys[2], &shared_keys[2])?;
+            list.push_back(&wrap_trans, &shared_keys[3], &shared_keys[3])?;
+            trans.commit()?;
+            Ok::<(), RrError>(())
+        });
+        first.join().expect("").expect("");
+        second.join().expect("").expect("");
+    });
+    // 没有等到锁超时
+    assert!(start.elapsed() < Duration::from_millis(900));
+    for key in &keys {
+        assert_eq!(vec![key.clone()], RedisRocksdb::list().range(&wrap_db, key, 0, -1).expect(""));
+    }
+    for key in &keys {
+        let _ = RedisRocksdb::list().clear(&wrap_db, key);
+    }
+}
+
+#[named]
+#[test]
+fn test_list_iter() {
+    let db = open_transaction_db(file!(), function_name!());
+    let mut redis_db = RedisRocksdb::new(db);
+    let key = b"list_iter".to_vec();
+    let _ = redis_db.list_clear(&key);
+    redis_db.set_list_config(ListConfig {
+        max_len: 4,
+        max_bytes: 1024,
+        compress_depth: 1,
+    });
+
+    let mut it = redis_db.list_iter(&b"list_iter_none".to_vec(), ListEnd::Front).expect("");
+    assert_eq!(None, it.next_value().expect(""));
+
+    let values: Vec<Vec<u8>> = (0..50).map(|i| format!("{}", i).into_bytes()).collect();
+    let refs: Vec<&Vec<u8>> = values.iter().collect();
+    assert_eq!(50, redis_db.list_push_back_many(&key, &refs).expect(""));
+
+    let mut front = redis_db.list_iter(&key, ListEnd::Front).expect("");
+    let mut back = redis_db.list_iter(&key, ListEnd::Back).expect("");
+    // 创建之后的修改不影响遍历的结果
+    assert_eq!(b"0".to_vec(), redis_db.list_pop_front(&key).expect("").expect(""));
+    assert_eq!(50, redis_db.list_push_back(&key, &b"50".to_vec()).expect(""));
+    let _ = redis_db.list_set(&key, 20, &b"x".to_vec()).expect("");
+
+    let mut forward = Vec::new();
+    while let Some(v) = front.next_value().expect("") {
+        forward.push(v.to_vec());
+    }
+    assert_eq!(values, forward);
+    assert_eq!(None, front.next_value().expect(""));
+
+    let mut backward = Vec::new();
+    while let Some(v) = back.next_value().expect("") {
+        backward.push(v.to_vec());
+    }
+    backward.reverse();
+    assert_eq!(values, backward);
+
+    let mut it = redis_db.list_iter(&key, ListEnd::Front).expect("");
+    let mut now = Vec::new();
+    while let Some(v) = it.next_value().expect("") {
+        now.push(v.to_vec());
+    }
+    assert_eq!(redis_db.list_range(&key, 0, -1).expect(""), now);
+
+    // 不使用RedisRocksdb时，可以直接在db的snapshot上遍历
+    drop(redis_db);
+    let db = open_rocks_db(file!(), function_name!());
+    let wrap = WrapRocksDb { db: &db };
+    let list = RedisRocksdb::list();
+    let _ = list.clear(&wrap, &key);
+    for v in &values {
+        let _ = list.push_back(&wrap, &key, v).expect("");
+    }
+    let mut it = ListIter::new(db.snapshot(), &key, ListEnd::Back).expect("");
+    let mut backward = Vec::new();
+    while let Some(v) = it.next_value().expect("") {
+        backward.push(v.to_vec());
+    }
+    backward.reverse();
+    assert_eq!(values, backward);
+}
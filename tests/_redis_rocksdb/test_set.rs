@@ -0,0 +1,193 @@
+use function_name::named;
+use redis_rocksdb::{RedisRocksdb, Set, WrapDb, WrapRocksDb, WrapTransaction, WrapTransactionDB};
+
+use crate::_redis_rocksdb::kits::{open_rocks_db, open_transaction_db};
+
+#[named]
+#[test]
+fn test_set() {
+    {
+        let redis_db = RedisRocksdb::new(open_transaction_db(file!(), function_name!()));
+
+        let wrap_db = WrapTransactionDB { db: redis_db.get_db() };
+        tt_set(&wrap_db, RedisRocksdb::set());
+
+        let trans = redis_db.get_db().transaction();
+        let wrap_trans = WrapTransaction { db: &trans };
+        tt_set(&wrap_trans, RedisRocksdb::set());
+        let _ = trans.rollback();
+    }
+
+    {
+        let rocks_db = open_rocks_db(file!(), function_name!());
+        let wrap_rocks_db = WrapRocksDb { db: &rocks_db };
+        tt_set(&wrap_rocks_db, RedisRocksdb::set());
+    }
+}
+
+fn m(s: &str) -> Vec<u8> {
+    s.as_bytes().to_vec()
+}
+
+fn tt_set<T: WrapDb>(db: &T, set: impl Set<T>) {
+    let key = vec![0 as u8, 1, 2];
+    let other_key = vec![0 as u8, 1, 3];
+
+    let _ = set.del_key(db, &key);
+    let _ = set.del_key(db, &other_key);
+    {
+        let re = set.len(db, &key);
+        assert_eq!(None, re.expect(""));
+        let re = set.members(db, &key);
+        assert_eq!(Vec::<Vec<u8>>::new(), re.expect(""));
+        let re = set.pop(db, &key, 1);
+        assert_eq!(Vec::<Vec<u8>>::new(), re.expect(""));
+        let re = set.rand_member(db, &key, -3);
+        assert_eq!(Vec::<Vec<u8>>::new(), re.expect(""));
+        let re = set.add(db, &key, &[b""]);
+        assert!(re.is_err());
+    }
+    {
+        let re = set.add(db, &key, &[b"c", b"a", b"b", b"a"]);
+        assert_eq!(3, re.expect(""));
+        let re = set.add(db, &key, &[b"a", b"d"]);
+        assert_eq!(1, re.expect(""));
+        let _ = set.add(db, &other_key, &[b"x"]);
+        let re = set.len(db, &key);
+        assert_eq!(Some(4), re.expect(""));
+        let re = set.members(db, &key);
+        assert_eq!(vec![m("a"), m("b"), m("c"), m("d")], re.expect(""));
+        let re = set.is_member(db, &key, b"b");
+        assert_eq!(true, re.expect(""));
+        let re = set.is_member(db, &key, b"x");
+        assert_eq!(false, re.expect(""));
+
+        let re = set.rem(db, &key, &[b"b", b"x"]);
+        assert_eq!(1, re.expect(""));
+        let re = set.len(db, &key);
+        assert_eq!(Some(3), re.expect(""));
+
+        let all = vec![m("a"), m("c"), m("d")];
+        let re = set.rand_member(db, &key, 2).expect("");
+        assert_eq!(2, re.len());
+        assert_ne!(re[0], re[1]);
+        assert!(re.iter().all(|v| all.contains(v)));
+        let mut re = set.rand_member(db, &key, 10).expect("");
+        re.sort();
+        assert_eq!(all, re);
+        let re = set.rand_member(db, &key, -10).expect("");
+        assert_eq!(10, re.len());
+        assert!(re.iter().all(|v| all.contains(v)));
+
+        let re = set.move_member(db, &key, &other_key, b"a");
+        assert_eq!(true, re.expect(""));
+        let re = set.move_member(db, &key, &other_key, b"a");
+        assert_eq!(false, re.expect(""));
+        let re = set.members(db, &other_key);
+        assert_eq!(vec![m("a"), m("x")], re.expect(""));
+        let re = set.len(db, &other_key);
+        assert_eq!(Some(2), re.expect(""));
+
+        let re = set.pop(db, &key, 1).expect("");
+        assert_eq!(1, re.len());
+        assert!(re[0] == m("c") || re[0] == m("d"));
+        let re = set.len(db, &key);
+        assert_eq!(Some(1), re.expect(""));
+        let re = set.pop(db, &key, 5);
+        assert_eq!(1, re.expect("").len());
+        let re = set.len(db, &key);
+        assert_eq!(None, re.expect(""));
+
+        let re = set.del_key(db, &other_key);
+        assert_eq!((), re.expect(""));
+        let re = set.members(db, &other_key);
+        assert_eq!(Vec::<Vec<u8>>::new(), re.expect(""));
+    }
+    {
+        // 集合运算
+        let (k1, k2, k3, dest) = (b"s1".to_vec(), b"s2".to_vec(), b"s3".to_vec(), b"sdest".to_vec());
+        for k in [&k1, &k2, &k3, &dest] {
+            let _ = set.del_key(db, k);
+        }
+        let _ = set.add(db, &k1, &[b"a", b"b", b"c", b"d"]);
+        let _ = set.add(db, &k2, &[b"c", b"d", b"e"]);
+        let _ = set.add(db, &k3, &[b"a", b"c", b"f"]);
+        let _ = set.add(db, &dest, &[b"old"]);
+
+        let re = set.inter(db, &[&k1, &k2]);
+        assert_eq!(vec![m("c"), m("d")], re.expect(""));
+        let re = set.inter(db, &[&k1, &k2, &k3]);
+        assert_eq!(vec![m("c")], re.expect(""));
+        let re = set.inter(db, &[&k1, b"s_none"]);
+        assert_eq!(Vec::<Vec<u8>>::new(), re.expect(""));
+        let re = set.union(db, &[&k1, &k2, &k3]);
+        assert_eq!(vec![m("a"), m("b"), m("c"), m("d"), m("e"), m("f")], re.expect(""));
+        let re = set.union(db, &[&k2, b"s_none"]);
+        assert_eq!(vec![m("c"), m("d"), m("e")], re.expect(""));
+        let re = set.diff(db, &[&k1, &k2]);
+        assert_eq!(vec![m("a"), m("b")], re.expect(""));
+        let re = set.diff(db, &[&k1, &k2, &k3]);
+        assert_eq!(vec![m("b")], re.expect(""));
+        let re = set.diff(db, &[&k1]);
+        assert_eq!(vec![m("a"), m("b"), m("c"), m("d")], re.expect(""));
+        let re = set.union(db, &[]);
+        assert!(re.is_err());
+
+        let re = set.union_store(db, &dest, &[&k2, &k3]);
+        assert_eq!(5, re.expect(""));
+        let re = set.members(db, &dest);
+        assert_eq!(vec![m("a"), m("c"), m("d"), m("e"), m("f")], re.expect(""));
+        // dest也是输入
+        let re = set.inter_store(db, &dest, &[&dest, &k1]);
+        assert_eq!(3, re.expect(""));
+        let re = set.members(db, &dest);
+        assert_eq!(vec![m("a"), m("c"), m("d")], re.expect(""));
+        let re = set.len(db, &dest);
+        assert_eq!(Some(3), re.expect(""));
+        let re = set.diff_store(db, &dest, &[&k2, &k1]);
+        assert_eq!(1, re.expect(""));
+        let re = set.members(db, &dest);
+        assert_eq!(vec![m("e")], re.expect(""));
+        let re = set.diff_store(db, &dest, &[&k3, &k1]);
+        assert_eq!(1, re.expect(""));
+        let re = set.inter_store(db, &dest, &[&k2, &k3, b"s_none"]);
+        assert_eq!(0, re.expect(""));
+        let re = set.len(db, &dest);
+        assert_eq!(None, re.expect(""));
+
+        for k in [&k1, &k2, &k3, &dest] {
+            let _ = set.del_key(db, k);
+        }
+    }
+    {
+        // 一个key是另一个key加上分隔符，不能读到对方的member
+        let (a, ab) = (b"a".to_vec(), b"a:__b".to_vec());
+        let _ = set.del_key(db, &a);
+        let _ = set.del_key(db, &ab);
+        let _ = set.add(db, &a, &[b"x"]);
+        let _ = set.add(db, &ab, &[b"m", b"n"]);
+
+        let re = set.members(db, &a);
+        assert_eq!(vec![m("x")], re.expect(""));
+        let re = set.inter(db, &[&a, &a]);
+        assert_eq!(vec![m("x")], re.expect(""));
+        let re = set.rand_member(db, &a, -5).expect("");
+        assert!(re.iter().all(|v| v == &m("x")));
+        let re = set.pop(db, &a, 3);
+        assert_eq!(vec![m("x")], re.expect(""));
+        let re = set.len(db, &a);
+        assert_eq!(None, re.expect(""));
+
+        let re = set.len(db, &ab);
+        assert_eq!(Some(2), re.expect(""));
+        let re = set.members(db, &ab);
+        assert_eq!(vec![m("m"), m("n")], re.expect(""));
+
+        let _ = set.add(db, &a, &[b"y"]);
+        let re = set.del_key(db, &a);
+        assert_eq!((), re.expect(""));
+        let re = set.members(db, &ab);
+        assert_eq!(vec![m("m"), m("n")], re.expect(""));
+        let _ = set.del_key(db, &ab);
+    }
+}
@@ -0,0 +1,343 @@
+use std::ops::Bound;
+
+use function_name::named;
+use redis_rocksdb::{Aggregate, RedisRocksdb, SortedSet, WrapDb, WrapRocksDb, WrapTransaction, WrapTransactionDB, ZAddOptions};
+
+use crate::_redis_rocksdb::kits::{open_rocks_db, open_transaction_db};
+
+#[named]
+#[test]
+fn test_sorted_set() {
+    {
+        let redis_db = RedisRocksdb::new(open_transaction_db(file!(), function_name!()));
+
+        let wrap_db = WrapTransactionDB { db: redis_db.get_db() };
+        tt_sorted_set(&wrap_db, RedisRocksdb::sorted_set());
+
+        let trans = redis_db.get_db().transaction();
+        let wrap_trans = WrapTransaction { db: &trans };
+        tt_sorted_set(&wrap_trans, RedisRocksdb::sorted_set());
+        let _ = trans.rollback();
+    }
+
+    {
+        let rocks_db = open_rocks_db(file!(), function_name!());
+        let wrap_rocks_db = WrapRocksDb { db: &rocks_db };
+        tt_sorted_set(&wrap_rocks_db, RedisRocksdb::sorted_set());
+    }
+}
+
+fn members(re: Vec<(Vec<u8>, f64)>) -> Vec<Vec<u8>> {
+    re.into_iter().map(|(m, _)| m).collect()
+}
+
+fn m(s: &str) -> Vec<u8> {
+    s.as_bytes().to_vec()
+}
+
+fn tt_sorted_set<T: WrapDb>(db: &T, zset: impl SortedSet<T>) {
+    let key = vec![0 as u8, 1, 2];
+    let none = ZAddOptions::default();
+
+    let _ = zset.del_key(db, &key);
+    {
+        let re = zset.len(db, &key);
+        assert_eq!(None, re.expect(""));
+        let re = zset.score(db, &key, b"a");
+        assert_eq!(None, re.expect(""));
+        let re = zset.rank(db, &key, b"a");
+        assert_eq!(None, re.expect(""));
+        let re = zset.pop_min(db, &key, 1);
+        assert_eq!(Vec::<(Vec<u8>, f64)>::new(), re.expect(""));
+        let re = zset.range(db, &key, 0, -1);
+        assert_eq!(Vec::<(Vec<u8>, f64)>::new(), re.expect(""));
+    }
+    {
+        let re = zset.add(db, &key, &[(1.0, b"a"), (2.0, b"b"), (3.0, b"c"), (-1.5, b"d")], none);
+        assert_eq!(4, re.expect(""));
+        let re = zset.add(db, &key, &[(1.0, b"a"), (2.5, b"b")], none);
+        assert_eq!(0, re.expect(""));
+        let re = zset.add(db, &key, &[(1.0, b"a"), (2.0, b"b"), (0.0, b"")], ZAddOptions { ch: true, ..none });
+        assert_eq!(2, re.expect(""));
+        let re = zset.len(db, &key);
+        assert_eq!(Some(5), re.expect(""));
+        let re = zset.range(db, &key, 0, -1);
+        assert_eq!(vec![(m("d"), -1.5), (m(""), 0.0), (m("a"), 1.0), (m("b"), 2.0), (m("c"), 3.0)], re.expect(""));
+
+        // NX: 只添加; XX: 只更新
+        let re = zset.add(db, &key, &[(9.0, b"a"), (4.0, b"e")], ZAddOptions { nx: true, ..none });
+        assert_eq!(1, re.expect(""));
+        let re = zset.score(db, &key, b"a");
+        assert_eq!(Some(1.0), re.expect(""));
+        let re = zset.add(db, &key, &[(1.1, b"a"), (5.0, b"f")], ZAddOptions { xx: true, ch: true, ..none });
+        assert_eq!(1, re.expect(""));
+        let re = zset.score(db, &key, b"a");
+        assert_eq!(Some(1.1), re.expect(""));
+        let re = zset.score(db, &key, b"f");
+        assert_eq!(None, re.expect(""));
+
+        // GT/LT: 只在score变大/变小时更新
+        let re = zset.add(db, &key, &[(1.0, b"a"), (3.5, b"c")], ZAddOptions { gt: true, ch: true, ..none });
+        assert_eq!(1, re.expect(""));
+        let re = zset.score(db, &key, b"a");
+        assert_eq!(Some(1.1), re.expect(""));
+        let re = zset.add(db, &key, &[(1.0, b"a"), (3.6, b"c")], ZAddOptions { lt: true, ch: true, ..none });
+        assert_eq!(1, re.expect(""));
+        let re = zset.score(db, &key, b"a");
+        assert_eq!(Some(1.0), re.expect(""));
+        let re = zset.score(db, &key, b"c");
+        assert_eq!(Some(3.5), re.expect(""));
+
+        // 不兼容的选项与NaN
+        let re = zset.add(db, &key, &[(1.0, b"a")], ZAddOptions { nx: true, xx: true, ..none });
+        assert!(re.is_err());
+        let re = zset.add(db, &key, &[(1.0, b"a")], ZAddOptions { gt: true, lt: true, ..none });
+        assert!(re.is_err());
+        let re = zset.add(db, &key, &[(f64::NAN, b"a")], none);
+        assert!(re.is_err());
+
+        // INCR
+        let re = zset.incr_by(db, &key, 2.0, b"a");
+        assert_eq!(3.0, re.expect(""));
+        let re = zset.incr_by(db, &key, 2.0, b"g");
+        assert_eq!(2.0, re.expect(""));
+        let re = zset.add_incr(db, &key, -1.0, b"a", ZAddOptions { gt: true, ..none });
+        assert_eq!(None, re.expect(""));
+        let re = zset.add_incr(db, &key, 1.0, b"h", ZAddOptions { xx: true, ..none });
+        assert_eq!(None, re.expect(""));
+        let re = zset.add_incr(db, &key, 1.0, b"a", ZAddOptions { gt: true, ..none });
+        assert_eq!(Some(4.0), re.expect(""));
+        let re = zset.incr_by(db, &key, f64::INFINITY, b"i");
+        assert_eq!(f64::INFINITY, re.expect(""));
+        let re = zset.incr_by(db, &key, f64::NEG_INFINITY, b"i");
+        assert!(re.is_err());
+        let re = zset.rem(db, &key, &[b"i", b"h"]);
+        assert_eq!(1, re.expect(""));
+
+        // d:-1.5 "":0 g:2 b:2 c:3.5 a:4 e:4
+        let re = zset.range(db, &key, 0, -1);
+        assert_eq!(vec![m("d"), m(""), m("b"), m("g"), m("c"), m("a"), m("e")], members(re.expect("")));
+        let re = zset.range(db, &key, -3, -2);
+        assert_eq!(vec![(m("c"), 3.5), (m("a"), 4.0)], re.expect(""));
+        let re = zset.range(db, &key, 5, 100);
+        assert_eq!(vec![m("a"), m("e")], members(re.expect("")));
+        let re = zset.range(db, &key, 3, 2);
+        assert_eq!(Vec::<(Vec<u8>, f64)>::new(), re.expect(""));
+        let re = zset.rev_range(db, &key, 0, 2);
+        assert_eq!(vec![m("e"), m("a"), m("c")], members(re.expect("")));
+        let re = zset.rev_range(db, &key, -2, -1);
+        assert_eq!(vec![(m(""), 0.0), (m("d"), -1.5)], re.expect(""));
+
+        let re = zset.rank(db, &key, b"d");
+        assert_eq!(Some(0), re.expect(""));
+        let re = zset.rank(db, &key, b"c");
+        assert_eq!(Some(4), re.expect(""));
+        let re = zset.rev_rank(db, &key, b"c");
+        assert_eq!(Some(2), re.expect(""));
+        let re = zset.rev_rank(db, &key, b"x");
+        assert_eq!(None, re.expect(""));
+
+        let re = zset.range_by_score(db, &key, Bound::Included(0.0), Bound::Included(3.5), None);
+        assert_eq!(vec![m(""), m("b"), m("g"), m("c")], members(re.expect("")));
+        let re = zset.range_by_score(db, &key, Bound::Excluded(0.0), Bound::Excluded(3.5), None);
+        assert_eq!(vec![m("b"), m("g")], members(re.expect("")));
+        let re = zset.range_by_score(db, &key, Bound::Unbounded, Bound::Unbounded, Some((2, 3)));
+        assert_eq!(vec![m("b"), m("g"), m("c")], members(re.expect("")));
+        let re = zset.range_by_score(db, &key, Bound::Excluded(-1.5), Bound::Included(4.0), Some((4, 10)));
+        assert_eq!(vec![m("a"), m("e")], members(re.expect("")));
+        let re = zset.range_by_score(db, &key, Bound::Included(5.0), Bound::Unbounded, None);
+        assert_eq!(Vec::<(Vec<u8>, f64)>::new(), re.expect(""));
+        let re = zset.range_by_score(db, &key, Bound::Excluded(f64::INFINITY), Bound::Unbounded, None);
+        assert_eq!(Vec::<(Vec<u8>, f64)>::new(), re.expect(""));
+        let re = zset.rev_range_by_score(db, &key, Bound::Included(3.5), Bound::Excluded(0.0), None);
+        assert_eq!(vec![m("c"), m("g"), m("b")], members(re.expect("")));
+        let re = zset.rev_range_by_score(db, &key, Bound::Excluded(4.0), Bound::Unbounded, Some((1, 2)));
+        assert_eq!(vec![m("g"), m("b")], members(re.expect("")));
+        let re = zset.rev_range_by_score(db, &key, Bound::Unbounded, Bound::Unbounded, Some((7, 2)));
+        assert_eq!(Vec::<(Vec<u8>, f64)>::new(), re.expect(""));
+        // 与redis的 LIMIT offset 0 一致
+        let re = zset.range_by_score(db, &key, Bound::Unbounded, Bound::Unbounded, Some((0, 0)));
+        assert_eq!(Vec::<(Vec<u8>, f64)>::new(), re.expect(""));
+        let re = zset.rev_range_by_score(db, &key, Bound::Unbounded, Bound::Unbounded, Some((1, 0)));
+        assert_eq!(Vec::<(Vec<u8>, f64)>::new(), re.expect(""));
+
+        let re = zset.count(db, &key, Bound::Included(2.0), Bound::Included(4.0));
+        assert_eq!(5, re.expect(""));
+        let re = zset.count(db, &key, Bound::Excluded(2.0), Bound::Excluded(4.0));
+        assert_eq!(1, re.expect(""));
+        let re = zset.count(db, &key, Bound::Unbounded, Bound::Unbounded);
+        assert_eq!(7, re.expect(""));
+
+        let re = zset.pop_min(db, &key, 2);
+        assert_eq!(vec![(m("d"), -1.5), (m(""), 0.0)], re.expect(""));
+        let re = zset.pop_max(db, &key, 1);
+        assert_eq!(vec![(m("e"), 4.0)], re.expect(""));
+        let re = zset.score(db, &key, b"e");
+        assert_eq!(None, re.expect(""));
+        let re = zset.len(db, &key);
+        assert_eq!(Some(4), re.expect(""));
+        let re = zset.pop_max(db, &key, 10);
+        assert_eq!(vec![m("a"), m("c"), m("g"), m("b")], members(re.expect("")));
+        let re = zset.len(db, &key);
+        assert_eq!(None, re.expect(""));
+    }
+    {
+        // lex: 所有member的score相同
+        let other_key = vec![0 as u8, 1, 3];
+        let _ = zset.add(db, &other_key, &[(0.0, b"b")], none);
+        let items: Vec<(f64, &[u8])> = vec![(0.0, b"a"), (0.0, b"b"), (0.0, b"c"), (0.0, b"d"), (0.0, b"e"), (0.0, b"f"), (0.0, b"g")];
+        let re = zset.add(db, &key, &items, none);
+        assert_eq!(7, re.expect(""));
+
+        let re = zset.range_by_lex(db, &key, b"-", b"[c", None);
+        assert_eq!(vec![m("a"), m("b"), m("c")], re.expect(""));
+        let re = zset.range_by_lex(db, &key, b"-", b"(c", None);
+        assert_eq!(vec![m("a"), m("b")], re.expect(""));
+        let re = zset.range_by_lex(db, &key, b"[aaa", b"(g", None);
+        assert_eq!(vec![m("b"), m("c"), m("d"), m("e"), m("f")], re.expect(""));
+        let re = zset.range_by_lex(db, &key, b"(b", b"+", Some((1, 2)));
+        assert_eq!(vec![m("d"), m("e")], re.expect(""));
+        let re = zset.range_by_lex(db, &key, b"-", b"+", Some((0, 0)));
+        assert_eq!(Vec::<Vec<u8>>::new(), re.expect(""));
+        let re = zset.range_by_lex(db, &key, b"+", b"-", None);
+        assert_eq!(Vec::<Vec<u8>>::new(), re.expect(""));
+        let re = zset.range_by_lex(db, &key, b"a", b"+", None);
+        assert!(re.is_err());
+
+        let re = zset.lex_count(db, &key, b"-", b"+");
+        assert_eq!(7, re.expect(""));
+        let re = zset.lex_count(db, &key, b"[b", b"[f");
+        assert_eq!(5, re.expect(""));
+        let re = zset.rem_range_by_lex(db, &key, b"(a", b"[c");
+        assert_eq!(2, re.expect(""));
+        let re = zset.range(db, &key, 0, -1);
+        assert_eq!(vec![m("a"), m("d"), m("e"), m("f"), m("g")], members(re.expect("")));
+        let re = zset.len(db, &key);
+        assert_eq!(Some(5), re.expect(""));
+        let re = zset.score(db, &other_key, b"b");
+        assert_eq!(Some(0.0), re.expect(""));
+
+        let _ = zset.del_key(db, &key);
+        let _ = zset.del_key(db, &other_key);
+    }
+    {
+        // lex: 一个key是另一个key加上分隔符，不能读到或删除对方的member
+        let (a, ax) = (b"a".to_vec(), b"a:__x".to_vec());
+        let _ = zset.del_key(db, &a);
+        let _ = zset.del_key(db, &ax);
+        let _ = zset.add(db, &a, &[(0.0, b"b"), (0.0, b"c")], none);
+        let _ = zset.add(db, &ax, &[(0.0, b"a"), (0.0, b"z")], none);
+
+        let re = zset.range_by_lex(db, &a, b"-", b"+", None);
+        assert_eq!(vec![m("b"), m("c")], re.expect(""));
+        let re = zset.lex_count(db, &a, b"-", b"+");
+        assert_eq!(2, re.expect(""));
+        let re = zset.rem_range_by_lex(db, &a, b"-", b"+");
+        assert_eq!(2, re.expect(""));
+        let re = zset.len(db, &a);
+        assert_eq!(None, re.expect(""));
+        let re = zset.range_by_lex(db, &ax, b"-", b"+", None);
+        assert_eq!(vec![m("a"), m("z")], re.expect(""));
+        let re = zset.len(db, &ax);
+        assert_eq!(Some(2), re.expect(""));
+
+        let _ = zset.del_key(db, &ax);
+    }
+    {
+        // 集合运算
+        let (k1, k2, k3, dest) = (b"z1".to_vec(), b"z2".to_vec(), b"z3".to_vec(), b"zdest".to_vec());
+        for k in [&k1, &k2, &k3, &dest] {
+            let _ = zset.del_key(db, k);
+        }
+        let _ = zset.add(db, &k1, &[(1.0, b"a"), (2.0, b"b"), (3.0, b"c")], none);
+        let _ = zset.add(db, &k2, &[(10.0, b"b"), (20.0, b"c"), (30.0, b"d")], none);
+        let _ = zset.add(db, &k3, &[(5.0, b"c")], none);
+        let _ = zset.add(db, &dest, &[(1.0, b"old")], none);
+        let keys: Vec<&[u8]> = vec![&k1, &k2];
+
+        let re = zset.union(db, &keys, None, Aggregate::Sum);
+        assert_eq!(vec![(m("a"), 1.0), (m("b"), 12.0), (m("c"), 23.0), (m("d"), 30.0)], re.expect(""));
+        let re = zset.union(db, &keys, Some(&[2.0, 0.5]), Aggregate::Max);
+        assert_eq!(vec![(m("a"), 2.0), (m("b"), 5.0), (m("c"), 10.0), (m("d"), 15.0)], re.expect(""));
+        let re = zset.inter(db, &keys, None, Aggregate::Min);
+        assert_eq!(vec![(m("b"), 2.0), (m("c"), 3.0)], re.expect(""));
+        let re = zset.inter(db, &[&k1, &k2, &k3], None, Aggregate::Sum);
+        assert_eq!(vec![(m("c"), 28.0)], re.expect(""));
+        let re = zset.diff(db, &[&k2, &k1], Some(&[2.0, 1.0]), Aggregate::Sum);
+        assert_eq!(vec![(m("d"), 60.0)], re.expect(""));
+        let re = zset.union(db, &keys, Some(&[1.0]), Aggregate::Sum);
+        assert!(re.is_err());
+        let re = zset.union(db, &[], None, Aggregate::Sum);
+        assert!(re.is_err());
+
+        let re = zset.union_store(db, &dest, &keys, None, Aggregate::Sum);
+        assert_eq!(4, re.expect(""));
+        let re = zset.score(db, &dest, b"old");
+        assert_eq!(None, re.expect(""));
+        let re = zset.range(db, &dest, 0, -1);
+        assert_eq!(vec![(m("a"), 1.0), (m("b"), 12.0), (m("c"), 23.0), (m("d"), 30.0)], re.expect(""));
+        // dest也是输入
+        let re = zset.inter_store(db, &dest, &[&dest, &k3], Some(&[1.0, -1.0]), Aggregate::Sum);
+        assert_eq!(1, re.expect(""));
+        let re = zset.range(db, &dest, 0, -1);
+        assert_eq!(vec![(m("c"), 18.0)], re.expect(""));
+        let re = zset.diff_store(db, &dest, &[&k1, &k2], None, Aggregate::Sum);
+        assert_eq!(1, re.expect(""));
+        let re = zset.range(db, &dest, 0, -1);
+        assert_eq!(vec![(m("a"), 1.0)], re.expect(""));
+        let re = zset.diff_store(db, &dest, &[&k3, &k1], None, Aggregate::Sum);
+        assert_eq!(0, re.expect(""));
+        let re = zset.len(db, &dest);
+        assert_eq!(None, re.expect(""));
+
+        for k in [&k1, &k2, &k3, &dest] {
+            let _ = zset.del_key(db, k);
+        }
+    }
+    {
+        // 足够多的数据，使b+ tree分裂
+        const MAX_RANG: u32 = 2000;
+        let names: Vec<Vec<u8>> = (0..MAX_RANG).map(|i| format!("m{}", i).into_bytes()).collect();
+        let items: Vec<(f64, &[u8])> = (0..MAX_RANG)
+            .map(|i| ((i * 7919 % MAX_RANG) as f64 / 2.0, names[i as usize].as_slice()))
+            .collect();
+        let re = zset.add(db, &key, &items, ZAddOptions::default());
+        assert_eq!(MAX_RANG as u64, re.expect(""));
+        let re = zset.count(db, &key, Bound::Included(100.0), Bound::Excluded(200.0));
+        assert_eq!(200, re.expect(""));
+        let re = zset.range(db, &key, 1000, 1000);
+        assert_eq!(vec![500.0], re.expect("").into_iter().map(|(_, s)| s).collect::<Vec<f64>>());
+        let re = zset.range_by_score(db, &key, Bound::Included(0.0), Bound::Unbounded, Some((1500, 3)));
+        assert_eq!(vec![750.0, 750.5, 751.0], re.expect("").into_iter().map(|(_, s)| s).collect::<Vec<f64>>());
+        let re = zset.rank(db, &key, &names[1]);
+        assert_eq!(Some(7919 % MAX_RANG as u64), re.expect(""));
+
+        let re = zset.del_key(db, &key);
+        assert_eq!((), re.expect(""));
+        let re = zset.len(db, &key);
+        assert_eq!(None, re.expect(""));
+        let re = zset.score(db, &key, &names[1]);
+        assert_eq!(None, re.expect(""));
+    }
+    {
+        // "a:_z"的空member与"a"的score tree的head不能是同一个key
+        let (a, az) = (b"a".to_vec(), b"a:_z".to_vec());
+        let _ = zset.del_key(db, &a);
+        let _ = zset.del_key(db, &az);
+        let _ = zset.add(db, &a, &[(1.0, b"x"), (2.0, b"y")], none);
+        let re = zset.add(db, &az, &[(5.0, b"")], none);
+        assert_eq!(1, re.expect(""));
+
+        let re = zset.len(db, &a);
+        assert_eq!(Some(2), re.expect(""));
+        let re = zset.range(db, &a, 0, -1);
+        assert_eq!(vec![(m("x"), 1.0), (m("y"), 2.0)], re.expect(""));
+        let re = zset.rank(db, &a, b"y");
+        assert_eq!(Some(1), re.expect(""));
+        let re = zset.range(db, &az, 0, -1);
+        assert_eq!(vec![(m(""), 5.0)], re.expect(""));
+
+        let _ = zset.del_key(db, &a);
+        let _ = zset.del_key(db, &az);
+    }
+}
@@ -0,0 +1,114 @@
+use function_name::named;
+use redis_rocksdb::{RedisRocksdb, Stack, WrapDb, WrapRocksDb, WrapTransaction, WrapTransactionDB};
+
+use crate::_redis_rocksdb::kits::{open_rocks_db, open_transaction_db};
+
+#[named]
+#[test]
+fn test_stack() {
+    {
+        let redis_db = RedisRocksdb::new(open_transaction_db(file!(), function_name!()));
+
+        let wrap_db = WrapTransactionDB { db: redis_db.get_db() };
+        tt_stack(&wrap_db, RedisRocksdb::stack());
+
+        let trans = redis_db.get_db().transaction();
+        let wrap_trans = WrapTransaction { db: &trans };
+        tt_stack(&wrap_trans, RedisRocksdb::stack());
+        let _ = trans.rollback();
+    }
+
+    {
+        let rocks_db = open_rocks_db(file!(), function_name!());
+        let wrap_rocks_db = WrapRocksDb { db: &rocks_db };
+        tt_stack(&wrap_rocks_db, RedisRocksdb::stack());
+    }
+}
+
+fn tt_stack<T: WrapDb>(db: &T, stack: impl Stack<T>) {
+    let key = vec![0 as u8, 1, 2];
+    let dst_key = vec![0 as u8, 1, 3];
+
+    let _ = stack.clear(db, &key);
+    let _ = stack.clear(db, &dst_key);
+    {
+        let re = stack.len(db, &key);
+        assert_eq!(-1, re.expect(""));
+        let re = stack.index(db, &key, 0);
+        assert_eq!(None, re.expect(""));
+        let re = stack.pop(db, &key);
+        assert_eq!(None, re.expect(""));
+        let re = stack.push_exists(db, &key, b"v");
+        assert_eq!(-1, re.expect(""));
+        let re = stack.set(db, &key, 0, b"v");
+        assert!(re.is_err());
+        let re = stack.clear(db, &key);
+        assert_eq!(-1, re.expect(""));
+    }
+    {
+        let re = stack.push(db, &key, b"0");
+        assert_eq!(1, re.expect(""));
+        let re = stack.pushs(db, &key, &[b"1", b"2", b"3"]);
+        assert_eq!(4, re.expect(""));
+        let re = stack.push_exists(db, &key, b"4");
+        assert_eq!(5, re.expect(""));
+        let re = stack.len(db, &key);
+        assert_eq!(5, re.expect(""));
+
+        let re = stack.index(db, &key, 0);
+        assert_eq!(Some(b"0".to_vec()), re.expect(""));
+        let re = stack.index(db, &key, -1);
+        assert_eq!(Some(b"4".to_vec()), re.expect(""));
+        let re = stack.index(db, &key, 5);
+        assert_eq!(None, re.expect(""));
+        let re = stack.index(db, &key, -6);
+        assert_eq!(None, re.expect(""));
+
+        let re = stack.range(db, &key, 1, 2);
+        assert_eq!(vec![b"1".to_vec(), b"2".to_vec()], re.expect(""));
+        let re = stack.range(db, &key, -2, 100);
+        assert_eq!(vec![b"3".to_vec(), b"4".to_vec()], re.expect(""));
+        let re = stack.range(db, &key, -100, 100);
+        assert_eq!(5, re.expect("").len());
+        let re = stack.range(db, &key, 3, 1);
+        assert_eq!(Vec::<Vec<u8>>::new(), re.expect(""));
+
+        let re = stack.set(db, &key, -2, b"33");
+        assert_eq!(b"3".to_vec(), re.expect(""));
+        let re = stack.index(db, &key, 3);
+        assert_eq!(Some(b"33".to_vec()), re.expect(""));
+        let re = stack.set(db, &key, 5, b"5");
+        assert!(re.is_err());
+
+        let re = stack.pop(db, &key);
+        assert_eq!(Some(b"4".to_vec()), re.expect(""));
+        let re = stack.pops(db, &key, 2);
+        assert_eq!(vec![b"33".to_vec(), b"2".to_vec()], re.expect(""));
+        let re = stack.len(db, &key);
+        assert_eq!(2, re.expect(""));
+
+        let re = stack.poplpush(db, &key, &dst_key);
+        assert_eq!(Some(b"1".to_vec()), re.expect(""));
+        let re = stack.index(db, &dst_key, 0);
+        assert_eq!(Some(b"1".to_vec()), re.expect(""));
+
+        let re = stack.pops(db, &key, 10);
+        assert_eq!(vec![b"0".to_vec()], re.expect(""));
+        let re = stack.len(db, &key);
+        assert_eq!(-1, re.expect(""));
+        let re = stack.poplpush(db, &key, &dst_key);
+        assert_eq!(None, re.expect(""));
+
+        let _ = stack.push(db, &key, b"0");
+        let _ = stack.push(db, &key, b"1");
+        let re = stack.pops(db, &key, u64::MAX);
+        assert_eq!(vec![b"1".to_vec(), b"0".to_vec()], re.expect(""));
+        let re = stack.len(db, &key);
+        assert_eq!(-1, re.expect(""));
+
+        let re = stack.clear(db, &dst_key);
+        assert_eq!(1, re.expect(""));
+        let re = stack.len(db, &dst_key);
+        assert_eq!(-1, re.expect(""));
+    }
+}